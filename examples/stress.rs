@@ -0,0 +1,174 @@
+//! Checks that no bytes are lost, duplicated or reordered
+//!
+//! The source produces chunks of random sizes, at random times, the
+//! consumer reads with random buffer sizes and methods, and another
+//! thread sends interrupts at random intervals. Every byte that
+//! comes out is checked against the one that was expected, and every
+//! interrupt is checked to come in order, and before any of the
+//! bytes that were produced after it was sent.
+//!
+//! Run this with `cargo run --release --example stress`.
+use std::{
+    io::{BufRead, Read},
+    sync::{
+        Arc, Mutex,
+        atomic::{
+            AtomicBool, AtomicU64,
+            Ordering::{Relaxed, SeqCst},
+        },
+    },
+    time::Duration,
+};
+
+use interrupt_read::{IoErrorExt, pair};
+
+/// How many bytes are produced on every run.
+const SIZE: u64 = 16 * 1024 * 1024;
+const RUNS: u64 = 8;
+
+fn main() {
+    for seed in 1..=RUNS {
+        let interrupts = run(seed);
+        println!("run {seed}: ok, {interrupts} interrupts observed");
+    }
+}
+
+/// Reads everything from a [`Source`], returning how many interrupts
+/// were observed
+fn run(seed: u64) -> u64 {
+    let produced = Arc::new(AtomicU64::new(0));
+    let source = Source {
+        pos: 0,
+        rng: Rng(seed),
+        produced: produced.clone(),
+    };
+    let (mut reader, interruptor) = pair(source);
+
+    // How much was produced right after each interrupt was sent, by
+    // which point the interrupt must have been observed.
+    let bounds = Arc::new(Mutex::new(Vec::new()));
+    let done = Arc::new(AtomicBool::new(false));
+    let interrupting = std::thread::spawn({
+        let (bounds, done) = (bounds.clone(), done.clone());
+        move || {
+            let mut rng = Rng(seed * 31);
+            let mut sent = 0;
+            while !done.load(Relaxed) {
+                std::thread::sleep(Duration::from_micros(rng.below(500)));
+                if interruptor.interrupt_with_reason(sent.to_string()).is_ok() {
+                    bounds.lock().unwrap().push(produced.load(SeqCst));
+                    sent += 1;
+                }
+            }
+        }
+    });
+
+    let mut rng = Rng(seed * 17);
+    let mut buf = vec![0; 64 * 1024];
+    let mut pos = 0;
+    // The interrupts observed, and where.
+    let mut observed = Vec::new();
+
+    while pos < SIZE {
+        // Bytes that were consumed even if the call returned an Err.
+        let mut consumed = Vec::new();
+        let result = match rng.below(3) {
+            0 => reader
+                .read(&mut buf[..1 + rng.below(64 * 1024) as usize])
+                .map(|len| {
+                    consumed.extend_from_slice(&buf[..len]);
+                }),
+            1 => {
+                let result = reader.fill_buf().map(|available| {
+                    let len = available.len().min(1 + rng.below(4096) as usize);
+                    consumed.extend_from_slice(&available[..len]);
+                });
+                reader.consume(consumed.len());
+                result
+            }
+            // read_until keeps what it has read so far on an interrupt.
+            _ => reader.read_until(b'\n', &mut consumed).map(|_| {}),
+        };
+
+        if !consumed.is_empty() {
+            check(pos, &consumed);
+            pos += consumed.len() as u64;
+        }
+
+        match result {
+            Ok(()) if consumed.is_empty() => panic!("unexpected end of stream at {pos}"),
+            Ok(()) => {}
+            Err(err) if err.is_interrupt() => {
+                let interrupt = err.into_interrupt().unwrap();
+                observed.push((interrupt.reason().unwrap().parse::<usize>().unwrap(), pos));
+            }
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    done.store(true, Relaxed);
+    interrupting.join().unwrap();
+
+    let bounds = bounds.lock().unwrap();
+    for (i, (sent, pos)) in observed.iter().enumerate() {
+        assert_eq!(*sent, i, "interrupt observed out of order");
+        assert!(
+            *pos <= bounds[*sent],
+            "interrupt {sent} observed at {pos}, after data produced past {}",
+            bounds[*sent]
+        );
+    }
+
+    observed.len() as u64
+}
+
+/// Panics if the bytes starting at `pos` aren't the expected ones
+fn check(pos: u64, bytes: &[u8]) {
+    for (i, byte) in bytes.iter().enumerate() {
+        let pos = pos + i as u64;
+        assert_eq!(*byte, expected(pos), "wrong byte at {pos}");
+    }
+}
+
+/// The byte that should be at `pos`
+fn expected(pos: u64) -> u8 {
+    (pos ^ (pos >> 8) ^ (pos >> 16)) as u8
+}
+
+/// Produces [`SIZE`] bytes, in chunks of random sizes, at random
+/// times
+struct Source {
+    pos: u64,
+    rng: Rng,
+    /// How many bytes were produced, before being sent.
+    produced: Arc<AtomicU64>,
+}
+
+impl Read for Source {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.rng.below(32) == 0 {
+            std::thread::sleep(Duration::from_micros(self.rng.below(200)));
+        }
+
+        let len = (1 + self.rng.below(buf.len() as u64)).min(SIZE - self.pos) as usize;
+        for byte in &mut buf[..len] {
+            *byte = expected(self.pos);
+            self.pos += 1;
+        }
+        self.produced.store(self.pos, SeqCst);
+
+        Ok(len)
+    }
+}
+
+/// A xorshift random number generator
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, max: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % max
+    }
+}
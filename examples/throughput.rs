@@ -0,0 +1,176 @@
+//! Compares the throughput of [`InterruptReader`] with [`BufReader`],
+//! and that of wrapping a `BufReader` through [`pair`]
+//!
+//! `read_to_end` is also compared to its default implementation, and
+//! to copying every chunk once by hand.
+//!
+//! Run this with `cargo run --release --example throughput`.
+//!
+//! [`InterruptReader`]: interrupt_read::InterruptReader
+//! [`BufReader`]: std::io::BufReader
+//! [`pair`]: interrupt_read::pair
+use std::{
+    io::{BufRead, BufReader, Cursor, Read},
+    time::{Duration, Instant},
+};
+
+use interrupt_read::{Builder, InterruptCheck};
+
+/// How many bytes are read on every run.
+const SIZE: usize = 64 * 1024 * 1024;
+/// How many bytes are read one at a time, which is a lot slower.
+const TINY_SIZE: usize = 4 * 1024 * 1024;
+const RUNS: u32 = 5;
+
+fn main() {
+    let buf_reader = time(SIZE, |source| {
+        let mut reader = BufReader::new(source);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap()
+    });
+
+    let interrupt_reader = time(SIZE, |source| {
+        let (mut reader, _interruptor) = interrupt_read::pair(source);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap()
+    });
+
+    // Goes through the default implementation of read_to_end, which
+    // copies everything through read.
+    let default_read_to_end = time(SIZE, |source| {
+        let (reader, _interruptor) = interrupt_read::pair(source);
+        let mut out = Vec::new();
+        OnlyRead(reader).read_to_end(&mut out).unwrap()
+    });
+
+    // What read_to_end does, one copy per chunk, without the chunks
+    // getting bigger.
+    let per_chunk_copy = time(SIZE, |source| {
+        let (mut reader, _interruptor) = interrupt_read::pair(source);
+        let mut out = Vec::new();
+        loop {
+            let chunk = reader.fill_buf().unwrap();
+            if chunk.is_empty() {
+                break out.len();
+            }
+            let len = chunk.len();
+            out.extend_from_slice(chunk);
+            reader.consume(len);
+        }
+    });
+
+    let big_reads = time(SIZE, |source| {
+        let (mut reader, _interruptor) = interrupt_read::pair(source);
+        let mut buf = vec![0; 1024 * 1024];
+        read_all(&mut reader, &mut buf)
+    });
+
+    // A lot of small chunks, so handing the buffers back and forth
+    // between the worker and the reader is most of the work.
+    // Through read_to_end, the worker's buffer would grow instead.
+    let small_chunks = time(SIZE, |source| {
+        let (mut reader, _interruptor) = Builder::new().buffer_size(512).pair(source);
+        read_all(&mut reader, &mut [0; 512])
+    });
+
+    let small_reads = time(SIZE, |source| {
+        let (mut reader, _interruptor) = interrupt_read::pair(source);
+        read_all(&mut reader, &mut [0; 512])
+    });
+
+    // Stands in for a BufReader<File>, without the disk getting in the
+    // way.
+    let wrapped = time(SIZE, |source| {
+        let (mut reader, _interruptor) = interrupt_read::pair(BufReader::new(source));
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap()
+    });
+
+    let tiny_reads = |check| {
+        time(TINY_SIZE, move |source| {
+            let (mut reader, _interruptor) = Builder::new().interrupt_check(check).pair(source);
+            read_all(&mut reader, &mut [0; 1])
+        })
+    };
+    let every_call = tiny_reads(InterruptCheck::EveryCall);
+    let per_chunk = tiny_reads(InterruptCheck::PerChunk);
+    let every_1024 = tiny_reads(InterruptCheck::EveryNBytes(1024));
+    let without_interruptors = time(TINY_SIZE, |source| {
+        let (mut reader, _) = Builder::new()
+            .skip_checks_without_interruptors(true)
+            .pair(source);
+        read_all(&mut reader, &mut [0; 1])
+    });
+
+    let bytes = time(TINY_SIZE, |source| {
+        let (reader, _interruptor) = interrupt_read::pair(source);
+        reader.bytes().map(Result::unwrap).count()
+    });
+
+    let bytes_fast = time(TINY_SIZE, |source| {
+        let (mut reader, _interruptor) = interrupt_read::pair(source);
+        reader.bytes_fast().map(Result::unwrap).count()
+    });
+
+    report("BufReader::read_to_end", SIZE, buf_reader);
+    report("InterruptReader::read_to_end", SIZE, interrupt_reader);
+    report(
+        "InterruptReader, default read_to_end",
+        SIZE,
+        default_read_to_end,
+    );
+    report("InterruptReader, one copy per chunk", SIZE, per_chunk_copy);
+    report("InterruptReader::read (1 MiB)", SIZE, big_reads);
+    report("InterruptReader::read (512 bytes)", SIZE, small_reads);
+    report("InterruptReader, 512 byte chunks", SIZE, small_chunks);
+    report("pair(BufReader)", SIZE, wrapped);
+    report("1 byte reads, EveryCall", TINY_SIZE, every_call);
+    report("1 byte reads, PerChunk", TINY_SIZE, per_chunk);
+    report("1 byte reads, EveryNBytes(1024)", TINY_SIZE, every_1024);
+    report(
+        "1 byte reads, without Interruptors",
+        TINY_SIZE,
+        without_interruptors,
+    );
+    report("Read::bytes", TINY_SIZE, bytes);
+    report("InterruptReader::bytes_fast", TINY_SIZE, bytes_fast);
+}
+
+/// Only implements [`Read::read`], so the default implementations of
+/// everything else are used
+struct OnlyRead<R>(R);
+
+impl<R: Read> Read for OnlyRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+/// Reads everything with calls to [`Read::read`]
+fn read_all(reader: &mut impl Read, buf: &mut [u8]) -> usize {
+    let mut len = 0;
+    loop {
+        match reader.read(buf).unwrap() {
+            0 => break len,
+            n => len += n,
+        }
+    }
+}
+
+/// The average time it takes to read everything from a source
+fn time(size: usize, mut f: impl FnMut(Cursor<Vec<u8>>) -> usize) -> Duration {
+    let mut total = Duration::ZERO;
+    for _ in 0..RUNS {
+        let source = Cursor::new(vec![b'a'; size]);
+        let instant = Instant::now();
+        assert_eq!(f(source), size);
+        total += instant.elapsed();
+    }
+
+    total / RUNS
+}
+
+fn report(name: &str, size: usize, duration: Duration) {
+    let throughput = size as f64 / duration.as_secs_f64() / (1024.0 * 1024.0);
+    println!("{name:<38} {duration:>10.2?} ({throughput:.0} MiB/s)");
+}
@@ -0,0 +1,243 @@
+//! Reading the same stream from multiple [`InterruptReader`]s
+//!
+//! Sometimes, the same stream has to be consumed by more than one
+//! component, like the stdout of a child process that is both shown
+//! in a live UI and indexed in the background. The [`pair`] function
+//! from this module spawns a thread that reads from the source and
+//! hands every chunk to a number of subscribers, each of which is a
+//! regular [`InterruptReader`] that reads at its own pace.
+//!
+//! # Backpressure
+//!
+//! Every subscriber has a bounded queue of [`BACKLOG`] chunks. When
+//! the queue of a subscriber is full, the thread reading from the
+//! source blocks until that subscriber catches up, so the slowest
+//! subscriber sets the pace for all of them, and memory usage stays
+//! bounded. Dropping a subscriber removes it from the broadcast, so
+//! a subscriber that is no longer needed should be dropped, rather
+//! than left unread.
+//!
+//! This also means that the subscribers have to be read at the same
+//! time (e.g. from different threads). Reading one of them to the end
+//! before starting on the next one will block forever, if the stream
+//! doesn't fit in the backlog.
+use std::{
+    io::{self, Read},
+    sync::{Arc, mpsc},
+};
+
+use crate::{InterruptReader, InterruptSendError, Interruptor, checked_len};
+
+/// How many chunks can be queued for each subscriber, before the
+/// source stops being read from
+pub const BACKLOG: usize = 16;
+
+/// Returns `consumers` [`InterruptReader`]s over the same stream,
+/// and a [`BroadcastInterruptor`] for all of them
+///
+/// Every subscriber receives the exact same bytes, with the end of
+/// the stream and any errors from the `reader` included. Chunks are
+/// shared between subscribers, so they are only copied when they are
+/// read out of each `InterruptReader`. Check out the [module level
+/// documentation] for how the subscribers affect one another.
+///
+/// Each subscriber can be interrupted on its own by its own
+/// [`Interruptor`] (through [`BroadcastInterruptor::subscribers`]),
+/// or they can all be interrupted at once by
+/// [`BroadcastInterruptor::interrupt`].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::io::{BufRead, Read, Write};
+///
+/// use interrupt_read::{broadcast, is_interrupt};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+/// let (mut subscribers, interruptor) = broadcast::pair(pipe_reader, 2);
+/// let mut indexer = subscribers.pop().unwrap();
+/// let mut ui = subscribers.pop().unwrap();
+///
+/// pipe_writer.write_all(b"hello\n")?;
+///
+/// let mut line = String::new();
+/// ui.read_line(&mut line)?;
+/// indexer.read_line(&mut line)?;
+/// assert_eq!(line, "hello\nhello\n");
+///
+/// // The interrupt reaches both of them.
+/// interruptor.interrupt()?;
+/// assert!(is_interrupt(&ui.fill_buf().unwrap_err()));
+/// assert!(is_interrupt(&indexer.fill_buf().unwrap_err()));
+///
+/// let data: Vec<u8> = (0..=u8::MAX).cycle().take(256 * 1024).collect();
+/// let writer = std::thread::spawn({
+///     let data = data.clone();
+///     move || pipe_writer.write_all(&data)
+/// });
+///
+/// // One of them reads slowly, in small increments.
+/// let slow = std::thread::spawn(move || {
+///     let mut out = Vec::new();
+///     let mut buf = [0; 100];
+///     loop {
+///         match indexer.read(&mut buf)? {
+///             0 => break std::io::Result::Ok(out),
+///             len => out.extend_from_slice(&buf[..len]),
+///         }
+///     }
+/// });
+///
+/// let mut fast = Vec::new();
+/// ui.read_to_end(&mut fast)?;
+///
+/// writer.join().unwrap()?;
+/// assert_eq!(fast, data);
+/// assert_eq!(slow.join().unwrap()?, data);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Dropping a subscriber doesn't affect the others:
+///
+/// ```rust
+/// use std::io::{Cursor, Read};
+///
+/// use interrupt_read::broadcast;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // Much more than what fits in the backlog.
+/// let data = vec![b'a'; 4 * 1024 * 1024];
+/// let (mut subscribers, _interruptor) = broadcast::pair(Cursor::new(data.clone()), 3);
+/// drop(subscribers.remove(1));
+///
+/// // They have to be read at the same time, since they share a source.
+/// let join_handles: Vec<_> = subscribers
+///     .into_iter()
+///     .map(|mut subscriber| {
+///         std::thread::spawn(move || {
+///             let mut out = Vec::new();
+///             subscriber.read_to_end(&mut out).map(|_| out)
+///         })
+///     })
+///     .collect();
+///
+/// for join_handle in join_handles {
+///     assert_eq!(join_handle.join().unwrap()?, data);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [module level documentation]: self
+pub fn pair<R: Read + Send + 'static>(
+    mut reader: R,
+    consumers: usize,
+) -> (Vec<InterruptReader<Subscription>>, BroadcastInterruptor) {
+    let (senders, pairs): (Vec<_>, Vec<_>) = (0..consumers)
+        .map(|_| {
+            let (chunk_tx, chunk_rx) = mpsc::sync_channel(BACKLOG);
+            let subscription = Subscription { chunk_rx, chunk: None, pos: 0 };
+            (chunk_tx, crate::pair(subscription))
+        })
+        .unzip();
+
+    std::thread::spawn(move || {
+        let mut senders = senders;
+        let mut buf = vec![0; 8 * 1024];
+
+        while !senders.is_empty() {
+            let chunk = match checked_len(reader.read(&mut buf), buf.len()) {
+                Ok(0) => break,
+                Ok(len) => Ok(Arc::from(&buf[..len])),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => Err((err.kind(), err.to_string())),
+            };
+
+            // Subscribers that were dropped are removed here.
+            senders.retain(|chunk_tx| chunk_tx.send(chunk.clone()).is_ok());
+        }
+    });
+
+    let (subscribers, interruptors) = pairs.into_iter().unzip();
+
+    (subscribers, BroadcastInterruptor(interruptors))
+}
+
+/// An interruptor for every subscriber of a broadcast
+///
+/// This is acquired through [`broadcast::pair`].
+///
+/// [`broadcast::pair`]: pair
+#[derive(Debug, Clone)]
+pub struct BroadcastInterruptor(Vec<Interruptor>);
+
+impl BroadcastInterruptor {
+    /// Interrupts every subscriber
+    ///
+    /// Subscribers that were dropped are skipped. This returns an
+    /// [`Err`] only if every one of them has been dropped.
+    pub fn interrupt(&self) -> Result<(), InterruptSendError> {
+        let mut result = Err(InterruptSendError);
+        for interruptor in self.0.iter() {
+            if interruptor.interrupt().is_ok() {
+                result = Ok(());
+            }
+        }
+
+        result
+    }
+
+    /// The [`Interruptor`]s of each subscriber, in the same order as
+    /// they were returned by [`broadcast::pair`]
+    ///
+    /// [`broadcast::pair`]: pair
+    pub fn subscribers(&self) -> &[Interruptor] {
+        &self.0
+    }
+}
+
+/// The source of a subscriber of a broadcast
+///
+/// This is what the [`InterruptReader`]s returned by
+/// [`broadcast::pair`] read from.
+///
+/// [`broadcast::pair`]: pair
+#[derive(Debug)]
+pub struct Subscription {
+    chunk_rx: mpsc::Receiver<Chunk>,
+    chunk: Option<Arc<[u8]>>,
+    pos: usize,
+}
+
+impl Read for Subscription {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(chunk) = self.chunk.as_ref() {
+                let len = (chunk.len() - self.pos).min(buf.len());
+                buf[..len].copy_from_slice(&chunk[self.pos..self.pos + len]);
+                self.pos += len;
+                if self.pos == chunk.len() {
+                    self.chunk = None;
+                }
+
+                break Ok(len);
+            }
+
+            match self.chunk_rx.recv() {
+                Ok(Ok(chunk)) => {
+                    self.chunk = Some(chunk);
+                    self.pos = 0;
+                }
+                Ok(Err((kind, msg))) => break Err(io::Error::new(kind, msg)),
+                // The end of the stream.
+                Err(_) => break Ok(0),
+            }
+        }
+    }
+}
+
+/// A chunk of the stream, or an error, which can't be cloned, so it
+/// is sent in pieces
+type Chunk = Result<Arc<[u8]>, (io::ErrorKind, String)>;
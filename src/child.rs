@@ -0,0 +1,515 @@
+//! Interruptable reading from a [`Child`] process
+//!
+//! A very common use of this crate is to read from the stdout of a
+//! child process, while still being able to stop reading without
+//! necessarily waiting for the child to print something. This
+//! module takes care of the boilerplate involved in doing that,
+//! including killing the child when you're done with it.
+//!
+//! When both the stdout and the stderr of the child are needed,
+//! [`outputs`] reads from both at once, under a single
+//! [`Interruptor`], while keeping track of which one each chunk came
+//! from.
+use std::{
+    collections::VecDeque,
+    io::{self, ErrorKind, Read},
+    process::{Child, ChildStdout, ExitStatus},
+    sync::{Arc, atomic::Ordering::Relaxed, mpsc},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use crate::{
+    Builder, Chunk, Event, Interrupt, InterruptReceived, Interruptor, ReaderHandle, SourceReader,
+    Wait, recv_event, try_recv_event,
+};
+
+/// The reader returned by [`pair`], which keeps a duplicate of the
+/// stdout of the child
+#[cfg(unix)]
+pub type ChildStdoutReader = SourceReader<ChildStdout, std::os::fd::OwnedFd>;
+
+/// The reader returned by [`pair`], which keeps a duplicate of the
+/// stdout of the child
+#[cfg(windows)]
+pub type ChildStdoutReader = SourceReader<ChildStdout, std::os::windows::io::OwnedHandle>;
+
+/// How often to check if the child exited, once both of its pipes
+/// have reached their end.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Returns an [`InterruptReader`] over the stdout of a [`Child`]
+///
+/// Along with the usual [`Interruptor`], this also returns a
+/// [`ChildGuard`], which will kill and wait for the child when
+/// dropped.
+///
+/// Killing the child closes its stdout, which is also what makes the
+/// worker thread that reads from it stop, so by dropping the
+/// `ChildGuard`, you make sure that no thread is left behind waiting
+/// on a `read` that will never return.
+///
+/// The reader is a [`ChildStdoutReader`], which also keeps a
+/// duplicate of the stdout, for things like registering it somewhere
+/// (see [`SourceReader`]).
+///
+/// This function returns an [`Err`] if the stdout of the child
+/// wasn't piped (i.e. it wasn't spawned with
+/// [`Command::stdout`]`(`[`Stdio::piped`]`())`), or if it was
+/// already taken.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::{
+///     io::BufRead,
+///     process::{Command, Stdio},
+/// };
+///
+/// use interrupt_read::child;
+///
+/// # fn main() -> std::io::Result<()> {
+/// // Prints "hello\n" forever.
+/// let child = Command::new("bash")
+///     .args(["-c", r#"while true; do echo "hello"; sleep 0.1; done"#])
+///     .stdout(Stdio::piped())
+///     .spawn()?;
+///
+/// let (mut stdout, _interruptor, guard) = child::pair(child)?;
+///
+/// let mut line = String::new();
+/// stdout.read_line(&mut line)?;
+/// assert_eq!(line, "hello\n");
+///
+/// // Kills the child, so the stdout will reach the end.
+/// drop(guard);
+///
+/// loop {
+///     line.clear();
+///     if stdout.read_line(&mut line)? == 0 {
+///         break;
+///     }
+/// }
+///
+/// // The worker thread winds down right away.
+/// let _stdout = stdout.into_inner().unwrap();
+/// # Ok(())
+/// # }
+/// ```
+///
+/// If the stdout wasn't piped, you get an error:
+///
+/// ```rust
+/// use std::process::Command;
+///
+/// use interrupt_read::child;
+///
+/// let child = Command::new("true").spawn().unwrap();
+/// assert!(child::pair(child).is_err());
+/// ```
+///
+/// [`Command::stdout`]: std::process::Command::stdout
+/// [`Stdio::piped`]: std::process::Stdio::piped
+/// [`InterruptReader`]: crate::InterruptReader
+pub fn pair(mut child: Child) -> io::Result<(ChildStdoutReader, Interruptor, ChildGuard)> {
+    let Some(stdout) = child.stdout.take() else {
+        // Prevents the child from being left behind.
+        drop(ChildGuard(Some(child)));
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the stdout of the child process was not piped",
+        ));
+    };
+
+    #[cfg(unix)]
+    let duplicate = |stdout: &ChildStdout| std::os::fd::AsFd::as_fd(stdout).try_clone_to_owned();
+    #[cfg(windows)]
+    let duplicate = |stdout: &ChildStdout| {
+        std::os::windows::io::AsHandle::as_handle(stdout).try_clone_to_owned()
+    };
+
+    let handle = duplicate(&stdout)?;
+    let (reader, interruptor) = crate::pair(stdout);
+
+    Ok((
+        SourceReader::new(reader, handle, duplicate),
+        interruptor,
+        ChildGuard(Some(child)),
+    ))
+}
+
+/// A guard that kills a [`Child`] when dropped
+///
+/// After killing the child, it will also wait for it, so no zombie
+/// process is left behind.
+///
+/// If you want the child to outlive this guard, you can call
+/// [`ChildGuard::disarm`].
+#[derive(Debug)]
+pub struct ChildGuard(Option<Child>);
+
+impl ChildGuard {
+    /// Calls [`Child::try_wait`] on the child
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.child().try_wait()
+    }
+
+    /// Calls [`Child::wait`] on the child
+    ///
+    /// Note that, since the stdout of the child is being read by the
+    /// [`InterruptReader`], this won't deadlock, as long as
+    /// something is consuming the output.
+    ///
+    /// [`InterruptReader`]: crate::InterruptReader
+    pub fn wait(&mut self) -> io::Result<ExitStatus> {
+        self.child().wait()
+    }
+
+    /// The OS assigned process identifier of the child
+    pub fn id(&self) -> u32 {
+        self.0.as_ref().unwrap().id()
+    }
+
+    /// Returns the [`Child`], without killing it
+    pub fn disarm(mut self) -> Child {
+        self.0.take().unwrap()
+    }
+
+    fn child(&mut self) -> &mut Child {
+        self.0.as_mut().unwrap()
+    }
+}
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        if let Some(child) = self.0.as_mut() {
+            _ = child.kill();
+            _ = child.wait();
+        }
+    }
+}
+
+/// Reads from both the stdout and the stderr of a [`Child`], under a
+/// single [`Interruptor`]
+///
+/// Each pipe is read from its own [`InterruptReader`], and the chunks
+/// of both are handed out by [`ChildOutputReader::next_output`],
+/// tagged with the pipe they came from, in the order that they were
+/// read. Once both pipes have reached their end, which they can do at
+/// very different times, [`OutputEvent::Exited`] is returned, as soon
+/// as the child exits.
+///
+/// The child is borrowed, not owned, so it can still be killed or
+/// waited for through [`ChildOutputReader::child`].
+///
+/// This function returns an [`Err`] if either the stdout or the
+/// stderr of the child wasn't piped, or was already taken, in which
+/// case the child is left as it was.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::process::{Command, Stdio};
+///
+/// use interrupt_read::{
+///     child::{self, OutputEvent},
+///     is_interrupt,
+/// };
+///
+/// # fn main() -> std::io::Result<()> {
+/// // The stderr is closed long before the stdout is.
+/// let mut child = Command::new("bash")
+///     .args([
+///         "-c",
+///         "echo out1; echo err1 >&2; echo out2; echo err2 >&2; exec 2>&-; sleep 0.2; echo out3",
+///     ])
+///     .stdout(Stdio::piped())
+///     .stderr(Stdio::piped())
+///     .spawn()?;
+///
+/// let (mut outputs, interruptor) = child::outputs(&mut child)?;
+///
+/// let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+/// let mut interrupted = false;
+/// let status = loop {
+///     match outputs.next_output() {
+///         Ok(OutputEvent::Stdout(chunk)) => stdout.extend_from_slice(&chunk),
+///         Ok(OutputEvent::Stderr(chunk)) => stderr.extend_from_slice(&chunk),
+///         Ok(OutputEvent::Exited(status)) => break status,
+///         Err(err) if is_interrupt(&err) => interrupted = true,
+///         Err(err) => return Err(err),
+///     }
+///
+///     // Interrupts the reader in the middle of the stream.
+///     if stdout.starts_with(b"out1") && !interrupted {
+///         interruptor.interrupt().unwrap();
+///     }
+/// };
+///
+/// assert!(interrupted);
+/// assert_eq!(stdout, b"out1\nout2\nout3\n");
+/// assert_eq!(stderr, b"err1\nerr2\n");
+/// assert!(status.success());
+/// // Both worker threads wound down once the pipes reached their end.
+/// assert!(outputs.is_finished());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// If either of the pipes wasn't piped, you get an error:
+///
+/// ```rust
+/// use std::process::{Command, Stdio};
+///
+/// use interrupt_read::child;
+///
+/// let mut child = Command::new("true").stdout(Stdio::piped()).spawn().unwrap();
+/// assert!(child::outputs(&mut child).is_err());
+/// assert!(child.stdout.is_some());
+/// # child.wait().unwrap();
+/// ```
+///
+/// [`InterruptReader`]: crate::InterruptReader
+pub fn outputs(child: &mut Child) -> io::Result<(ChildOutputReader<'_>, Interruptor)> {
+    let (stdout, stderr) = match (child.stdout.take(), child.stderr.take()) {
+        (Some(stdout), Some(stderr)) => (stdout, stderr),
+        (stdout, stderr) => {
+            child.stdout = stdout;
+            child.stderr = stderr;
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "the stdout and stderr of the child process were not both piped",
+            ));
+        }
+    };
+
+    let builder = Builder::new();
+    let (event_tx, event_rx) = mpsc::channel();
+    let shared = Arc::new(builder.shared(builder.buffer_size, None));
+
+    let relays = vec![
+        spawn_relay(stdout, OutputEvent::Stdout, event_tx.clone()),
+        spawn_relay(stderr, OutputEvent::Stderr, event_tx.clone()),
+    ];
+
+    let reader = ChildOutputReader {
+        child,
+        event_rx,
+        shared: ReaderHandle(shared.clone()),
+        pending: VecDeque::new(),
+        open: relays.len(),
+        relays,
+        delivered: 0,
+    };
+
+    Ok((reader, Interruptor::new(event_tx, shared)))
+}
+
+/// Reads from both the stdout and the stderr of a [`Child`]
+///
+/// This is acquired through [`outputs`], check out its documentation
+/// for more information.
+#[derive(Debug)]
+pub struct ChildOutputReader<'a> {
+    child: &'a mut Child,
+    event_rx: mpsc::Receiver<Event>,
+    shared: ReaderHandle,
+    /// What arrived from the relay threads, but wasn't returned yet.
+    pending: VecDeque<Relayed>,
+    /// How many pipes haven't reached their end yet.
+    open: usize,
+    relays: Vec<JoinHandle<()>>,
+    /// Bytes delivered to the consumer since creation.
+    delivered: u64,
+}
+
+impl ChildOutputReader<'_> {
+    /// Returns the next chunk from either pipe, or the exit of the
+    /// child
+    ///
+    /// This blocks until one of those arrives, or until an interrupt
+    /// does, in which case the usual error is returned, so
+    /// [`is_interrupt`] works on it. Like with an
+    /// [`InterruptReader`], interrupts take precedence over the
+    /// chunks that already arrived, which are returned
+    /// afterwards.
+    ///
+    /// The chunks of each pipe come in the order that they were read,
+    /// and errors from either of them are returned as they come.
+    /// Once both pipes have reached their end, this waits for the
+    /// child to exit, and then returns [`OutputEvent::Exited`], as
+    /// will every call after that.
+    ///
+    /// [`is_interrupt`]: crate::is_interrupt
+    /// [`InterruptReader`]: crate::InterruptReader
+    pub fn next_output(&mut self) -> io::Result<OutputEvent> {
+        // Interrupts take precedence over what already arrived.
+        self.take_events(Wait::Never)?;
+
+        loop {
+            match self.pending.pop_front() {
+                Some(Relayed::Output(output)) => {
+                    let (OutputEvent::Stdout(chunk) | OutputEvent::Stderr(chunk)) = &output else {
+                        unreachable!("only chunks are relayed");
+                    };
+                    self.delivered += chunk.len() as u64;
+                    return Ok(output);
+                }
+                Some(Relayed::Err(err)) => return Err(err),
+                Some(Relayed::Eof) => self.open -= 1,
+                None if self.open > 0 => self.take_events(Wait::Block)?,
+                None => match self.child.try_wait()? {
+                    Some(status) => {
+                        // They only have to return, after sending their ends.
+                        for relay in self.relays.drain(..) {
+                            _ = relay.join();
+                        }
+                        return Ok(OutputEvent::Exited(status));
+                    }
+                    None => self.take_events(Wait::Until(Some(
+                        self.shared.clock.now() + EXIT_POLL_INTERVAL,
+                    )))?,
+                },
+            }
+        }
+    }
+
+    /// The [`Child`] whose pipes are being read from
+    ///
+    /// Killing it makes both pipes reach their end, which winds down
+    /// the worker threads.
+    pub fn child(&mut self) -> &mut Child {
+        self.child
+    }
+
+    /// Wether the worker threads of both pipes have wound down
+    ///
+    /// This is always the case once [`OutputEvent::Exited`] has been
+    /// returned.
+    pub fn is_finished(&self) -> bool {
+        self.relays.iter().all(JoinHandle::is_finished)
+    }
+
+    /// Takes in the events that arrived, waiting for one first, as
+    /// told by `wait`
+    ///
+    /// Interrupts are returned as an [`Err`] right away, while other
+    /// events are put in `pending`.
+    fn take_events(&mut self, wait: Wait) -> io::Result<()> {
+        match self.shared.persistent.load(Relaxed) {
+            0 => {}
+            seq => {
+                return Err(self.interrupted(Interrupt {
+                    seq,
+                    if_waiting: None,
+                    reason: None,
+                    label: None,
+                    kind: ErrorKind::Other,
+                    claim: None,
+                }));
+            }
+        }
+
+        let deadline = match wait {
+            Wait::Block => None,
+            Wait::Until(deadline) => deadline,
+            Wait::Never => Some(self.shared.clock.now()),
+        };
+
+        if !matches!(wait, Wait::Never) {
+            match recv_event(&self.event_rx, &self.shared, deadline) {
+                Ok(event) => self.take_event(event)?,
+                Err(mpsc::RecvTimeoutError::Timeout) => return Ok(()),
+                // Only once the relay threads and Interruptors are gone.
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    if let Some(deadline) = deadline {
+                        self.shared.clock.wait_until(deadline);
+                    }
+                }
+            }
+        }
+
+        while let Ok(event) = try_recv_event(&self.event_rx, &self.shared) {
+            self.take_event(event)?;
+        }
+
+        Ok(())
+    }
+
+    fn take_event(&mut self, event: Event) -> io::Result<()> {
+        match event {
+            Event::Interrupt(interrupt) => Err(self.interrupted(interrupt)),
+            Event::Relayed(relayed) => {
+                self.pending.push_back(relayed);
+                Ok(())
+            }
+            _ => unreachable!("only sent to an InterruptReader"),
+        }
+    }
+
+    /// Observes an interrupt, returning the [`Error`] for it
+    ///
+    /// [`Error`]: io::Error
+    fn interrupted(&mut self, interrupt: Interrupt) -> io::Error {
+        if self.shared.observe(interrupt.seq) {
+            self.pending
+                .retain(|relayed| !matches!(relayed, Relayed::Output(_)));
+        }
+
+        io::Error::new(interrupt.kind, InterruptReceived {
+            reason: interrupt.reason,
+            label: interrupt.label,
+            bytes_delivered: self.delivered,
+        })
+    }
+}
+
+/// What was returned by [`ChildOutputReader::next_output`]
+#[derive(Debug)]
+pub enum OutputEvent {
+    /// A chunk read from the stdout of the child.
+    Stdout(Chunk),
+    /// A chunk read from the stderr of the child.
+    Stderr(Chunk),
+    /// Both pipes reached their end, and the child exited.
+    Exited(ExitStatus),
+}
+
+/// What the relay thread of a pipe sends to a [`ChildOutputReader`]
+#[derive(Debug)]
+pub(crate) enum Relayed {
+    Output(OutputEvent),
+    Err(io::Error),
+    /// The pipe reached its end, and its worker thread is gone.
+    Eof,
+}
+
+/// Spawns a thread that relays the chunks of a pipe, until it
+/// reaches its end or the [`ChildOutputReader`] is dropped
+fn spawn_relay<R: Read + Send + 'static>(
+    pipe: R,
+    output: fn(Chunk) -> OutputEvent,
+    event_tx: mpsc::Sender<Event>,
+) -> JoinHandle<()> {
+    let (mut reader, _) = crate::pair(pipe);
+
+    std::thread::spawn(move || {
+        loop {
+            let relayed = match reader.next_chunk() {
+                Ok(Some(chunk)) => Relayed::Output(output(chunk)),
+                Ok(None) => break,
+                Err(err) => Relayed::Err(err),
+            };
+
+            if event_tx.send(Event::Relayed(relayed)).is_err() {
+                return;
+            } else if reader.is_poisoned() {
+                break;
+            }
+        }
+
+        // Waits for the worker thread, which is done reading.
+        _ = reader.into_inner();
+        _ = event_tx.send(Event::Relayed(Relayed::Eof));
+    })
+}
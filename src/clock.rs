@@ -0,0 +1,109 @@
+//! Where the time comes from, for everything that waits
+//!
+//! Timeouts, deadlines, idle detection, throttling, stall watchdogs
+//! and the like all get the time from a [`Clock`], and wait on it.
+//! By default, that is the [`SystemClock`], but [`Builder::clock`]
+//! replaces it for a pair, most commonly with a [`MockClock`], which
+//! only moves when it is told to. That way, tests of code that
+//! relies on those features don't have to sleep, and don't depend on
+//! how fast the machine running them is.
+//!
+//! Deadlines that are passed in, like the one of
+//! [`InterruptReader::read_line_deadline`], should then come from
+//! [`Clock::now`] as well.
+//!
+//! Since the thread that is reading is the one stuck waiting, the
+//! clock has to be moved from another one, once the wait has begun.
+//! [`MockClock::advance_when_waiting`] does that.
+//!
+//! # Examples
+//!
+//! Testing a function that gives up after a second of silence,
+//! without waiting for a second:
+//!
+//! ```rust
+//! use std::{
+//!     io::{BufRead, Read, Write},
+//!     sync::Arc,
+//!     time::Duration,
+//! };
+//!
+//! use interrupt_read::{Builder, InterruptReader, testing::MockClock};
+//!
+//! /// Reads everything that arrives until the source goes quiet.
+//! fn read_burst(reader: &mut InterruptReader<impl Read>) -> std::io::Result<Vec<u8>> {
+//!     let mut out = Vec::new();
+//!     reader.read_until_idle(&mut out, Duration::from_secs(1))?;
+//!     Ok(out)
+//! }
+//!
+//! # fn main() -> std::io::Result<()> {
+//! let clock = Arc::new(MockClock::new());
+//! let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+//! let (mut reader, _interruptor) = Builder::new().clock(clock.clone()).pair(pipe_reader);
+//! let watch = reader.state_watch();
+//!
+//! pipe_writer.write_all(b"hello")?;
+//! assert_eq!(reader.fill_buf()?, b"hello");
+//!
+//! let join_handle = std::thread::spawn(move || read_burst(&mut reader));
+//!
+//! // Once it is waiting for more, the second passes all at once.
+//! clock.advance_when_waiting(&watch, Duration::from_secs(1));
+//!
+//! assert_eq!(join_handle.join().unwrap()?, b"hello");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Builder::clock`]: crate::Builder::clock
+//! [`MockClock`]: crate::testing::MockClock
+//! [`MockClock::advance_when_waiting`]: crate::testing::MockClock::advance_when_waiting
+//! [`InterruptReader::read_line_deadline`]: crate::InterruptReader::read_line_deadline
+use std::time::{Duration, Instant};
+
+/// A source of time, for everything in an [`InterruptReader`] that
+/// waits
+///
+/// Check out the [module level documentation] for more information.
+///
+/// [`InterruptReader`]: crate::InterruptReader
+/// [module level documentation]: self
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    /// The current time
+    fn now(&self) -> Instant;
+
+    /// Blocks the current thread until `deadline` is reached
+    fn wait_until(&self, deadline: Instant);
+
+    /// How long to block on something else before checking if
+    /// `deadline` was reached
+    ///
+    /// Most waits aren't just for a deadline, but also for something
+    /// that can end them early, like data arriving, or an interrupt.
+    /// Those block for at most this long, and then check
+    /// [`Clock::now`], until `deadline` is reached, so a clock that
+    /// doesn't follow real time should return something short, in
+    /// order to wake them up soon after it moves past `deadline`.
+    ///
+    /// By default, this is how long until `deadline`.
+    fn timeout_until(&self, deadline: Instant) -> Duration {
+        deadline.saturating_duration_since(self.now())
+    }
+}
+
+/// The [`Clock`] that follows real time, through [`Instant::now`]
+///
+/// This is the default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wait_until(&self, deadline: Instant) {
+        std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+    }
+}
@@ -0,0 +1,218 @@
+//! Interruptable reading from a stream that is also written to
+//!
+//! The regular [`pair`] function takes the whole stream, so for
+//! things like sockets and PTYs, where you also want to write while
+//! reads are interruptable, the stream has to be split first. This
+//! module does that, handing out a [`WriteHalf`] alongside the
+//! [`InterruptReader`], which reads from a [`ReadHalf`].
+//!
+//! The split can be done in two ways:
+//!
+//! - [`duplex::pair_cloned`], for streams that implement
+//!   [`TryClone`], where each half gets its own handle to the stream,
+//!   so reads and writes are completely independent.
+//! - [`duplex::pair`], for any stream, where the halves share the
+//!   stream through a [`Mutex`]. The worker thread only holds the
+//!   lock for the duration of each `read`, but if the stream blocks
+//!   on reads, writes have to wait for them to return. So this should
+//!   only be used with streams whose reads don't block for long.
+//!
+//! [`pair`]: crate::pair
+//! [`duplex::pair_cloned`]: pair_cloned
+//! [`duplex::pair`]: pair
+use std::{
+    io::{self, Read, Write},
+    net::TcpStream,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::{InterruptReader, Interruptor};
+
+/// Splits a stream that can be cloned into an [`InterruptReader`]
+/// and a [`WriteHalf`]
+///
+/// Each half holds its own handle to the stream, acquired through
+/// [`TryClone::try_clone`], so writing is never held up by a `read`
+/// from the worker thread.
+///
+/// This function returns an [`Err`] if the stream couldn't be
+/// cloned.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::{
+///     io::{BufRead, BufReader, Write},
+///     net::{TcpListener, TcpStream},
+///     time::Duration,
+/// };
+///
+/// use interrupt_read::{duplex, is_interrupt};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let listener = TcpListener::bind("127.0.0.1:0")?;
+/// let stream = TcpStream::connect(listener.local_addr()?)?;
+/// let (mut peer, _) = listener.accept()?;
+///
+/// // Answers every line that it gets.
+/// std::thread::spawn(move || {
+///     for line in BufReader::new(peer.try_clone()?).lines() {
+///         writeln!(peer, "ok: {}", line?)?;
+///     }
+///     std::io::Result::Ok(())
+/// });
+///
+/// let (mut reader, mut writer, interruptor) = duplex::pair_cloned(stream)?;
+///
+/// // Nothing has been sent, so this blocks.
+/// let join_handle = std::thread::spawn(move || {
+///     let mut line = String::new();
+///     let result = reader.read_line(&mut line);
+///     (result.map(|_| line), reader)
+/// });
+///
+/// std::thread::sleep(Duration::from_millis(50));
+/// writer.write_all(b"hello\n")?;
+///
+/// let (result, mut reader) = join_handle.join().unwrap();
+/// assert_eq!(result?, "ok: hello\n");
+///
+/// // Interrupting doesn't affect the write half.
+/// interruptor.interrupt()?;
+/// assert!(is_interrupt(
+///     &reader.read_line(&mut String::new()).unwrap_err()
+/// ));
+///
+/// writer.write_all(b"again\n")?;
+/// let mut line = String::new();
+/// reader.read_line(&mut line)?;
+/// assert_eq!(line, "ok: again\n");
+/// # Ok(())
+/// # }
+/// ```
+pub fn pair_cloned<S: TryClone + Read + Write + Send + 'static>(
+    stream: S,
+) -> io::Result<(InterruptReader<ReadHalf<S>>, WriteHalf<S>, Interruptor)> {
+    let clone = stream.try_clone()?;
+    let (reader, interruptor) = crate::pair(ReadHalf(Half::Owned(stream)));
+
+    Ok((reader, WriteHalf(Half::Owned(clone)), interruptor))
+}
+
+/// Splits any stream into an [`InterruptReader`] and a
+/// [`WriteHalf`]
+///
+/// The halves share the stream through a [`Mutex`], which the
+/// worker thread holds for the duration of each `read`. This means
+/// that, if a `read` blocks, so do the writes on the `WriteHalf`,
+/// until it returns. For streams that can be cloned, like sockets,
+/// use [`duplex::pair_cloned`] instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::io::{Cursor, Read, Write};
+///
+/// use interrupt_read::duplex;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let (mut reader, mut writer, _interruptor) = duplex::pair(Cursor::new(b"hello".to_vec()));
+///
+/// let mut out = String::new();
+/// reader.read_to_string(&mut out)?;
+/// assert_eq!(out, "hello");
+///
+/// writer.write_all(b" world")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`duplex::pair_cloned`]: pair_cloned
+pub fn pair<S: Read + Write + Send + 'static>(
+    stream: S,
+) -> (InterruptReader<ReadHalf<S>>, WriteHalf<S>, Interruptor) {
+    let stream = Arc::new(Mutex::new(stream));
+    let (reader, interruptor) = crate::pair(ReadHalf(Half::Locked(stream.clone())));
+
+    (reader, WriteHalf(Half::Locked(stream)), interruptor)
+}
+
+/// Streams that can be cloned into another handle of the same
+/// stream
+///
+/// This is what lets [`duplex::pair_cloned`] give each half its own
+/// handle.
+///
+/// [`duplex::pair_cloned`]: pair_cloned
+pub trait TryClone: Sized {
+    /// Returns a new handle to the same stream
+    fn try_clone(&self) -> io::Result<Self>;
+}
+
+impl TryClone for TcpStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        TcpStream::try_clone(self)
+    }
+}
+
+#[cfg(unix)]
+impl TryClone for std::os::unix::net::UnixStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        std::os::unix::net::UnixStream::try_clone(self)
+    }
+}
+
+/// The half of a stream that the [`InterruptReader`] reads from
+///
+/// This is acquired through [`duplex::pair`] or
+/// [`duplex::pair_cloned`].
+///
+/// [`duplex::pair`]: pair
+/// [`duplex::pair_cloned`]: pair_cloned
+#[derive(Debug)]
+pub struct ReadHalf<S>(Half<S>);
+
+impl<S: Read> Read for ReadHalf<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.0 {
+            Half::Owned(stream) => stream.read(buf),
+            Half::Locked(stream) => lock(stream).read(buf),
+        }
+    }
+}
+
+/// The half of a stream that can be written to
+///
+/// This is acquired through [`duplex::pair`] or
+/// [`duplex::pair_cloned`].
+///
+/// [`duplex::pair`]: pair
+/// [`duplex::pair_cloned`]: pair_cloned
+#[derive(Debug)]
+pub struct WriteHalf<S>(Half<S>);
+
+impl<S: Write> Write for WriteHalf<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.0 {
+            Half::Owned(stream) => stream.write(buf),
+            Half::Locked(stream) => lock(stream).write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.0 {
+            Half::Owned(stream) => stream.flush(),
+            Half::Locked(stream) => lock(stream).flush(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Half<S> {
+    Owned(S),
+    Locked(Arc<Mutex<S>>),
+}
+
+fn lock<S>(stream: &Mutex<S>) -> std::sync::MutexGuard<'_, S> {
+    stream.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -34,20 +34,161 @@
 //! [`BufRead::read_until`], which is probably not what you want to
 //! happen.
 //!
+//! # Tracing
+//!
+//! With the `tracing` feature enabled, the crate emits [`tracing`]
+//! events at the `DEBUG` and `TRACE` levels, for the chunks read by
+//! the worker thread (which runs inside of a `worker` span), the
+//! errors that it forwards, the interrupts that are sent and
+//! observed, the buffers that are handed back, and the dropping or
+//! unwrapping of the reader. Without the feature, none of this is
+//! compiled in.
+//!
+//! ```rust
+//! # #[cfg(feature = "tracing")]
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use std::{
+//!     io::{Read, Write},
+//!     sync::{Arc, Mutex},
+//! };
+//!
+//! use interrupt_read::{is_interrupt, pair};
+//! use tracing_subscriber::filter::LevelFilter;
+//!
+//! #[derive(Clone)]
+//! struct Capture(Arc<Mutex<Vec<u8>>>);
+//!
+//! impl Write for Capture {
+//!     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+//!         self.0.lock().unwrap().write(buf)
+//!     }
+//!
+//!     fn flush(&mut self) -> std::io::Result<()> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let capture = Capture(Arc::default());
+//! tracing_subscriber::fmt()
+//!     .with_max_level(LevelFilter::TRACE)
+//!     .with_ansi(false)
+//!     .with_writer({
+//!         let capture = capture.clone();
+//!         move || capture.clone()
+//!     })
+//!     .init();
+//!
+//! let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+//! let (mut reader, interruptor) = pair(pipe_reader);
+//! let mut buf = [0; 5];
+//!
+//! pipe_writer.write_all(b"hello")?;
+//! assert_eq!(reader.read(&mut buf)?, 5);
+//! interruptor.interrupt()?;
+//! assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+//! pipe_writer.write_all(b"world")?;
+//! assert_eq!(reader.read(&mut buf)?, 5);
+//! drop(reader);
+//!
+//! let output = String::from_utf8(capture.0.lock().unwrap().clone())?;
+//! let events: Vec<&str> = output
+//!     .lines()
+//!     .filter_map(|line| {
+//!         [
+//!             "read a chunk",
+//!             "interrupt sent",
+//!             "interrupt observed",
+//!             "reader dropped",
+//!         ]
+//!         .into_iter()
+//!         .find(|event| line.contains(event))
+//!     })
+//!     .collect();
+//!
+//! assert_eq!(events, [
+//!     "read a chunk",
+//!     "interrupt sent",
+//!     "interrupt observed",
+//!     "read a chunk",
+//!     "reader dropped"
+//! ]);
+//! assert!(output.contains("worker{buffer_size=8192}"));
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "tracing"))]
+//! # fn main() {}
+//! ```
+//!
 //! [`BufReader`]: std::io::BufReader
 //! [`ErrorKind::Other`]: std::io::ErrorKind::Other
 //! [`ErrorKind::Interrupted`]: std::io::ErrorKind::Interrupted
 //! [`interrupt_reader::pair`]: pair
+//! [`tracing`]: https://docs.rs/tracing
+#![cfg_attr(feature = "read_buf", feature(core_io_borrowed_buf, read_buf))]
+/// Emits a [`tracing`] event at the `TRACE` level, if the `tracing`
+/// feature is enabled
+///
+/// When it isn't, this expands to nothing, so the arguments aren't
+/// even evaluated.
+///
+/// [`tracing`]: https://docs.rs/tracing
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::trace!($($arg)*)
+    };
+}
+
+/// Like [`trace!`], but at the `DEBUG` level
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*)
+    };
+}
+
+pub mod broadcast;
+pub mod child;
+pub mod clock;
+pub mod duplex;
+pub mod local;
+#[cfg(all(unix, feature = "os-poll"))]
+pub mod poll;
+pub mod raw;
+pub mod reconnect;
+pub mod record;
+pub mod select;
+pub mod tcp;
+pub mod tee;
+pub mod testing;
+
+#[cfg(all(windows, feature = "windows-cancel"))]
+use std::os::windows::io::AsHandle;
 use std::{
-    io::{BufRead, Cursor, Error, Read, Take},
+    any::Any,
+    borrow::Cow,
+    collections::VecDeque,
+    io::{BufRead, Error, ErrorKind, IoSliceMut, PipeWriter, Read, Seek, SeekFrom, Stdin, Write},
+    ops::ControlFlow,
     sync::{
-        Arc,
-        atomic::{AtomicBool, Ordering::Relaxed},
-        mpsc,
+        Arc, Condvar, Mutex, MutexGuard, PoisonError, Weak,
+        atomic::{
+            AtomicBool, AtomicU64, AtomicUsize,
+            Ordering::{Acquire, Relaxed, Release, SeqCst},
+        },
+        mpsc::{self, RecvTimeoutError, TryRecvError},
     },
     thread::JoinHandle,
+    time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{
+    io::PipeReader,
+    os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd},
 };
 
+use clock::{Clock, SystemClock};
+
 /// Returns a pair of an [`InterruptReader`] and an [`Interruptor`].
 ///
 /// When you call any of the reading methods of `InterruptReader`, the
@@ -74,325 +215,11422 @@ use std::{
 /// Behind the scenes, this is done through channels and a spawned
 /// thread, but no timeout is used, all operations are blocking.
 ///
+/// If you want to configure the pair, you can use a [`Builder`]
+/// instead.
+///
 /// [`Error`]: std::io::Error
 /// [`ErrorKind::Other`]: std::io::ErrorKind::Other
-pub fn pair<R: Read + Send + 'static>(mut reader: R) -> (InterruptReader<R>, Interruptor) {
-    let (event_tx, event_rx) = mpsc::channel();
-    let (buffer_tx, buffer_rx) = mpsc::channel();
-    let is_reading = Arc::new(AtomicBool::new(true));
-
-    let join_handle = std::thread::spawn({
-        let event_tx = event_tx.clone();
-        let is_reading = is_reading.clone();
-        move || {
-            // Same capacity as BufReader
-            let mut buf = vec![0; 8 * 1024];
-            is_reading.store(true, Relaxed);
-
-            let reader = loop {
-                match reader.read(&mut buf) {
-                    Ok(num_bytes) => {
-                        // This means the InterruptReader has been dropped, so no more reading
-                        // will be done.
-                        let event = Event::Buf(std::mem::take(&mut buf), num_bytes);
-                        if event_tx.send(event).is_err() {
-                            break reader;
-                        }
+pub fn pair<R: Read + Send + 'static>(reader: R) -> (InterruptReader<R>, Interruptor) {
+    Builder::new().pair(reader)
+}
 
-                        buf = match buffer_rx.recv() {
-                            Ok(buf) => buf,
-                            // Same as before.
-                            Err(_) => break reader,
-                        }
-                    }
-                    Err(err) => {
-                        if event_tx.send(Event::Err(err)).is_err() {
-                            break reader;
-                        }
-                    }
-                }
-            };
-            is_reading.store(false, Relaxed);
-            reader
-        }
-    });
+/// Returns a pair of an [`InterruptReader`] and an [`Interruptor`],
+/// whose worker thread can be woken up
+///
+/// With the regular [`pair`], the worker thread may stay blocked on a
+/// `read` forever, if the source never produces another byte, even
+/// after the `InterruptReader` is dropped. For sources with a file
+/// descriptor, this function avoids that, by having the worker thread
+/// [`poll`] on both the source and an internal pipe before every
+/// `read`. When the `InterruptReader` is dropped, or
+/// [`Interruptor::close`] is called, a byte is written to that pipe,
+/// and the worker thread exits right away.
+///
+/// Other than that, this works just like [`pair`]. Note that
+/// interrupts never needed to wake up the worker thread, they take
+/// effect right away regardless. The reader is a [`SourceReader`],
+/// which also keeps a duplicate of the file descriptor of the source.
+///
+/// This function returns an [`Err`] if the internal pipe couldn't be
+/// created, or if the file descriptor couldn't be duplicated.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use interrupt_read::pair_fd;
+///
+/// # fn main() -> std::io::Result<()> {
+/// // The writer never writes.
+/// let (pipe_reader, _pipe_writer) = std::io::pipe()?;
+/// let (reader, _interruptor) = pair_fd(pipe_reader)?;
+///
+/// let is_reading = reader.is_reading_fn();
+/// std::thread::sleep(Duration::from_millis(50));
+/// assert!(is_reading());
+///
+/// // With `pair`, this would spin forever.
+/// drop(reader);
+/// while is_reading() {
+///     std::thread::yield_now();
+/// }
+///
+/// // And the worker thread can be joined right away.
+/// let (pipe_reader, _pipe_writer) = std::io::pipe()?;
+/// let (reader, _interruptor) = pair_fd(pipe_reader)?;
+/// std::thread::sleep(Duration::from_millis(50));
+/// let _pipe_reader = reader.into_inner().unwrap();
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`poll`]: https://man7.org/linux/man-pages/man2/poll.2.html
+#[cfg(unix)]
+pub fn pair_fd<R: Read + AsFd + Send + 'static>(
+    reader: R,
+) -> std::io::Result<(SourceReader<R, OwnedFd>, Interruptor)> {
+    Builder::new().pair_fd(reader)
+}
 
-    let interrupt_reader = InterruptReader {
-        is_reading,
-        cursor: None,
-        buffer_tx,
-        event_rx,
-        join_handle,
-    };
-    let interruptor = Interruptor(event_tx);
+/// Returns a pair of a [`DynInterruptReader`] and an
+/// [`Interruptor`]
+///
+/// This works just like [`pair`], but the reader is a trait object,
+/// so the type of the `InterruptReader` doesn't depend on the type of
+/// the source. This makes it easy to store readers over different
+/// sources in the same place, without a generic parameter.
+///
+/// [`InterruptReader::into_inner`] still returns the [`Box`].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::{
+///     fs::File,
+///     io::{BufRead, Write},
+///     net::{TcpListener, TcpStream},
+/// };
+///
+/// use interrupt_read::{DynInterruptReader, pair_boxed};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // Doesn't need to be generic.
+/// fn first_line(reader: &mut DynInterruptReader) -> std::io::Result<String> {
+///     let mut line = String::new();
+///     reader.read_line(&mut line)?;
+///     Ok(line)
+/// }
+///
+/// let path = std::env::temp_dir().join("interrupt_read_pair_boxed");
+/// std::fs::write(&path, "from a file\n")?;
+///
+/// let listener = TcpListener::bind("127.0.0.1:0")?;
+/// let stream = TcpStream::connect(listener.local_addr()?)?;
+/// let (mut peer, _) = listener.accept()?;
+/// peer.write_all(b"from a socket\n")?;
+///
+/// let mut readers = vec![
+///     pair_boxed(Box::new(File::open(&path)?)).0,
+///     pair_boxed(Box::new(stream)).0,
+/// ];
+///
+/// assert_eq!(first_line(&mut readers[0])?, "from a file\n");
+/// assert_eq!(first_line(&mut readers[1])?, "from a socket\n");
+/// # std::fs::remove_file(&path)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn pair_boxed(reader: Box<dyn Read + Send + 'static>) -> (DynInterruptReader, Interruptor) {
+    Builder::new().pair(reader)
+}
 
-    (interrupt_reader, interruptor)
+/// Returns a pair of an [`InterruptReader`] and an [`Interruptor`],
+/// which transforms every chunk on the worker thread
+///
+/// This is for decoding work that would otherwise be done by an
+/// adapter on the consumer's thread, defeating the point of reading
+/// on another one. `map` is called with every chunk read from the
+/// source, and what it returns is what the `InterruptReader` serves,
+/// so it can change the length of the chunk, or return a different
+/// buffer entirely. If it returns an empty [`Vec`], nothing is
+/// served, and if it returns an [`Err`], that is returned by the
+/// read operation, just like an error from the source. It isn't
+/// called for the end of the stream.
+///
+/// Other than that, this works just like [`pair`].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::{
+///     io::{Cursor, Read},
+///     sync::{Arc, Mutex},
+/// };
+///
+/// use interrupt_read::pair_with_map;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let data: Vec<u8> = b"hello world".iter().map(|byte| byte ^ 0x55).collect();
+/// let map_thread = Arc::new(Mutex::new(None));
+///
+/// let (mut reader, _interruptor) = pair_with_map(Cursor::new(data), {
+///     let map_thread = map_thread.clone();
+///     move |mut chunk: Vec<u8>| {
+///         *map_thread.lock().unwrap() = Some(std::thread::current().id());
+///         chunk.iter_mut().for_each(|byte| *byte ^= 0x55);
+///         Ok(chunk)
+///     }
+/// });
+///
+/// let mut out = String::new();
+/// reader.read_to_string(&mut out)?;
+/// assert_eq!(out, "hello world");
+///
+/// // It ran on the worker thread.
+/// let map_thread = map_thread.lock().unwrap().unwrap();
+/// assert_eq!(map_thread, reader.worker_thread().id());
+/// assert_ne!(map_thread, std::thread::current().id());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Changing the length of the chunks:
+///
+/// ```rust
+/// use std::io::{BufRead, ErrorKind, Read};
+///
+/// use interrupt_read::{
+///     pair_with_map,
+///     testing::{ScriptedReader, Step},
+/// };
+///
+/// # fn main() -> std::io::Result<()> {
+/// let (mut reader, _interruptor) = pair_with_map(
+///     ScriptedReader::new(vec![
+///         Step::Data(b"hxexlxlxox".to_vec()),
+///         // Nothing is left of this one.
+///         Step::Data(b"x".to_vec()),
+///         Step::Data(b"bad".to_vec()),
+///         Step::Data(b" xwxoxrxlxdx".to_vec()),
+///     ]),
+///     |chunk: Vec<u8>| {
+///         if chunk == b"bad" {
+///             return Err(std::io::Error::new(ErrorKind::InvalidData, "bad chunk"));
+///         }
+///         Ok(chunk.into_iter().filter(|byte| *byte != b'x').collect())
+///     },
+/// );
+///
+/// let mut out = String::new();
+/// let err = reader.read_line(&mut out).unwrap_err();
+/// assert_eq!(err.kind(), ErrorKind::InvalidData);
+/// assert_eq!(out, "hello");
+///
+/// reader.read_to_string(&mut out)?;
+/// assert_eq!(out, "hello world");
+/// # Ok(())
+/// # }
+/// ```
+pub fn pair_with_map<R, F>(reader: R, map: F) -> (InterruptReader<R>, Interruptor)
+where
+    R: Read + Send + 'static,
+    F: FnMut(Vec<u8>) -> std::io::Result<Vec<u8>> + Send + 'static,
+{
+    Builder::new().pair_with_map(reader, map)
 }
 
-/// An interruptable, buffered [`Read`]er.
+/// Returns an interruptable reader over a source that is recreated
+/// whenever it fails
 ///
-/// This reader is created by wrapping a `Read` struct in the
-/// [`interrupt_read::pair`] function, which also returns an
-/// [`Interruptor`], which is capable of sending interrupt signals,
-/// which make any `read` operations on the `InterruptReader` return
-/// an error of kind [`ErrorKind::Other`], with a payload of
-/// [`InterruptReceived`].
+/// Instead of a reader, this takes a `factory`, which the worker
+/// thread calls to create the source, and to create it again after
+/// an error or the end of the stream, as configured by the
+/// [`ReconnectPolicy`]. Every time that happens, reading returns an
+/// error with a [`Reconnecting`] payload, and then goes on, with
+/// the data of the new source.
 ///
-/// When an interrupt is received, _the underlying data is not lost_,
-/// it still exists, and if you call a reading function again, it will
-/// be retrieved, unless another interrupt is sent before that.
+/// Both the factory and the waits in between attempts run on the
+/// worker thread, so, like blocking reads, they don't hold up
+/// interrupts. The waits also end early if the [`InterruptReader`]
+/// is dropped, or the pair is [closed].
 ///
-/// You can check if an [`std::io::Error`] is of this type by
-/// calling the [`is_interrupt`] function.
+/// Check out the [`reconnect`] module for more information.
 ///
 /// # Examples
 ///
-/// One potential application of this struct is if you want to stop a
-/// thread that is reading from the stdout of a child process without
-/// necessarily terminating said childrop_:
+/// ```rust
+/// use std::{
+///     io::{Error, ErrorKind, Read},
+///     net::TcpStream,
+///     time::{Duration, Instant},
+/// };
+///
+/// use interrupt_read::{
+///     is_interrupt, pair_with_reconnect,
+///     reconnect::{ReconnectPolicy, reconnecting},
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let policy = ReconnectPolicy::new().backoff(Duration::from_secs(60), Duration::from_secs(60));
+/// let (mut reader, interruptor) = pair_with_reconnect(
+///     |_| Err::<TcpStream, _>(Error::from(ErrorKind::ConnectionRefused)),
+///     policy,
+/// );
+///
+/// let err = reader.read(&mut [0; 16]).unwrap_err();
+/// assert_eq!(reconnecting(&err).unwrap().delay(), Duration::from_secs(60));
+///
+/// // The worker thread is waiting, but that doesn't hold up interrupts.
+/// let start = Instant::now();
+/// interruptor.interrupt()?;
+/// assert!(is_interrupt(&reader.read(&mut [0; 16]).unwrap_err()));
+///
+/// // Nor does it keep the worker thread from stopping.
+/// assert!(reader.into_inner().is_ok());
+/// assert!(start.elapsed() < Duration::from_secs(10));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`ReconnectPolicy`]: reconnect::ReconnectPolicy
+/// [`Reconnecting`]: reconnect::Reconnecting
+/// [closed]: Interruptor::close
+pub fn pair_with_reconnect<F, R>(
+    factory: F,
+    policy: reconnect::ReconnectPolicy,
+) -> (
+    InterruptReader<reconnect::ReconnectReader<F, R>>,
+    Interruptor,
+)
+where
+    F: FnMut(reconnect::Attempt) -> std::io::Result<R> + Send + 'static,
+    R: Read + Send + 'static,
+{
+    let (reader, shared) = reconnect::ReconnectReader::new(factory, policy);
+    let (reader, interruptor) = pair(reader);
+    _ = shared.set(interruptor.shared.clone());
+
+    (reader, interruptor)
+}
+
+/// Returns a pair of a [`LocalInterruptReader`] and an
+/// [`Interruptor`], for a non-blocking source, without a worker
+/// thread
+///
+/// The source is read from directly, so it doesn't have to be
+/// [`Send`] or `'static`. Whenever it returns
+/// [`ErrorKind::WouldBlock`], the reader waits for a short while on
+/// the `Interruptor`, then tries again. Interrupts work just like
+/// they do with [`pair`].
+///
+/// Check out the [`local`] module for more information.
+///
+/// # Examples
 ///
 /// ```rust
 /// use std::{
-///     io::{BufRead, ErrorKind},
-///     process::{Child, Command, Stdio},
-///     time::Duration,
+///     cell::RefCell,
+///     collections::VecDeque,
+///     io::{ErrorKind, Read},
+///     rc::Rc,
 /// };
 ///
-/// use interrupt_read::{is_interrupt, pair};
+/// use interrupt_read::{is_interrupt, pair_local};
+///
+/// /// A queue that would block once it's empty.
+/// struct Queue(Rc<RefCell<VecDeque<u8>>>);
 ///
-/// struct ChildKiller(Child);
-/// impl Drop for ChildKiller {
-///     fn drop(&mut self) {
-///         _ = self.0.kill();
+/// impl Read for Queue {
+///     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+///         match self.0.borrow_mut().read(buf)? {
+///             0 => Err(ErrorKind::WouldBlock.into()),
+///             len => Ok(len),
+///         }
 ///     }
 /// }
 ///
-/// # match main() {
-/// #     Ok(()) => {}
-/// #     Err(err) => panic!("{err}")
+/// # fn main() -> std::io::Result<()> {
+/// let queue = Rc::new(RefCell::new(VecDeque::from(b"abc".to_vec())));
+/// let (mut reader, interruptor) = pair_local(Queue(queue.clone()));
+///
+/// let mut buf = [0; 8];
+/// assert_eq!(reader.read(&mut buf)?, 3);
+///
+/// interruptor.interrupt().unwrap();
+/// assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+///
+/// queue.borrow_mut().extend(b"def");
+/// assert_eq!(reader.read(&mut buf)?, 3);
+/// assert_eq!(&buf[..3], b"def");
+/// # Ok(())
 /// # }
-/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
-/// // Prints "hello\n" every second forever.
-/// let mut child = Command::new("bash")
-///     .args(["-c", r#"while true; do echo "hello"; sleep 1; done"#])
-///     .stdout(Stdio::piped())
-///     .spawn()
-///     .unwrap();
+/// ```
 ///
-/// let (mut stdout, interruptor) = pair(child.stdout.take().unwrap());
-/// let _child_killer = ChildKiller(child);
+/// [`LocalInterruptReader`]: local::LocalInterruptReader
+pub fn pair_local<R: Read>(reader: R) -> (local::LocalInterruptReader<R>, Interruptor) {
+    local::LocalInterruptReader::new(reader)
+}
+
+/// Returns a pair of an [`InterruptReader`] and an [`Interruptor`],
+/// whose worker runs wherever `spawner` puts it
 ///
-/// let join_handle = std::thread::spawn(move || {
-///     let mut string = String::new();
-///     loop {
-///         match stdout.read_line(&mut string) {
-///             Ok(0) => break Ok(string),
-///             Ok(_) => {}
-///             Err(err) if is_interrupt(&err) => {
-///                 break Ok(string);
+/// This works just like [`pair`], but instead of spawning a thread
+/// for the worker, it is handed over to `spawner`, which can run it
+/// on a thread pool, or on a thread with a name, priority or pinned
+/// core of its choosing. The worker runs until the source reaches the
+/// end or the `InterruptReader` is dropped, so it ties up whatever
+/// thread it runs on until then.
+///
+/// Since there is no [`JoinHandle`], the worker sends the source
+/// back once it is done, which is how [`InterruptReader::into_inner`]
+/// gets it. If the source panics, the panic is caught, so it doesn't
+/// take the spawner's thread down, and it is returned by
+/// `into_inner` as usual.
+///
+/// On Windows, with the `windows-cancel` feature,
+/// `Interruptor::cancel_blocking_read` doesn't work with these pairs,
+/// as there is no thread handle to cancel reads on.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::{
+///     io::{Cursor, Read},
+///     sync::{Arc, Mutex, mpsc},
+/// };
+///
+/// use interrupt_read::pair_with_spawner;
+///
+/// type Job = Box<dyn FnOnce() + Send + 'static>;
+///
+/// # fn main() -> std::io::Result<()> {
+/// // A pool of two threads, which run jobs as they come.
+/// let (job_tx, job_rx) = mpsc::channel::<Job>();
+/// let job_rx = Arc::new(Mutex::new(job_rx));
+/// let pool: Vec<_> = (0..2)
+///     .map(|_| {
+///         let job_rx = job_rx.clone();
+///         std::thread::spawn(move || {
+///             // Not holding the lock while running the job.
+///             while let Ok(job) = { job_rx.lock().unwrap().recv() } {
+///                 job();
 ///             }
-///             Err(err) => break Err(err),
-///         }
-///     }
+///         })
+///     })
+///     .collect();
+///
+/// let spawner = |job| job_tx.send(job).unwrap();
+/// let (mut first, _interruptor) = pair_with_spawner(Cursor::new(b"hello".to_vec()), spawner);
+/// let (mut second, _interruptor) = pair_with_spawner(Cursor::new(b"world".to_vec()), spawner);
+///
+/// let mut out = String::new();
+/// first.read_to_string(&mut out)?;
+/// second.read_to_string(&mut out)?;
+/// assert_eq!(out, "helloworld");
+///
+/// let pool_threads: Vec<_> = pool.iter().map(|handle| handle.thread().id()).collect();
+/// assert!(pool_threads.contains(&first.worker_thread().id()));
+///
+/// // The sources come back through the workers.
+/// assert_eq!(first.into_inner().unwrap().position(), 5);
+/// assert_eq!(second.into_inner().unwrap().into_inner(), b"world");
+///
+/// drop(job_tx);
+/// for handle in pool {
+///     handle.join().unwrap();
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn pair_with_spawner<R, S>(reader: R, spawner: S) -> (InterruptReader<R>, Interruptor)
+where
+    R: Read + Send + 'static,
+    S: FnOnce(Box<dyn FnOnce() + Send + 'static>),
+{
+    Builder::new().pair_with_spawner(reader, spawner)
+}
+
+/// Returns an interruptable reader over the [`Stdin`]
+///
+/// This works just like calling [`pair`] with [`std::io::stdin`],
+/// but with chunks of at most 1 KiB, since an interactive `read`
+/// only returns one line anyway. That doesn't avoid buffering twice,
+/// as [`Stdin`] has a buffer of its own, of 8 KiB, which the worker
+/// thread reads from. Since the worker thread only ever hands out
+/// the bytes that it got from a single `read`, this plays nicely
+/// with prompts printed through [`eprint!`] or [`print!`], as every
+/// line that is typed in will be served as soon as it is available.
+///
+/// Keep in mind that, interactively, the stdin never really reaches
+/// the end, so the worker thread will most likely stay blocked on a
+/// `read` even after the `InterruptReader` is dropped. That doesn't
+/// prevent the process from exiting, but for as long as it is
+/// blocked, the worker thread holds the lock of the process wide
+/// [`Stdin`]. Any other read from the stdin, through
+/// [`std::io::stdin`] or another call to this function, waits for
+/// it, and the next line that is typed in goes to the detached
+/// worker thread, which throws it away.
+///
+/// So if the stdin is read again later, keep the same
+/// `InterruptReader` around instead of dropping it. On Unix,
+/// [`pair_fd`] over [`std::io::stdin`] also works, since its worker
+/// thread only calls `read` once a line is there, and exits as soon
+/// as the reader is dropped.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::io::{BufRead, Write};
+///
+/// use interrupt_read::{is_interrupt, stdin};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let (mut stdin, interruptor) = stdin();
+///
+/// // Give up on the user after 10 seconds.
+/// std::thread::spawn(move || {
+///     std::thread::sleep(std::time::Duration::from_secs(10));
+///     _ = interruptor.interrupt();
 /// });
 ///
-/// std::thread::sleep(Duration::new(3, 1_000_000));
+/// eprint!("Name: ");
+/// let mut name = String::new();
+/// match stdin.read_line(&mut name) {
+///     Ok(_) => eprintln!("Hello, {}!", name.trim()),
+///     Err(err) if is_interrupt(&err) => eprintln!("\nToo slow!"),
+///     Err(err) => return Err(err),
+/// }
+/// # Ok(())
+/// # }
+/// ```
 ///
-/// interruptor.interrupt()?;
+/// Dropping the reader won't hang, even though the worker thread may
+/// still be waiting:
 ///
-/// let result = join_handle.join().unwrap()?;
+/// ```rust
+/// let (stdin, _interruptor) = interrupt_read::stdin();
+/// drop(stdin);
+/// ```
 ///
-/// assert_eq!(result, "hello\nhello\nhello\n");
+/// [`Stdin`]: std::io::Stdin
+pub fn stdin() -> (InterruptReader<Stdin>, Interruptor) {
+    Builder::new().buffer_size(1024).pair(std::io::stdin())
+}
+
+/// Returns a pair of an [`InterruptReader`] and an [`Interruptor`],
+/// where the reader is interrupted once `timeout` has passed
 ///
-/// Ok(())
+/// The time is counted from the call to this function, and only one
+/// interrupt is sent, with a reason saying that the time ran out
+/// (see [`InterruptReceived::reason`]). The `Interruptor` can still
+/// be used to interrupt earlier than that.
+///
+/// The interrupt is sent from a timer thread, which stops as soon as
+/// the `InterruptReader` is dropped or unwrapped through
+/// [`InterruptReader::into_inner`], so no interrupt is sent after
+/// that.
+///
+/// With the default [`SystemClock`], the time that is counted is
+/// real time. In order to test code that relies on the timeout
+/// without waiting for it, [`Builder::timeout_reader`] takes the
+/// [`Clock`] of a [`Builder`], like a [`MockClock`].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::{
+///     io::{Cursor, Read},
+///     sync::Arc,
+///     time::Duration,
+/// };
+///
+/// use interrupt_read::{Builder, is_interrupt, testing::MockClock, timeout_reader};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // Nothing is ever written, so this never finishes on its own.
+/// let clock = Arc::new(MockClock::new());
+/// let timeout = Duration::from_secs(30);
+/// let (pipe_reader, _pipe_writer) = std::io::pipe()?;
+/// let (mut reader, _interruptor) = Builder::new()
+///     .clock(clock.clone())
+///     .timeout_reader(pipe_reader, timeout);
+///
+/// std::thread::spawn({
+///     let (clock, watch) = (clock.clone(), reader.state_watch());
+///     move || clock.advance_when_waiting(&watch, timeout)
+/// });
+///
+/// let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+/// assert!(is_interrupt(&err));
+/// assert_eq!(clock.elapsed(), timeout);
+///
+/// // A source that finishes in time works as normal.
+/// let (mut reader, _interruptor) =
+///     timeout_reader(Cursor::new(b"hello".to_vec()), Duration::from_secs(5));
+/// let mut out = Vec::new();
+/// reader.read_to_end(&mut out)?;
+/// assert_eq!(out, b"hello");
+/// # Ok(())
 /// # }
 /// ```
 ///
+/// [`MockClock`]: testing::MockClock
+pub fn timeout_reader<R: Read + Send + 'static>(
+    reader: R,
+    timeout: Duration,
+) -> (InterruptReader<R>, Interruptor) {
+    Builder::new().timeout_reader(reader, timeout)
+}
+
+/// A builder for an [`InterruptReader`] and [`Interruptor`] pair
+///
+/// This lets you configure the pair before spawning the worker
+/// thread. With the default configuration, [`Builder::pair`] behaves
+/// exactly like [`interrupt_read::pair`].
+///
+/// # Examples
+///
+/// ```rust
+/// use interrupt_read::Builder;
+///
+/// let (reader, interruptor) = Builder::new()
+///     .buffer_size(512)
+///     .coalesce_interrupts(true)
+///     .pair(std::io::empty());
+/// ```
+///
 /// [`interrupt_read::pair`]: pair
-/// [`ErrorKind::Other`]: std::io::ErrorKind::Other
-#[derive(Debug)]
-pub struct InterruptReader<R> {
-    is_reading: Arc<AtomicBool>,
-    cursor: Option<Take<Cursor<Vec<u8>>>>,
-    buffer_tx: mpsc::Sender<Vec<u8>>,
-    event_rx: mpsc::Receiver<Event>,
-    join_handle: JoinHandle<R>,
+#[derive(Debug, Clone)]
+pub struct Builder {
+    buffer_size: usize,
+    coalesce_interrupts: bool,
+    interrupt_check: InterruptCheck,
+    skip_checks_without_interruptors: bool,
+    throttle: Option<Rate>,
+    adaptive: Option<(usize, usize)>,
+    retry_interrupted: bool,
+    eof: Eof,
+    overflow: Overflow,
+    max_buffered_bytes: Option<usize>,
+    stall_watchdog: Option<StallWatchdog>,
+    on_drop: DropBehavior,
+    on_detach: Option<OnDetach>,
+    poison_after: Option<u32>,
+    clock: Arc<dyn Clock>,
+    before_read: Option<BeforeRead>,
 }
 
-impl<R: Read> InterruptReader<R> {
-    /// Unwraps this `InterruptReader`, returning the underlying
-    /// reader.
+impl Builder {
+    /// Returns a new `Builder`, with the default configuration
+    pub fn new() -> Self {
+        Self {
+            // Same capacity as BufReader
+            buffer_size: 8 * 1024,
+            coalesce_interrupts: false,
+            interrupt_check: InterruptCheck::EveryCall,
+            skip_checks_without_interruptors: false,
+            throttle: None,
+            adaptive: None,
+            retry_interrupted: true,
+            eof: Eof::Empty,
+            overflow: Overflow::Backpressure,
+            max_buffered_bytes: None,
+            stall_watchdog: None,
+            on_drop: DropBehavior::Detach,
+            on_detach: None,
+            poison_after: None,
+            clock: Arc::new(SystemClock),
+            before_read: None,
+        }
+    }
+
+    /// The size of the buffer used by the worker thread
     ///
-    /// Note that any leftover data in the internal buffer is lost.
-    /// Therefore, a following read from the underlying reader may
-    /// lead to data loss.
+    /// This is the maximum amount of bytes that will be read from
+    /// the underlying [`Read`]er at once. By default, it is 8 KiB,
+    /// same as [`BufReader`].
     ///
-    /// This may return [`Err`] if the underlying joined thread has
-    /// panicked, probably because the [`Read`]er has done so.
-    pub fn into_inner(self) -> std::thread::Result<R> {
-        let Self { buffer_tx, event_rx, join_handle, .. } = self;
-        drop((event_rx, buffer_tx));
-        join_handle.join()
+    /// When [`Read::read`] is called with a bigger buffer than this,
+    /// the worker thread's buffer grows to match it (up to 1 MiB),
+    /// so that big reads, like those of [`Read::read_to_end`], take
+    /// fewer round trips between the threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is `0`.
+    ///
+    /// [`BufReader`]: std::io::BufReader
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "buffer size can't be 0");
+        self.buffer_size = size;
+        self
     }
 
-    /// Wether the reader thread is still active.
-    pub fn is_reading(&self) -> bool {
+    /// Lets the buffer used by the worker thread adapt to the source
+    ///
+    /// With this, the buffer starts out at [`Builder::buffer_size`]
+    /// (kept within `min` and `max`), doubles in size, up to `max`,
+    /// when reads keep filling it completely, and halves in size,
+    /// down to `min`, after a streak of reads that only fill a small
+    /// part of it. This suits both interactive sources, which send a
+    /// few bytes at a time, and bulk transfers, which benefit from
+    /// bigger chunks.
+    ///
+    /// The buffers are only reallocated when the size changes. In
+    /// this mode, the buffer doesn't grow to match big calls to
+    /// [`Read::read`], as described in [`Builder::buffer_size`]. You
+    /// can check the current size with
+    /// [`InterruptReader::worker_buffer_size`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is `0`, or if it is bigger than `max`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, Cursor, Read};
+    ///
+    /// use interrupt_read::{
+    ///     Builder,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let builder = Builder::new().adaptive_buffer(256, 1024 * 1024);
+    ///
+    /// // A child that prints short lines, one at a time.
+    /// let lines = (0..100).map(|_| Step::Data(b"ok\n".to_vec()));
+    /// let (mut reader, _interruptor) = builder.clone().pair(ScriptedReader::new(lines.collect()));
+    /// assert_eq!(reader.worker_buffer_size(), 8 * 1024);
+    ///
+    /// let mut line = String::new();
+    /// while reader.read_line(&mut line)? > 0 {}
+    /// assert_eq!(reader.worker_buffer_size(), 256);
+    ///
+    /// // A bulk transfer.
+    /// let data = vec![0; 16 * 1024 * 1024];
+    /// let (mut reader, _interruptor) = builder.pair(Cursor::new(data));
+    ///
+    /// let mut out = Vec::new();
+    /// reader.read_to_end(&mut out)?;
+    /// assert_eq!(reader.worker_buffer_size(), 1024 * 1024);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn adaptive_buffer(mut self, min: usize, max: usize) -> Self {
+        assert!(min > 0, "minimum buffer size can't be 0");
+        assert!(
+            min <= max,
+            "minimum buffer size can't be bigger than the maximum"
+        );
+        self.adaptive = Some((min, max));
+        self
+    }
+
+    /// Wether to coalesce interrupts that haven't been observed yet
+    ///
+    /// By default, every call to [`Interruptor::interrupt`] makes
+    /// one read operation fail. With this option enabled, multiple
+    /// calls that occur before the reader has observed any of them
+    /// will only produce one [`InterruptReceived`] error.
+    ///
+    /// Once that error has been returned, new interrupts will go
+    /// through as usual, so the read/interrupt cycle is not affected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    ///
+    /// use interrupt_read::{Builder, is_interrupt};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (mut reader, interruptor) = Builder::new()
+    ///     .coalesce_interrupts(true)
+    ///     .pair(b"hello".as_slice());
+    ///
+    /// for _ in 0..5 {
+    ///     interruptor.interrupt()?;
+    /// }
+    ///
+    /// let mut buf = [0; 5];
+    /// assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+    /// assert_eq!(reader.read(&mut buf)?, 5);
+    /// assert_eq!(&buf, b"hello");
+    ///
+    /// // Once observed, interrupts work again.
+    /// interruptor.interrupt()?;
+    /// assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn coalesce_interrupts(mut self, coalesce: bool) -> Self {
+        self.coalesce_interrupts = coalesce;
+        self
+    }
+
+    /// How often to check for interrupts while serving buffered data
+    ///
+    /// By default, this is [`InterruptCheck::EveryCall`]. Check out
+    /// [`InterruptCheck`] for the trade-offs of each option. This
+    /// can also be changed later, through
+    /// [`InterruptReader::set_interrupt_check`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    ///
+    /// use interrupt_read::{Builder, InterruptCheck, is_interrupt};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (mut reader, interruptor) = Builder::new()
+    ///     .interrupt_check(InterruptCheck::PerChunk)
+    ///     .pair(b"hello world".as_slice());
+    ///
+    /// let mut buf = [0; 6];
+    /// assert_eq!(reader.read(&mut buf)?, 6);
+    /// assert_eq!(&buf, b"hello ");
+    ///
+    /// // The rest of the chunk is served before the interrupt.
+    /// interruptor.interrupt()?;
+    /// assert_eq!(reader.read(&mut buf[..5])?, 5);
+    /// assert_eq!(&buf[..5], b"world");
+    /// assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+    /// assert_eq!(reader.read(&mut buf)?, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn interrupt_check(mut self, check: InterruptCheck) -> Self {
+        self.interrupt_check = check;
+        self
+    }
+
+    /// Sets wether to stop checking for interrupts while serving
+    /// buffered data, once every [`Interruptor`] is dropped
+    ///
+    /// Without an `Interruptor`, there is nothing to check for, so
+    /// the reader goes through the same path as with
+    /// [`InterruptCheck::PerChunk`], after one last check for the
+    /// interrupts that were sent before the last one was dropped, or
+    /// by any that was created and dropped in the meantime.
+    /// Once an `Interruptor` is created again, through
+    /// [`WeakInterruptor::upgrade`] or
+    /// [`InterruptReader::interruptor`], the [`InterruptCheck`]
+    /// policy applies again. By default, this is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    ///
+    /// use interrupt_read::{Builder, is_interrupt};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (mut reader, interruptor) = Builder::new()
+    ///     .skip_checks_without_interruptors(true)
+    ///     .pair(b"hello world".as_slice());
+    /// let weak = interruptor.downgrade();
+    ///
+    /// // What was sent before the drop still comes through.
+    /// interruptor.interrupt()?;
+    /// drop(interruptor);
+    /// assert!(!reader.interrupt_possible());
+    /// assert!(is_interrupt(&reader.read(&mut [0; 6]).unwrap_err()));
+    ///
+    /// let mut buf = [0; 6];
+    /// assert_eq!(reader.read(&mut buf)?, 6);
+    /// assert_eq!(&buf, b"hello ");
+    ///
+    /// // Even through one that was dropped right away.
+    /// weak.upgrade().unwrap().interrupt()?;
+    /// assert!(!reader.interrupt_possible());
+    /// assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+    ///
+    /// // Upgrading makes the reader check on every call again.
+    /// let interruptor = weak.upgrade().unwrap();
+    /// assert!(reader.interrupt_possible());
+    /// interruptor.interrupt()?;
+    /// assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+    /// assert_eq!(reader.read(&mut buf)?, 5);
+    /// assert_eq!(&buf[..5], b"world");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn skip_checks_without_interruptors(mut self, skip: bool) -> Self {
+        self.skip_checks_without_interruptors = skip;
+        self
+    }
+
+    /// Limits how fast the worker thread reads from the source
+    ///
+    /// The worker thread reads chunks worth of about 50ms at this
+    /// [`Rate`], and waits between reads so that, on average, the
+    /// consumer observes this rate, which is useful for simulating
+    /// slow devices. By default, reading isn't throttled.
+    ///
+    /// Interrupts are still delivered right away, regardless of the
+    /// throttling. The rate can be changed later, through
+    /// [`InterruptReader::set_throttle`] or
+    /// [`Interruptor::set_throttle`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{Cursor, Read},
+    ///     sync::Arc,
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{Builder, Rate, Readiness, is_interrupt, testing::MockClock};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let clock = Arc::new(MockClock::new());
+    /// let data = vec![b'a'; 20_000];
+    /// let (mut reader, _interruptor) = Builder::new()
+    ///     .clock(clock.clone())
+    ///     .throttle(Rate::bytes_per_second(100_000))
+    ///     .pair(Cursor::new(data.clone()));
+    ///
+    /// // About 50ms worth of data is read at once.
+    /// let mut out = vec![0; 20_000];
+    /// assert_eq!(reader.read(&mut out)?, 5_000);
+    /// let readiness = reader.poll_read_ready(Some(Duration::ZERO))?;
+    /// assert_eq!(readiness, Readiness::TimedOut);
+    ///
+    /// clock.advance(Duration::from_millis(50));
+    /// assert_eq!(reader.read(&mut out[5_000..])?, 5_000);
+    ///
+    /// // And the rest takes another 150ms, including the end.
+    /// clock.advance(Duration::from_millis(150));
+    /// reader.read_exact(&mut out[10_000..])?;
+    /// assert_eq!(reader.read(&mut [0; 16])?, 0);
+    /// assert_eq!(out, data);
+    ///
+    /// // Interrupts don't wait for the throttling.
+    /// let (mut reader, interruptor) = Builder::new()
+    ///     .clock(clock.clone())
+    ///     .throttle(Rate::bytes_per_second(1))
+    ///     .pair(Cursor::new(data.clone()));
+    ///
+    /// let mut buf = [0; 16];
+    /// assert_eq!(reader.read(&mut buf)?, 1);
+    /// let watch = reader.state_watch();
+    /// std::thread::spawn(move || {
+    ///     clock.advance_when_waiting(&watch, Duration::from_millis(50));
+    ///     interruptor.interrupt().unwrap();
+    /// });
+    ///
+    /// // The next byte would only be read after a second.
+    /// assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn throttle(mut self, rate: Rate) -> Self {
+        self.throttle = Some(rate);
+        self
+    }
+
+    /// Wether the worker thread retries reads that fail with
+    /// [`ErrorKind::Interrupted`]
+    ///
+    /// Those errors come from signals landing in the worker thread,
+    /// and functions like [`Read::read_to_end`] and [`BufReader`]
+    /// just retry on them, so by default, this is `true`, and they
+    /// never reach the consumer. Set it to `false` in order to see
+    /// them.
+    ///
+    /// This has nothing to do with the interrupts sent by an
+    /// [`Interruptor`], which are never of that kind.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{ErrorKind, Read};
+    ///
+    /// use interrupt_read::{
+    ///     Builder, pair,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let script = || {
+    ///     ScriptedReader::new(vec![
+    ///         Step::Error(ErrorKind::Interrupted),
+    ///         Step::Error(ErrorKind::Interrupted),
+    ///         Step::Data(b"hello".to_vec()),
+    ///     ])
+    /// };
+    ///
+    /// let (mut reader, _interruptor) = pair(script());
+    /// let mut buf = [0; 16];
+    /// assert_eq!(reader.read(&mut buf)?, 5);
+    /// assert_eq!(reader.read(&mut buf)?, 0);
+    ///
+    /// let (mut reader, _interruptor) = Builder::new().retry_interrupted(false).pair(script());
+    /// for _ in 0..2 {
+    ///     let err = reader.read(&mut buf).unwrap_err();
+    ///     assert_eq!(err.kind(), ErrorKind::Interrupted);
+    /// }
+    /// assert_eq!(reader.read(&mut buf)?, 5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ErrorKind::Interrupted`]: std::io::ErrorKind::Interrupted
+    /// [`BufReader`]: std::io::BufReader
+    pub fn retry_interrupted(mut self, retry: bool) -> Self {
+        self.retry_interrupted = retry;
+        self
+    }
+
+    /// What ends the stream for [`read_to_end`] and
+    /// [`read_to_string`]
+    ///
+    /// By default, that is the first `Ok(0)` from the source, like
+    /// with any other reader. With [`Eof::Final`], those are skipped,
+    /// since the source may return more data afterwards, and only
+    /// [`Interruptor::close`] or the worker thread stopping end the
+    /// stream. Other read operations still return every `Ok(0)`.
+    ///
+    /// Keep in mind that the worker thread keeps reading after an
+    /// `Ok(0)`, so with a source that returns it right away, like a
+    /// [`Cursor`] at its end, [`Eof::Final`] keeps both threads busy
+    /// until the stream is closed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::Read,
+    ///     sync::{Arc, Barrier},
+    /// };
+    ///
+    /// use interrupt_read::{
+    ///     Builder, Eof, pair,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let mut steps = vec![
+    ///     Step::Data(b"before".to_vec()),
+    ///     Step::Eof,
+    ///     Step::Data(b" after".to_vec()),
+    /// ];
+    ///
+    /// let (mut reader, _interruptor) = pair(ScriptedReader::new(steps.clone()));
+    /// let mut out = String::new();
+    /// reader.read_to_string(&mut out)?;
+    /// assert_eq!(out, "before");
+    ///
+    /// // Closed once the script is done, which ends the stream.
+    /// let barrier = Arc::new(Barrier::new(2));
+    /// steps.push(Step::WaitForSignal(barrier.clone()));
+    /// let (mut reader, interruptor) = Builder::new()
+    ///     .eof(Eof::Final)
+    ///     .pair(ScriptedReader::new(steps));
+    /// std::thread::spawn(move || {
+    ///     barrier.wait();
+    ///     interruptor.close().unwrap();
+    /// });
+    ///
+    /// let mut out = String::new();
+    /// reader.read_to_string(&mut out)?;
+    /// assert_eq!(out, "before after");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`read_to_end`]: Read::read_to_end
+    /// [`read_to_string`]: Read::read_to_string
+    /// [`Cursor`]: std::io::Cursor
+    pub fn eof(mut self, eof: Eof) -> Self {
+        self.eof = eof;
+        self
+    }
+
+    /// Runs `f` on the source, in the worker thread, before every
+    /// read from it
+    ///
+    /// This is for sources that need some housekeeping in between
+    /// reads, like a serial port whose timeout should be adjusted, or
+    /// whose carrier should be checked for. What `f` returns decides
+    /// what happens next, as described in [`ReadDirective`], and an
+    /// [`Err`] returned by it is treated just like an error returned
+    /// by the read itself. The type `R` has to be the one of the
+    /// source that the pair is created with.
+    ///
+    /// Since `f` is shared by the clones of this `Builder`, it is
+    /// shared by the pairs created from them as well.
+    ///
+    /// # Panics
+    ///
+    /// Creating a pair panics if its source isn't of type `R`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::Read,
+    ///     sync::{
+    ///         Arc,
+    ///         atomic::{AtomicUsize, Ordering},
+    ///     },
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{
+    ///     Builder, ReadDirective, is_interrupt,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let calls = Arc::new(AtomicUsize::new(0));
+    /// let script = ScriptedReader::new(vec![
+    ///     Step::Data(b"first".to_vec()),
+    ///     Step::Data(b"second".to_vec()),
+    ///     Step::Data(b"never read".to_vec()),
+    /// ]);
+    ///
+    /// let (mut reader, interruptor) = Builder::new()
+    ///     .before_each_read({
+    ///         let calls = calls.clone();
+    ///         move |script: &mut ScriptedReader| {
+    ///             let steps_left = script.remaining().len();
+    ///             Ok(match calls.fetch_add(1, Ordering::Relaxed) {
+    ///                 0 => ReadDirective::SkipAndWait(Duration::from_millis(50)),
+    ///                 1 | 2 => ReadDirective::Continue,
+    ///                 _ => {
+    ///                     assert_eq!(steps_left, 1);
+    ///                     ReadDirective::Stop
+    ///                 }
+    ///             })
+    ///         }
+    ///     })
+    ///     .pair(script);
+    ///
+    /// // The worker thread waits, but interrupts don't.
+    /// interruptor.interrupt().unwrap();
+    /// assert!(is_interrupt(&reader.read(&mut [0; 16]).unwrap_err()));
+    ///
+    /// let mut out = String::new();
+    /// reader.read_to_string(&mut out)?;
+    /// assert_eq!(out, "firstsecond");
+    /// assert_eq!(calls.load(Ordering::Relaxed), 4);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn before_each_read<R: 'static>(
+        mut self,
+        mut f: impl FnMut(&mut R) -> std::io::Result<ReadDirective> + Send + 'static,
+    ) -> Self {
+        self.before_read = Some(BeforeRead {
+            reader: std::any::TypeId::of::<R>(),
+            f: Arc::new(Mutex::new(move |reader: &mut dyn Any| {
+                f(reader.downcast_mut().unwrap())
+            })),
+        });
+        self
+    }
+
+    /// What the worker thread does when the reader falls behind
+    ///
+    /// By default, with [`Overflow::Backpressure`], the worker thread
+    /// stops reading once all of its buffers were sent, until the
+    /// reader hands one back, so nothing is ever lost.
+    ///
+    /// With [`Overflow::DropOldest`], it keeps on reading, and once
+    /// more than `max_buffered_chunks` chunks are waiting to be
+    /// received, the oldest ones are dropped, so the reader always
+    /// gets the most recent data. The chunks that are kept are still
+    /// in order, and interrupts work as usual. How many bytes were
+    /// dropped can be checked through
+    /// [`InterruptReader::dropped_bytes`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_buffered_chunks` is `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::Read,
+    ///     sync::{Arc, Barrier},
+    /// };
+    ///
+    /// use interrupt_read::{
+    ///     Builder, Overflow,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// // A hundred numbered chunks, sent as fast as possible.
+    /// let barrier = Arc::new(Barrier::new(2));
+    /// let mut steps: Vec<Step> = (0..100u64)
+    ///     .map(|n| Step::Data(n.to_be_bytes().to_vec()))
+    ///     .collect();
+    /// steps.push(Step::WaitForSignal(barrier.clone()));
+    ///
+    /// let (mut reader, _interruptor) = Builder::new()
+    ///     .overflow(Overflow::DropOldest { max_buffered_chunks: 4 })
+    ///     .pair(ScriptedReader::new(steps));
+    ///
+    /// // By the time it reaches the barrier, everything was read.
+    /// barrier.wait();
+    ///
+    /// let mut data = Vec::new();
+    /// reader.read_to_end(&mut data)?;
+    /// let numbers: Vec<u64> = data
+    ///     .chunks(8)
+    ///     .map(|n| u64::from_be_bytes(n.try_into().unwrap()))
+    ///     .collect();
+    ///
+    /// // Only the most recent chunks were kept, in order.
+    /// assert_eq!(numbers, [96, 97, 98, 99]);
+    /// assert_eq!(reader.dropped_bytes(), 96 * 8);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        if let Overflow::DropOldest { max_buffered_chunks } = overflow {
+            assert!(max_buffered_chunks > 0, "at least one chunk must be kept");
+        }
+        self.overflow = overflow;
+        self
+    }
+
+    /// Caps how many bytes can be read ahead of the consumer
+    ///
+    /// Once the chunks that were sent by the worker thread, but not
+    /// yet consumed, add up to `bytes`, it stops reading from the
+    /// source until the reader frees some of them, so a paused
+    /// consumer doesn't make the bytes pile up. Since a chunk is only
+    /// freed once all of it is consumed, and the worker thread checks
+    /// the cap before reading, no more than `bytes` plus one chunk
+    /// are ever held. [`Chunk`]s from [`InterruptReader::next_chunk`]
+    /// count towards the cap until they are dropped.
+    ///
+    /// While the worker thread waits, interrupts work as usual. By
+    /// default, there is no cap, other than the one given by the
+    /// buffers of the worker thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{Cursor, Read},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{Builder, is_interrupt};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// const CAP: usize = 100 * 1024;
+    /// const CHUNK: usize = 64 * 1024;
+    ///
+    /// let data: Vec<u8> = (0..2 * 1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+    /// let (mut reader, interruptor) = Builder::new()
+    ///     .buffer_size(CHUNK)
+    ///     .max_buffered_bytes(CAP)
+    ///     .pair(Cursor::new(data.clone()));
+    ///
+    /// // The consumer is paused, but the worker thread stops at the cap.
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// assert!(reader.bytes_buffered() <= CAP + CHUNK);
+    ///
+    /// // And interrupts still come through.
+    /// interruptor.interrupt().unwrap();
+    /// assert!(is_interrupt(&reader.read(&mut [0; 16]).unwrap_err()));
+    ///
+    /// // A consumer that keeps pausing still gets everything, without
+    /// // the bytes piling up.
+    /// let mut out = Vec::new();
+    /// let mut buf = [0; 4096];
+    /// let mut peak = 0;
+    /// loop {
+    ///     peak = peak.max(reader.bytes_buffered());
+    ///     match reader.read(&mut buf)? {
+    ///         0 => break,
+    ///         len => out.extend_from_slice(&buf[..len]),
+    ///     }
+    ///     if out.len() % (256 * 1024) == 0 {
+    ///         std::thread::sleep(Duration::from_millis(5));
+    ///     }
+    /// }
+    ///
+    /// assert!(peak <= CAP + CHUNK);
+    /// assert!(out == data);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn max_buffered_bytes(mut self, bytes: usize) -> Self {
+        assert!(bytes > 0, "at least one byte must be buffered");
+        self.max_buffered_bytes = Some(bytes);
+        self
+    }
+
+    /// Calls `on_stall` when the source goes quiet for `threshold`
+    ///
+    /// Once no bytes were read from the source for `threshold`, be it
+    /// since the pair was created or since the last chunk, `on_stall`
+    /// is called with a [`StallInfo`], from a thread of its own. It
+    /// is only called once for every silence, however long it
+    /// lasts, and then only once data resumes and a new silence
+    /// begins. The end of the stream doesn't count as data.
+    ///
+    /// A common use is to call an [`Interruptor`] from `on_stall`,
+    /// which gives up on a read that is taking too long, without
+    /// keeping a timer for every reader. The thread stops once the
+    /// [`InterruptReader`] is dropped, or [`Interruptor::close`] is
+    /// called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::Read,
+    ///     sync::{Arc, mpsc},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{
+    ///     Builder,
+    ///     testing::{BlockingGate, MockClock},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let clock = Arc::new(MockClock::new());
+    /// let (gate, handle) = BlockingGate::new();
+    /// let (stall_tx, stall_rx) = mpsc::channel();
+    /// let (mut reader, _interruptor) = Builder::new()
+    ///     .clock(clock.clone())
+    ///     .stall_watchdog(Duration::from_secs(10), move |info| _ = stall_tx.send(info))
+    ///     .pair(gate);
+    ///
+    /// let mut buf = [0; 16];
+    /// handle.release(b"a");
+    /// assert_eq!(reader.read(&mut buf)?, 1);
+    ///
+    /// clock.advance(Duration::from_secs(10));
+    /// let stall = stall_rx.recv().unwrap();
+    /// assert_eq!(stall.total_bytes(), 1);
+    /// assert_eq!(stall.silence(), Duration::from_secs(10));
+    ///
+    /// // However long the silence lasts, it is only reported once.
+    /// clock.advance(Duration::from_secs(60));
+    /// assert!(stall_rx.recv_timeout(Duration::from_millis(10)).is_err());
+    ///
+    /// // Until data resumes, and a new silence begins.
+    /// handle.release(b"bc");
+    /// assert_eq!(reader.read(&mut buf)?, 2);
+    /// clock.advance(Duration::from_secs(10));
+    ///
+    /// let stall = stall_rx.recv().unwrap();
+    /// assert_eq!(stall.total_bytes(), 3);
+    /// assert_eq!(stall.silence(), Duration::from_secs(10));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Interrupting a read once the source stalls:
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{BufRead, Write},
+    ///     sync::{Arc, OnceLock},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{Builder, Interruptor, is_interrupt, testing::MockClock};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let clock = Arc::new(MockClock::new());
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    ///
+    /// let watched = Arc::new(OnceLock::<Interruptor>::new());
+    /// let (mut reader, interruptor) = Builder::new()
+    ///     .clock(clock.clone())
+    ///     .stall_watchdog(Duration::from_millis(50), {
+    ///         let watched = watched.clone();
+    ///         move |_| {
+    ///             if let Some(interruptor) = watched.get() {
+    ///                 _ = interruptor.interrupt();
+    ///             }
+    ///         }
+    ///     })
+    ///     .pair(pipe_reader);
+    /// watched.set(interruptor).unwrap();
+    ///
+    /// pipe_writer.write_all(b"hello\n")?;
+    /// let mut line = String::new();
+    /// reader.read_line(&mut line)?;
+    /// assert_eq!(line, "hello\n");
+    ///
+    /// // Nothing else is written, so the watchdog steps in.
+    /// std::thread::spawn({
+    ///     let (clock, watch) = (clock.clone(), reader.state_watch());
+    ///     move || clock.advance_when_waiting(&watch, Duration::from_millis(50))
+    /// });
+    /// let err = reader.read_line(&mut line).unwrap_err();
+    /// assert!(is_interrupt(&err));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stall_watchdog(
+        mut self,
+        threshold: Duration,
+        on_stall: impl Fn(StallInfo) + Send + Sync + 'static,
+    ) -> Self {
+        assert!(!threshold.is_zero(), "stall threshold can't be zero");
+        self.stall_watchdog = Some(StallWatchdog { threshold, on_stall: Arc::new(on_stall) });
+        self
+    }
+
+    /// What happens to the worker thread when the [`InterruptReader`]
+    /// is dropped
+    ///
+    /// Dropping the `InterruptReader` always tells the worker thread
+    /// to stop, but if it is blocked on the source, it can only do so
+    /// once the source returns. By default, with
+    /// [`DropBehavior::Detach`], the drop doesn't wait for that.
+    ///
+    /// With [`DropBehavior::Join`], it waits until the worker thread
+    /// is done, so neither it nor the source outlive the
+    /// `InterruptReader`. With [`DropBehavior::JoinTimeout`], it
+    /// waits for at most that long, and then detaches the worker
+    /// thread, calling the function passed to
+    /// [`Builder::on_detach`], if there is one.
+    ///
+    /// Sources that can be woken up, like those of [`pair_fd`] or
+    /// [`tcp::pair`], are woken up before waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{Cursor, Read},
+    ///     sync::{
+    ///         Arc,
+    ///         atomic::{AtomicBool, Ordering},
+    ///     },
+    /// };
+    ///
+    /// use interrupt_read::{Builder, DropBehavior};
+    ///
+    /// /// A source that lets us know when it's dropped.
+    /// struct Source(Cursor<&'static [u8]>, Arc<AtomicBool>);
+    ///
+    /// impl Read for Source {
+    ///     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    ///         self.0.read(buf)
+    ///     }
+    /// }
+    ///
+    /// impl Drop for Source {
+    ///     fn drop(&mut self) {
+    ///         self.1.store(true, Ordering::Relaxed);
+    ///     }
+    /// }
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let dropped = Arc::new(AtomicBool::new(false));
+    /// let source = Source(Cursor::new(b"hello"), dropped.clone());
+    /// let (mut reader, _interruptor) = Builder::new().on_drop(DropBehavior::Join).pair(source);
+    ///
+    /// let mut out = String::new();
+    /// reader.read_to_string(&mut out)?;
+    /// assert_eq!(out, "hello");
+    ///
+    /// // The source is gone once the reader is.
+    /// drop(reader);
+    /// assert!(dropped.load(Ordering::Relaxed));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`pair_fd`]: pair_fd
+    pub fn on_drop(mut self, behavior: DropBehavior) -> Self {
+        self.on_drop = behavior;
+        self
+    }
+
+    /// Calls `on_detach` when a [`DropBehavior::JoinTimeout`] runs
+    /// out, with the worker thread that was left behind
+    ///
+    /// This is useful for logging a worker thread that is stuck on a
+    /// source that never returns. With [`pair_with_spawner`], it
+    /// isn't called if the worker never started.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     sync::{Arc, mpsc},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{Builder, DropBehavior, testing::MockClock};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// // Nothing is ever written, so the worker thread stays blocked.
+    /// let (pipe_reader, _pipe_writer) = std::io::pipe()?;
+    ///
+    /// let clock = Arc::new(MockClock::new());
+    /// let timeout = Duration::from_secs(5);
+    /// let (detach_tx, detach_rx) = mpsc::channel();
+    /// let (reader, _interruptor) = Builder::new()
+    ///     .clock(clock.clone())
+    ///     .on_drop(DropBehavior::JoinTimeout(timeout))
+    ///     .on_detach(move |thread| _ = detach_tx.send(thread.id()))
+    ///     .pair(pipe_reader);
+    /// let worker = reader.worker_thread().id();
+    ///
+    /// // While joining, the reader counts as waiting.
+    /// std::thread::spawn({
+    ///     let (clock, watch) = (clock.clone(), reader.state_watch());
+    ///     move || clock.advance_when_waiting(&watch, timeout)
+    /// });
+    ///
+    /// drop(reader);
+    /// assert_eq!(clock.elapsed(), timeout);
+    /// assert_eq!(detach_rx.try_recv().unwrap(), worker);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_detach(
+        mut self,
+        on_detach: impl Fn(&std::thread::Thread) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_detach = Some(OnDetach(Arc::new(on_detach)));
+        self
+    }
+
+    /// Poisons the stream after this many errors from the source in a
+    /// row
+    ///
+    /// Errors from the source don't end the stream, since the worker
+    /// thread goes on reading after them, so a source that is broken
+    /// can return the same error forever. Once the stream is
+    /// poisoned, the worker thread is told to stop, and every read
+    /// operation returns an error with a [`Poisoned`] payload right
+    /// away, of the same kind as the last error. Errors of kind
+    /// [`ErrorKind::Interrupted`] don't count, and reading any data
+    /// starts the count over.
+    ///
+    /// By default, a single [`ErrorKind::BrokenPipe`] or
+    /// [`ErrorKind::UnexpectedEof`] poisons the stream, since the
+    /// source can't recover from those, while other errors never do.
+    /// Setting this replaces that, counting the errors of every kind.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `errors` is `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{ErrorKind, Read};
+    ///
+    /// use interrupt_read::{
+    ///     Builder, Poisoned,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// let (mut reader, _interruptor) =
+    ///     Builder::new()
+    ///         .poison_after(2)
+    ///         .pair(ScriptedReader::new(vec![
+    ///             Step::Error(ErrorKind::ConnectionReset),
+    ///             Step::Error(ErrorKind::ConnectionReset),
+    ///             Step::Data(b"never read".to_vec()),
+    ///         ]));
+    ///
+    /// let mut buf = [0; 16];
+    /// for _ in 0..2 {
+    ///     let err = reader.read(&mut buf).unwrap_err();
+    ///     assert_eq!(err.kind(), ErrorKind::ConnectionReset);
+    /// }
+    /// assert!(reader.is_poisoned());
+    ///
+    /// // From now on, the reader doesn't even wait for the worker thread.
+    /// let err = reader.read(&mut buf).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::ConnectionReset);
+    /// let poisoned = err.get_ref().unwrap().downcast_ref::<Poisoned>().unwrap();
+    /// assert_eq!(poisoned.consecutive_errors(), 2);
+    /// ```
+    pub fn poison_after(mut self, errors: u32) -> Self {
+        assert!(errors > 0, "there must be at least one error");
+        self.poison_after = Some(errors);
+        self
+    }
+
+    /// The [`Clock`] that everything that waits gets the time from
+    ///
+    /// By default, this is the [`SystemClock`]. Replacing it with a
+    /// [`MockClock`] lets tests control the time, check out the
+    /// [`clock`] module for more information.
+    ///
+    /// [`MockClock`]: testing::MockClock
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns a pair of an [`InterruptReader`] and an
+    /// [`Interruptor`], with this configuration.
+    ///
+    /// See [`interrupt_read::pair`] for more information.
+    ///
+    /// [`interrupt_read::pair`]: pair
+    pub fn pair<R: Read + Send + 'static>(self, reader: R) -> (InterruptReader<R>, Interruptor) {
+        self.spawn(reader, None, None, R::read, None, |_, _| Wake::Readable)
+    }
+
+    /// Returns a pair of an [`InterruptReader`] and an
+    /// [`Interruptor`], which transforms every chunk on the worker
+    /// thread, with this configuration.
+    ///
+    /// See [`interrupt_read::pair_with_map`] for more information.
+    ///
+    /// [`interrupt_read::pair_with_map`]: pair_with_map
+    pub fn pair_with_map<R, F>(self, reader: R, map: F) -> (InterruptReader<R>, Interruptor)
+    where
+        R: Read + Send + 'static,
+        F: FnMut(Vec<u8>) -> std::io::Result<Vec<u8>> + Send + 'static,
+    {
+        self.spawn(reader, None, Some(Box::new(map)), R::read, None, |_, _| {
+            Wake::Readable
+        })
+    }
+
+    /// Returns a pair of an [`InterruptReader`] and an
+    /// [`Interruptor`], whose worker thread can be woken up
+    ///
+    /// Check out [`interrupt_read::pair_fd`] for more information.
+    ///
+    /// [`interrupt_read::pair_fd`]: pair_fd
+    #[cfg(unix)]
+    pub fn pair_fd<R: Read + AsFd + Send + 'static>(
+        self,
+        reader: R,
+    ) -> std::io::Result<(SourceReader<R, OwnedFd>, Interruptor)> {
+        let (cancel_rx, cancel_tx) = std::io::pipe()?;
+        let fd = reader.as_fd().try_clone_to_owned()?;
+        let (reader, interruptor) = self.spawn(
+            reader,
+            Some(cancel_tx),
+            None,
+            R::read,
+            None,
+            move |reader, shared| wait_readable(reader.as_fd(), &cancel_rx, shared),
+        );
+        let duplicate = |reader: &R| reader.as_fd().try_clone_to_owned();
+
+        Ok((SourceReader::new(reader, fd, duplicate), interruptor))
+    }
+
+    /// Returns a pair of an [`InterruptReader`] and an
+    /// [`Interruptor`], where the reader is interrupted once
+    /// `timeout` has passed, with this configuration
+    ///
+    /// The time is counted by the [`Builder::clock`]. See
+    /// [`interrupt_read::timeout_reader`] for more information.
+    ///
+    /// [`interrupt_read::timeout_reader`]: timeout_reader
+    pub fn timeout_reader<R: Read + Send + 'static>(
+        self,
+        reader: R,
+        timeout: Duration,
+    ) -> (InterruptReader<R>, Interruptor) {
+        let (reader, interruptor) = self.pair(reader);
+        let clock = interruptor.shared.clock.clone();
+        let deadline = clock.now().checked_add(timeout);
+
+        std::thread::spawn({
+            let interruptor = interruptor.clone();
+            move || {
+                let mut state = interruptor.shared.state();
+                loop {
+                    if state.reader_dropped {
+                        return;
+                    }
+
+                    let state_changed = &interruptor.shared.state_changed;
+                    state = match deadline {
+                        Some(deadline) => {
+                            if clock.now() >= deadline {
+                                break;
+                            }
+                            match state_changed.wait_timeout(state, clock.timeout_until(deadline)) {
+                                Ok((state, _)) => state,
+                                Err(err) => err.into_inner().0,
+                            }
+                        }
+                        None => state_changed
+                            .wait(state)
+                            .unwrap_or_else(PoisonError::into_inner),
+                    };
+                }
+
+                drop(state);
+                _ = interruptor.interrupt_with_reason(format!("timed out after {timeout:?}"));
+            }
+        });
+
+        (reader, interruptor)
+    }
+
+    /// The [`Shared`] state of a new pair, with this configuration
+    fn shared(&self, buffer_size: usize, cancel: Option<PipeWriter>) -> Shared {
+        Shared {
+            coalesce_interrupts: self.coalesce_interrupts,
+            waiting: AtomicU64::new(0),
+            buffered: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+            persistent: AtomicU64::new(0),
+            discard_until: AtomicU64::new(0),
+            token_interrupt: AtomicU64::new(0),
+            observed: AtomicU64::new(0),
+            throttle: AtomicU64::new(self.throttle.map_or(0, |rate| rate.0)),
+            adaptive: self.adaptive.is_some(),
+            buffer_size: AtomicUsize::new(buffer_size),
+            read_size: AtomicUsize::new(0),
+            panic_message: std::sync::OnceLock::new(),
+            recorder: Mutex::new(None),
+            max_latest: match self.overflow {
+                Overflow::Backpressure => None,
+                Overflow::DropOldest { max_buffered_chunks } => Some(max_buffered_chunks),
+            },
+            dropped: AtomicU64::new(0),
+            max_buffered_bytes: self.max_buffered_bytes,
+            held: AtomicUsize::new(0),
+            clock: self.clock.clone(),
+            interruptors: AtomicUsize::new(0),
+            interruptors_created: AtomicU64::new(0),
+            upstream: std::sync::OnceLock::new(),
+            upstream_interrupt: AtomicU64::new(0),
+            claims: Mutex::default(),
+            diversion: Mutex::new(None),
+            diverting: AtomicBool::new(false),
+            watchdog: self.stall_watchdog.as_ref().map(|_| Watchdog {
+                start: self.clock.now(),
+                last_data: AtomicU64::new(0),
+                total: AtomicU64::new(0),
+                stalled: AtomicBool::new(false),
+            }),
+            cancel,
+            #[cfg(unix)]
+            ready: std::sync::OnceLock::new(),
+            #[cfg(all(windows, feature = "windows-cancel"))]
+            worker_thread: std::sync::OnceLock::new(),
+            state: Mutex::default(),
+            state_changed: Condvar::new(),
+            // Lets the worker thread read ahead while the reader is busy.
+            pool: Mutex::new(Pool {
+                free: (1..BUFFERS).map(|_| Vec::new()).collect(),
+                worker_waiting: false,
+                stopped: false,
+                command: None,
+                latest: VecDeque::new(),
+                next_latest: 0,
+            }),
+            pool_changed: Condvar::new(),
+        }
+    }
+
+    /// Returns a pair of an [`InterruptReader`] and an
+    /// [`Interruptor`], whose worker runs wherever `spawner` puts
+    /// it, with this configuration
+    ///
+    /// See [`interrupt_read::pair_with_spawner`] for more
+    /// information.
+    ///
+    /// [`interrupt_read::pair_with_spawner`]: pair_with_spawner
+    pub fn pair_with_spawner<R, S>(self, reader: R, spawner: S) -> (InterruptReader<R>, Interruptor)
+    where
+        R: Read + Send + 'static,
+        S: FnOnce(Box<dyn FnOnce() + Send + 'static>),
+    {
+        let spawner = Box::new(spawner);
+        self.spawn(reader, None, None, R::read, Some(spawner), |_, _| {
+            Wake::Readable
+        })
+    }
+
+    /// Spawns the worker thread, which calls `wait` before every
+    /// `read`
+    ///
+    /// If there is a `spawner`, it is called with the worker instead
+    /// of spawning a thread.
+    fn spawn<R: Read + Send + 'static>(
+        self,
+        mut reader: R,
+        cancel: Option<PipeWriter>,
+        mut map: Option<Map>,
+        read: fn(&mut R, &mut [u8]) -> std::io::Result<usize>,
+        spawner: Option<Spawner<'_>>,
+        mut wait: impl FnMut(&R, &Shared) -> Wake + Send + 'static,
+    ) -> (InterruptReader<R>, Interruptor) {
+        let (event_tx, event_rx) = mpsc::channel();
+        let is_reading = Arc::new(AtomicBool::new(true));
+        let initial_size = match self.adaptive {
+            Some((min, max)) => self.buffer_size.clamp(min, max),
+            None => self.buffer_size,
+        };
+        let shared = Arc::new(self.shared(initial_size, cancel));
+        if let Some(before_read) = &self.before_read {
+            assert!(
+                before_read.reader == std::any::TypeId::of::<R>(),
+                "the hook of Builder::before_each_read takes a different type of source"
+            );
+        }
+
+        let run = {
+            let event_tx = event_tx.clone();
+            let is_reading = is_reading.clone();
+            let shared = shared.clone();
+            let forwarder = PanicForwarder {
+                event_tx: event_tx.clone(),
+                is_reading: is_reading.clone(),
+                shared: shared.clone(),
+            };
+            let worker = move || {
+                #[cfg(feature = "tracing")]
+                let _span =
+                    tracing::debug_span!("worker", buffer_size = self.buffer_size).entered();
+
+                let mut adaptive = self.adaptive.map(|(min, max)| Adaptive::new(min, max));
+                let mut size = initial_size;
+                let mut buf = vec![0; size];
+                let mut pace = Pace::new(&shared);
+                is_reading.store(true, Relaxed);
+
+                let run = |command: Command, reader: &mut R| {
+                    let reply = Event::Reply(command.id, (command.f)(reader));
+                    let sent = event_tx.send(reply).is_ok();
+                    shared.signal_ready();
+                    sent
+                };
+
+                let reader = 'read: loop {
+                    if let Some(command) = shared.take_command()
+                        && !run(command, &mut reader)
+                    {
+                        break reader;
+                    }
+
+                    match wait(&reader, &shared) {
+                        Wake::Readable => {}
+                        Wake::Command => continue,
+                        Wake::Stop => break reader,
+                    }
+
+                    let directive = match &self.before_read {
+                        Some(before_read) => {
+                            let mut f =
+                                before_read.f.lock().unwrap_or_else(PoisonError::into_inner);
+                            f(&mut reader)
+                        }
+                        None => Ok(ReadDirective::Continue),
+                    };
+
+                    let len = pace.read_size(buf.len());
+                    #[cfg(feature = "tracing")]
+                    let instant = Instant::now();
+                    let result = match directive {
+                        Ok(ReadDirective::Continue) => {
+                            checked_len(read(&mut reader, &mut buf[..len]), len)
+                        }
+                        Ok(ReadDirective::SkipAndWait(delay)) => {
+                            trace!(?delay, "skipping a read");
+                            if shared.worker_sleep(delay) {
+                                continue;
+                            }
+                            break reader;
+                        }
+                        Ok(ReadDirective::Stop) => {
+                            debug!("stopped before a read");
+                            _ = event_tx.send(Event::Close);
+                            shared.signal_ready();
+                            break reader;
+                        }
+                        Err(err) => Err(err),
+                    };
+                    match result {
+                        Ok(num_bytes) => {
+                            let arrived = shared.clock.now();
+                            trace!(len = num_bytes, elapsed = ?instant.elapsed(), "read a chunk");
+                            shared.produced(num_bytes);
+                            shared.record(|recorder| recorder.data(&buf[..num_bytes]));
+
+                            if let Some(adaptive) = &mut adaptive {
+                                size = adaptive.update(size, len, num_bytes);
+                            }
+
+                            let chunk = std::mem::take(&mut buf);
+                            let event = match &mut map {
+                                Some(map) if num_bytes > 0 => {
+                                    map_chunk(map, chunk, num_bytes, arrived)
+                                }
+                                _ => Some(Event::Buf(chunk, num_bytes, arrived)),
+                            };
+
+                            // Written into the sink instead, if the chunks are diverted.
+                            let mut spare = None;
+                            let event = match event {
+                                Some(Event::Buf(mut chunk, len, arrived)) if len > 0 => {
+                                    match shared.divert(&chunk[..len]) {
+                                        None => Some(Event::Buf(chunk, len, arrived)),
+                                        Some(Ok(())) => {
+                                            spare = Some(chunk);
+                                            None
+                                        }
+                                        Some(Err((cause, written))) => {
+                                            let err =
+                                                Error::new(cause.kind(), DivertFailed { cause });
+                                            if event_tx.send(Event::Err(err)).is_err() {
+                                                break reader;
+                                            }
+                                            chunk.copy_within(written..len, 0);
+                                            Some(Event::Buf(chunk, len - written, arrived))
+                                        }
+                                    }
+                                }
+                                event => event,
+                            };
+
+                            let sent_buf = matches!(event, Some(Event::Buf(..)));
+                            if let Some(event) = event {
+                                if let Event::Buf(_, len, _) = &event {
+                                    shared.queued.fetch_add(*len, Relaxed);
+                                    shared.held.fetch_add(*len, Relaxed);
+                                }
+                                let event = shared.keep_latest(event);
+                                // This means the InterruptReader has been dropped, so no
+                                // more reading will be done.
+                                if event_tx.send(event).is_err() {
+                                    break reader;
+                                }
+                                shared.signal_ready();
+                            }
+
+                            // Otherwise, the map took the buffer, so it is replaced, unless
+                            // it was diverted.
+                            buf = if let Some(spare) = spare {
+                                spare
+                            } else if sent_buf {
+                                loop {
+                                    match shared.take_buffer() {
+                                        Ok(buf) => break buf,
+                                        // Commands don't need a buffer.
+                                        Err(Some(command)) => {
+                                            if !run(command, &mut reader) {
+                                                break 'read reader;
+                                            }
+                                        }
+                                        // Same as before.
+                                        Err(None) => break 'read reader,
+                                    }
+                                }
+                            } else {
+                                Vec::new()
+                            };
+                            // The spare buffers start out empty, and the others may have
+                            // been resized by the reader.
+                            if adaptive.is_some() {
+                                if buf.len() != size {
+                                    buf.resize(size, 0);
+                                }
+                                if buf.capacity() > size * 2 {
+                                    buf.shrink_to(size);
+                                }
+                            } else {
+                                size = size.max(shared.read_size.load(Relaxed));
+                                if buf.len() < size {
+                                    buf.resize(size, 0);
+                                }
+                            }
+                            shared.buffer_size.store(buf.len(), Relaxed);
+
+                            if !shared.pace(&mut pace, num_bytes) {
+                                break reader;
+                            }
+                        }
+                        // An aborted read is not the source's fault.
+                        Err(err) if was_cancelled(&err) => {
+                            if shared.state().reader_dropped {
+                                break reader;
+                            }
+                        }
+                        // Nobody is left to return it to, which is also how an
+                        // interrupted stage before this one ends up here.
+                        Err(_) if shared.state().reader_dropped => break reader,
+                        // Just a signal landing in this thread.
+                        Err(err)
+                            if self.retry_interrupted
+                                && err.kind() == std::io::ErrorKind::Interrupted =>
+                        {
+                            trace!("retrying an interrupted read");
+                            if shared.state().reader_dropped {
+                                break reader;
+                            }
+                        }
+                        Err(err) => {
+                            debug!(error = %err, "forwarding an error");
+                            shared.record(|recorder| recorder.error(&err));
+                            if event_tx.send(Event::Err(err)).is_err() {
+                                break reader;
+                            }
+                            shared.signal_ready();
+                        }
+                    }
+                };
+                debug!("worker thread stopped");
+                is_reading.store(false, Relaxed);
+                reader
+            };
+
+            move || forwarder.run(worker)
+        };
+
+        let handle = match spawner {
+            None => WorkerHandle::Thread(std::thread::spawn(run)),
+            Some(spawner) => {
+                let thread = Arc::new(std::sync::OnceLock::new());
+                let (result_tx, result_rx) = mpsc::sync_channel(1);
+                spawner(Box::new({
+                    let thread = thread.clone();
+                    move || {
+                        _ = thread.set(std::thread::current());
+                        // The panic was already forwarded, and shouldn't take the
+                        // spawner's thread down.
+                        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(run));
+                        _ = result_tx.send(result);
+                    }
+                }));
+                WorkerHandle::Spawned(result_rx, thread)
+            }
+        };
+
+        if let Some(StallWatchdog { threshold, on_stall }) = self.stall_watchdog {
+            let shared = shared.clone();
+            std::thread::spawn(move || watch_stalls(&shared, threshold, &*on_stall));
+        }
+
+        #[cfg(all(windows, feature = "windows-cancel"))]
+        if let WorkerHandle::Thread(join_handle) = &handle
+            && let Ok(handle) = join_handle.as_handle().try_clone_to_owned()
+        {
+            _ = shared.worker_thread.set(handle);
+        }
+
+        let interrupt_reader = InterruptReader {
+            is_reading,
+            buffer: None,
+            event_rx,
+            shared: ReaderHandle(shared.clone()),
+            last_skipped: 0,
+            last_partial_fill: 0,
+            closed: false,
+            pending: VecDeque::new(),
+            interrupt_check: self.interrupt_check,
+            skip_checks_without_interruptors: self.skip_checks_without_interruptors,
+            checked_without_interruptors: None,
+            eof: self.eof,
+            unchecked: 0,
+            commands: 0,
+            delivered: 0,
+            last_chunk_arrival: None,
+            last_error_kind: None,
+            consecutive_errors: 0,
+            poison_after: self.poison_after,
+            poisoned: false,
+            worker: Worker {
+                handle: Some(handle),
+                shared: shared.clone(),
+                on_drop: self.on_drop,
+                on_detach: self.on_detach,
+            },
+            event_tx: event_tx.clone(),
+        };
+        let interruptor = Interruptor::new(event_tx, shared);
+
+        (interrupt_reader, interruptor)
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An interruptable, buffered [`Read`]er.
+///
+/// This reader is created by wrapping a `Read` struct in the
+/// [`interrupt_read::pair`] function, which also returns an
+/// [`Interruptor`], which is capable of sending interrupt signals,
+/// which make any `read` operations on the `InterruptReader` return
+/// an error of kind [`ErrorKind::Other`], with a payload of
+/// [`InterruptReceived`].
+///
+/// When an interrupt is received, _the underlying data is not lost_,
+/// it still exists, and if you call a reading function again, it will
+/// be retrieved, unless another interrupt is sent before that.
+///
+/// You can check if an [`std::io::Error`] is of this type by
+/// calling the [`is_interrupt`] function.
+///
+/// # Examples
+///
+/// One potential application of this struct is if you want to stop a
+/// thread that is reading from the stdout of a child process without
+/// necessarily terminating said childrop_:
+///
+/// ```rust
+/// use std::{
+///     io::{BufRead, ErrorKind},
+///     process::{Command, Stdio},
+///     sync::mpsc,
+/// };
+///
+/// use interrupt_read::{child, is_interrupt};
+///
+/// # match main() {
+/// #     Ok(()) => {}
+/// #     Err(err) => panic!("{err}")
+/// # }
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // Prints "hello\n" three times, and then hangs.
+/// let child = Command::new("bash")
+///     .args(["-c", r#"for _ in 1 2 3; do echo "hello"; done; sleep 1000"#])
+///     .stdout(Stdio::piped())
+///     .spawn()?;
+///
+/// // The child will be killed once `_child_guard` is dropped.
+/// let (mut stdout, interruptor, _child_guard) = child::pair(child)?;
+///
+/// let (line_tx, line_rx) = mpsc::channel();
+/// let join_handle = std::thread::spawn(move || {
+///     let mut string = String::new();
+///     loop {
+///         match stdout.read_line(&mut string) {
+///             Ok(0) => break Ok(string),
+///             Ok(_) => _ = line_tx.send(()),
+///             Err(err) if is_interrupt(&err) => {
+///                 break Ok(string);
+///             }
+///             Err(err) => break Err(err),
+///         }
+///     }
+/// });
+///
+/// // After the third line, the child is stuck, and so is the thread.
+/// for _ in 0..3 {
+///     line_rx.recv()?;
+/// }
+///
+/// interruptor.interrupt()?;
+///
+/// let result = join_handle.join().unwrap()?;
+///
+/// assert_eq!(result, "hello\nhello\nhello\n");
+///
+/// Ok(())
+/// # }
+/// ```
+///
+/// # Compared to other readers
+///
+/// `InterruptReader` follows the contracts of [`Read`] and
+/// [`BufRead`] like [`BufReader`] does: reading into an empty buffer
+/// returns `Ok(0)` right away, consuming more than [`fill_buf`]
+/// returned only consumes what it returned, and, at the end of the
+/// stream, every read operation returns nothing, without consuming
+/// anything. There are some intentional differences though:
+///
+/// - `Ok(0)` is the end of the stream as reported by the source, but
+///   it isn't necessarily final. Like with a [`BufReader`] over a
+///   terminal, if the source returns more data afterwards, the next
+///   read operation returns it. With [`Builder::eof`], `read_to_end`
+///   and `read_to_string` can skip it altogether.
+/// - Errors, including interrupts, never consume any data. And an
+///   interrupt that arrived takes precedence over the data that was
+///   already read from the source, but not yet returned by
+///   [`fill_buf`].
+/// - The size of what [`fill_buf`] returns depends on what the worker
+///   thread read in one go, not on a buffer capacity.
+///
+/// [`interrupt_read::pair`]: pair
+/// [`ErrorKind::Other`]: std::io::ErrorKind::Other
+/// [`BufReader`]: std::io::BufReader
+/// [`fill_buf`]: BufRead::fill_buf
+#[derive(Debug)]
+pub struct InterruptReader<R> {
+    is_reading: Arc<AtomicBool>,
+    buffer: Option<Buffer>,
+    event_rx: mpsc::Receiver<Event>,
+    shared: ReaderHandle,
+    last_skipped: u64,
+    last_partial_fill: usize,
+    closed: bool,
+    /// Events that were received, but not handled yet.
+    pending: VecDeque<Event>,
+    interrupt_check: InterruptCheck,
+    skip_checks_without_interruptors: bool,
+    /// How many [`Interruptor`]s were created when interrupts were
+    /// last checked for, if every one of them was dropped by then.
+    checked_without_interruptors: Option<u64>,
+    eof: Eof,
+    /// Bytes consumed since interrupts were last checked for.
+    unchecked: usize,
+    /// How many [`Command`]s were sent to the worker thread.
+    commands: u64,
+    /// Bytes delivered to the consumer since creation.
+    delivered: u64,
+    /// When the last chunk that was received arrived from the source.
+    last_chunk_arrival: Option<Instant>,
+    last_error_kind: Option<ErrorKind>,
+    /// Errors from the source since data was last delivered.
+    consecutive_errors: u32,
+    /// How many errors poison the stream, if not the default.
+    poison_after: Option<u32>,
+    poisoned: bool,
+    worker: Worker<R>,
+    /// For [`InterruptReader::interruptor`].
+    event_tx: mpsc::Sender<Event>,
+}
+
+/// An [`InterruptReader`] over any source, acquired through
+/// [`pair_boxed`]
+pub type DynInterruptReader = InterruptReader<Box<dyn Read + Send + 'static>>;
+
+impl<R: Read> InterruptReader<R> {
+    /// Unwraps this `InterruptReader`, returning the underlying
+    /// reader.
+    ///
+    /// Note that any leftover data in the internal buffer is lost.
+    /// Therefore, a following read from the underlying reader may
+    /// lead to data loss. If you want to keep that data, use
+    /// [`InterruptReader::into_parts`] instead.
+    ///
+    /// This may return [`Err`] if the underlying joined thread has
+    /// panicked, probably because the [`Read`]er has done so.
+    pub fn into_inner(self) -> std::thread::Result<R> {
+        debug!("unwrapping the reader");
+        let Self { event_rx, shared, worker, .. } = self;
+        let stage = shared.0.clone();
+        drop((event_rx, shared));
+        let reader = worker.join();
+        stage.withdraw_upstream_interrupt();
+        reader
+    }
+
+    /// Unwraps this `InterruptReader`, returning the data that was
+    /// read ahead and the underlying reader
+    ///
+    /// Unlike [`InterruptReader::into_inner`], no data is lost: the
+    /// returned bytes are everything that was read from the source,
+    /// but not yet consumed, so they come before anything that is
+    /// read from the source afterwards. Pass both to
+    /// [`InterruptReader::from_parts`] in order to make the source
+    /// interruptable again. Interrupts and errors that weren't
+    /// returned yet are dropped.
+    ///
+    /// Like `into_inner`, this waits for the worker thread's current
+    /// `read` to return, and returns [`Err`] if the worker thread has
+    /// panicked.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{Cursor, Read};
+    ///
+    /// use interrupt_read::{Builder, InterruptReader};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let data: Vec<u8> = (0..=u8::MAX).cycle().take(64 * 1024).collect();
+    /// let (mut reader, _interruptor) = Builder::new()
+    ///     .buffer_size(1024)
+    ///     .pair(Cursor::new(data.clone()));
+    ///
+    /// let mut out = vec![0; 32 * 1024];
+    /// reader.read_exact(&mut out)?;
+    ///
+    /// let (leftover, cursor) = reader.into_parts().unwrap();
+    /// // The worker thread had read ahead of the consumer.
+    /// assert_eq!(cursor.position() as usize, out.len() + leftover.len());
+    ///
+    /// // Back to being interruptable, without losing anything.
+    /// let (mut reader, _interruptor) = InterruptReader::from_parts(leftover, cursor);
+    /// reader.read_to_end(&mut out)?;
+    /// assert_eq!(out, data);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_parts(self) -> std::thread::Result<(Vec<u8>, R)> {
+        debug!("unwrapping the reader into its parts");
+        let Self {
+            buffer,
+            event_rx,
+            shared,
+            pending,
+            worker,
+            ..
+        } = self;
+
+        let discarding = shared.is_discarding();
+        let latest = shared.0.clone();
+        // Stops the worker thread, whose last chunk is still sent.
+        drop(shared);
+        let reader = worker.join();
+        latest.withdraw_upstream_interrupt();
+        let reader = reader?;
+
+        let mut leftover = buffer
+            .map(|buffer| buffer.remaining().to_vec())
+            .unwrap_or_default();
+        let events = event_rx.try_iter().filter_map(|event| match event {
+            Event::Latest(id) => latest.take_latest(id),
+            event => Some(event),
+        });
+        for event in pending.into_iter().chain(events) {
+            if let Event::Buf(buf, len, _) = event
+                && !discarding
+            {
+                leftover.extend_from_slice(&buf[..len]);
+            }
+        }
+
+        Ok((leftover, reader))
+    }
+
+    /// Returns a pair of an [`InterruptReader`] and an
+    /// [`Interruptor`], where `leftover` is read before anything
+    /// from the `reader`
+    ///
+    /// This is the counterpart to [`InterruptReader::into_parts`],
+    /// and otherwise works just like [`interrupt_read::pair`].
+    ///
+    /// [`interrupt_read::pair`]: pair
+    pub fn from_parts(leftover: Vec<u8>, reader: R) -> (Self, Interruptor)
+    where
+        R: Send + 'static,
+    {
+        let (mut reader, interruptor) = pair(reader);
+        reader.chain_front(leftover);
+        (reader, interruptor)
+    }
+
+    /// Turns this `InterruptReader` into the first stage of a
+    /// pipeline, reading from what `f` wraps it in on a second worker
+    /// thread
+    ///
+    /// This is useful for adapters that do a lot of work, like
+    /// decompressors, which then do it off the consumer's thread. The
+    /// returned [`Interruptor`] interrupts the second stage, and
+    /// since that is what the consumer reads from, it is unblocked
+    /// right away, no matter what the first stage is doing, so the
+    /// interrupt isn't passed on to it.
+    ///
+    /// When the returned `InterruptReader` is dropped, the first
+    /// stage is interrupted, so both worker threads can stop, even if
+    /// the source never ends. [`InterruptReader::into_inner`] works
+    /// the same way, returning the adapter, with the first stage
+    /// inside, which can then be read from as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{ErrorKind, Read, Write},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{
+    ///     is_interrupt, pair,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// /// Expands pairs of a count and a byte.
+    /// struct RunLength<R>(R);
+    ///
+    /// impl<R: Read> Read for RunLength<R> {
+    ///     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    ///         let mut run = [0; 2];
+    ///         match self.0.read_exact(&mut run) {
+    ///             Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(0),
+    ///             result => result?,
+    ///         }
+    ///         let len = usize::from(run[0]).min(buf.len());
+    ///         buf[..len].fill(run[1]);
+    ///         Ok(len)
+    ///     }
+    /// }
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let (reader, _) = pair(ScriptedReader::new(vec![
+    ///     Step::Data(vec![3, b'a', 2, b'b']),
+    ///     Step::Sleep(Duration::from_millis(50)),
+    ///     Step::Data(vec![1, b'c']),
+    /// ]));
+    /// let (mut reader, interruptor) = reader.pipe_through(RunLength);
+    ///
+    /// let mut buf = [0; 5];
+    /// reader.read_exact(&mut buf)?;
+    /// assert_eq!(&buf, b"aaabb");
+    ///
+    /// // While the first stage waits on the source.
+    /// interruptor.interrupt().unwrap();
+    /// assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+    ///
+    /// let mut rest = String::new();
+    /// reader.read_to_string(&mut rest)?;
+    /// assert_eq!(rest, "c");
+    ///
+    /// // Now with a source that doesn't end on its own.
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (reader, _) = pair(pipe_reader);
+    /// let (mut reader, _interruptor) = reader.pipe_through(RunLength);
+    /// pipe_writer.write_all(&[2, b'd'])?;
+    /// reader.read_exact(&mut buf[..2])?;
+    /// assert_eq!(&buf[..2], b"dd");
+    ///
+    /// // Both stages are waiting on the pipe, but this doesn't wait for it.
+    /// let RunLength(mut first) = reader.into_inner().unwrap();
+    /// pipe_writer.write_all(&[1, b'e'])?;
+    /// drop(pipe_writer);
+    /// let mut rest = Vec::new();
+    /// first.read_to_end(&mut rest)?;
+    /// assert_eq!(rest, [1, b'e']);
+    /// assert!(first.into_inner().is_ok());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pipe_through<F, R2>(self, f: F) -> (InterruptReader<R2>, Interruptor)
+    where
+        F: FnOnce(InterruptReader<R>) -> R2,
+        R2: Read + Send + 'static,
+    {
+        let upstream = self.interruptor();
+        let (reader, interruptor) = pair(f(self));
+        _ = reader.shared.upstream.set(upstream);
+        (reader, interruptor)
+    }
+
+    /// Wether the worker thread has finished
+    ///
+    /// The worker thread finishes once the source reaches the end of
+    /// the stream, after an [`Interruptor::close`], or when the
+    /// source panics. This doesn't affect the reader in any way,
+    /// so it is useful for health checks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{io::Read, time::Duration};
+    ///
+    /// use interrupt_read::{
+    ///     pair,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// let (mut reader, _interruptor) = pair(ScriptedReader::new(vec![
+    ///     Step::Data(b"hello".to_vec()),
+    ///     Step::Sleep(Duration::from_millis(50)),
+    ///     Step::Panic("oh no"),
+    /// ]));
+    ///
+    /// let mut buf = [0; 5];
+    /// reader.read_exact(&mut buf).unwrap();
+    /// assert!(!reader.worker_is_finished());
+    /// assert_eq!(reader.worker_panic_message(), None);
+    ///
+    /// // The panic is returned as an error.
+    /// assert!(reader.read(&mut buf).is_err());
+    /// while !reader.worker_is_finished() {
+    ///     std::thread::yield_now();
+    /// }
+    /// assert_eq!(reader.worker_panic_message(), Some("oh no"));
+    ///
+    /// // And the panic itself is still there.
+    /// assert!(reader.into_inner().is_err());
+    /// ```
+    pub fn worker_is_finished(&self) -> bool {
+        self.worker.is_finished()
+    }
+
+    /// A file descriptor that becomes readable when something
+    /// arrives from the worker thread
+    ///
+    /// This is for registering the `InterruptReader` with an event
+    /// loop, like one built on `epoll` or the [`polling`] crate,
+    /// without dedicating a thread to blocking on it. The file
+    /// descriptor becomes readable whenever a chunk, an error, or an
+    /// interrupt is sent to this reader, and is only reset by
+    /// [`InterruptReader::try_fill_buf`], once it returns [`None`].
+    ///
+    /// So, much like with an edge triggered event loop, once it is
+    /// readable, you should call `try_fill_buf` (and [`consume`])
+    /// until it returns `None`. No wakeups are missed that way, but
+    /// there may be spurious ones, where `try_fill_buf` returns
+    /// `None` right away. Don't read from the file descriptor
+    /// yourself.
+    ///
+    /// The file descriptor is created on the first call, which
+    /// returns an [`Err`] if that fails. Until then, nothing is done
+    /// on every event.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{BufRead, Write},
+    ///     os::fd::AsRawFd,
+    /// };
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// let fd = reader.readiness_fd()?.as_raw_fd();
+    /// let is_readable = |timeout_ms| {
+    ///     let mut pollfd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+    ///     unsafe { libc::poll(&mut pollfd, 1, timeout_ms) == 1 }
+    /// };
+    ///
+    /// // It starts out readable, in case something already arrived.
+    /// assert!(is_readable(0));
+    /// assert_eq!(reader.try_fill_buf()?, None);
+    /// assert!(!is_readable(100));
+    ///
+    /// pipe_writer.write_all(b"hello")?;
+    /// assert!(is_readable(1000));
+    /// assert_eq!(reader.try_fill_buf()?, Some(&b"hello"[..]));
+    /// reader.consume(5);
+    /// assert_eq!(reader.try_fill_buf()?, None);
+    /// assert!(!is_readable(100));
+    ///
+    /// interruptor.interrupt()?;
+    /// assert!(is_readable(1000));
+    /// assert!(is_interrupt(&reader.try_fill_buf().unwrap_err()));
+    /// assert_eq!(reader.try_fill_buf()?, None);
+    /// assert!(!is_readable(100));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`polling`]: https://docs.rs/polling
+    /// [`consume`]: BufRead::consume
+    #[cfg(unix)]
+    pub fn readiness_fd(&self) -> std::io::Result<BorrowedFd<'_>> {
+        if let Some(ready) = self.shared.ready.get() {
+            return Ok(ready.reader.as_fd());
+        }
+
+        let (reader, writer) = std::io::pipe()?;
+        set_nonblocking(reader.as_fd());
+        set_nonblocking(writer.as_fd());
+        let ready = ReadyPipe {
+            reader,
+            writer,
+            signaled: AtomicBool::new(false),
+        };
+
+        let ready = self.shared.ready.get_or_init(|| ready);
+        // Something may have arrived before it was created.
+        self.shared.signal_ready();
+
+        Ok(ready.reader.as_fd())
+    }
+
+    /// The worker thread, which reads from the source
+    ///
+    /// If the pair was created through [`pair_with_spawner`], this is
+    /// the thread that the worker runs on, which is only known once
+    /// it starts, so this waits for that.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let (reader, _interruptor) = interrupt_read::pair(std::io::empty());
+    /// assert_ne!(reader.worker_thread().id(), std::thread::current().id());
+    /// ```
+    pub fn worker_thread(&self) -> &std::thread::Thread {
+        self.worker.thread()
+    }
+
+    /// The message of the panic of the worker thread, if it panicked
+    ///
+    /// This is set once the source panics, with the message passed
+    /// to [`panic!`], if it was a string. The panic itself can still
+    /// be retrieved through [`InterruptReader::into_inner`].
+    pub fn worker_panic_message(&self) -> Option<&str> {
+        self.shared.panic_message.get().map(String::as_str)
+    }
+
+    /// The kind of the last error returned by the source, if it
+    /// returned any
+    ///
+    /// Unlike [`InterruptReader::consecutive_errors`], this is kept
+    /// after data is read again.
+    pub fn last_error_kind(&self) -> Option<ErrorKind> {
+        self.last_error_kind
+    }
+
+    /// How many errors from the source were returned since data was
+    /// last read
+    ///
+    /// Errors of kind [`ErrorKind::Interrupted`] aren't counted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{ErrorKind, Read};
+    ///
+    /// use interrupt_read::{
+    ///     Builder,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let (mut reader, _interruptor) =
+    ///     Builder::new()
+    ///         .poison_after(3)
+    ///         .pair(ScriptedReader::new(vec![
+    ///             Step::Error(ErrorKind::ConnectionReset),
+    ///             Step::Error(ErrorKind::ConnectionReset),
+    ///             Step::Data(b"a".to_vec()),
+    ///             Step::Error(ErrorKind::TimedOut),
+    ///             Step::Data(b"b".to_vec()),
+    ///         ]));
+    ///
+    /// let mut buf = [0; 16];
+    /// assert!(reader.read(&mut buf).is_err());
+    /// assert!(reader.read(&mut buf).is_err());
+    /// assert_eq!(reader.consecutive_errors(), 2);
+    ///
+    /// // Data came through before the third error, so it recovered.
+    /// assert_eq!(reader.read(&mut buf)?, 1);
+    /// assert_eq!(reader.consecutive_errors(), 0);
+    ///
+    /// assert!(reader.read(&mut buf).is_err());
+    /// assert_eq!(reader.consecutive_errors(), 1);
+    /// assert_eq!(reader.last_error_kind(), Some(ErrorKind::TimedOut));
+    ///
+    /// assert_eq!(reader.read(&mut buf)?, 1);
+    /// assert_eq!(&buf[..1], b"b");
+    /// assert!(!reader.is_poisoned());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn consecutive_errors(&self) -> u32 {
+        self.consecutive_errors
+    }
+
+    /// Wether the errors from the source have poisoned the stream
+    ///
+    /// Once the stream is poisoned, the worker thread stops, and
+    /// every read operation returns an error with a [`Poisoned`]
+    /// payload. Check out [`Builder::poison_after`] for when that
+    /// happens.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{ErrorKind, Read};
+    ///
+    /// use interrupt_read::{
+    ///     pair,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let (mut reader, _interruptor) = pair(ScriptedReader::new(vec![
+    ///     Step::Data(b"hello".to_vec()),
+    ///     Step::Error(ErrorKind::BrokenPipe),
+    ///     Step::Data(b"world".to_vec()),
+    /// ]));
+    ///
+    /// let mut buf = [0; 16];
+    /// assert_eq!(reader.read(&mut buf)?, 5);
+    /// assert!(!reader.is_poisoned());
+    ///
+    /// // By default, a broken pipe is enough.
+    /// assert!(reader.read(&mut buf).is_err());
+    /// assert!(reader.is_poisoned());
+    /// let err = reader.read(&mut buf).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// When the last chunk that was received arrived from the source
+    ///
+    /// The time is taken by the worker thread, right after the read
+    /// from the source returned, so the difference between this and
+    /// when the chunk is consumed is how long it waited for the
+    /// consumer. This is updated whenever a chunk is received from
+    /// the worker thread, and is [`None`] before the first one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{io::Read, sync::Arc, time::Duration};
+    ///
+    /// use interrupt_read::{
+    ///     Builder,
+    ///     testing::{MockClock, ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let clock = Arc::new(MockClock::new());
+    /// let script = ScriptedReader::new(vec![
+    ///     Step::Data(b"first".to_vec()),
+    ///     Step::Sleep(Duration::from_secs(5)),
+    ///     Step::Data(b"second".to_vec()),
+    /// ]);
+    /// let (mut reader, _interruptor) = Builder::new()
+    ///     .clock(clock.clone())
+    ///     .pair(script.clock(clock.clone()));
+    /// assert_eq!(reader.last_chunk_arrival(), None);
+    ///
+    /// let mut buf = [0; 16];
+    /// assert_eq!(reader.read(&mut buf)?, 5);
+    /// let first = reader.last_chunk_arrival().unwrap();
+    ///
+    /// clock.advance(Duration::from_secs(5));
+    /// assert_eq!(reader.read(&mut buf)?, 6);
+    /// let second = reader.last_chunk_arrival().unwrap();
+    /// assert_eq!(second - first, Duration::from_secs(5));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn last_chunk_arrival(&self) -> Option<Instant> {
+        self.last_chunk_arrival
+    }
+
+    /// Runs a closure on the underlying reader, in the worker thread
+    ///
+    /// The closure runs in between reads, never concurrently with
+    /// one, so this is a way of calling methods like
+    /// [`TcpStream::set_nodelay`] or [`File::metadata`], or of
+    /// changing the settings of a custom reader, without tearing the
+    /// pair down. The data that the worker thread had already read is
+    /// kept, and served as usual.
+    ///
+    /// Note that, if the worker thread is blocked on a `read`, this
+    /// blocks until that `read` returns, which, for sources like
+    /// sockets, may take a while. If that is a concern, use
+    /// [`InterruptReader::try_with_inner`] instead.
+    ///
+    /// This returns an [`Err`] if the worker thread has stopped,
+    /// e.g. after [`Interruptor::close`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, Read};
+    ///
+    /// // Prints the same line forever.
+    /// struct Letters(u8);
+    ///
+    /// impl Read for Letters {
+    ///     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    ///         buf[..2].copy_from_slice(&[self.0, b'\n']);
+    ///         Ok(2)
+    ///     }
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (mut reader, _interruptor) = interrupt_read::pair(Letters(b'a'));
+    ///
+    /// let mut line = String::new();
+    /// reader.read_line(&mut line)?;
+    /// assert_eq!(line, "a\n");
+    ///
+    /// let old = reader.with_inner(|letters| std::mem::replace(&mut letters.0, b'b'))?;
+    /// assert_eq!(old, b'a');
+    ///
+    /// // What was read before the change comes first.
+    /// let mut lines = reader.lines().map(Result::unwrap);
+    /// let line = lines.find(|line| line != "a").unwrap();
+    /// assert_eq!(line, "b");
+    /// assert!(lines.take(10).all(|line| line == "b"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`TcpStream::set_nodelay`]: std::net::TcpStream::set_nodelay
+    /// [`File::metadata`]: std::fs::File::metadata
+    pub fn with_inner<F, T>(&mut self, f: F) -> std::io::Result<T>
+    where
+        F: FnOnce(&mut R) -> T + Send + 'static,
+        T: Send + 'static,
+        R: 'static,
+    {
+        self.run_on_worker(f, false, None).map(|(value, _)| value)
+    }
+
+    /// Like [`InterruptReader::with_inner`], but gives up after a
+    /// `timeout`
+    ///
+    /// If the worker thread doesn't get to the closure in time, this
+    /// returns an [`Err`] of kind [`ErrorKind::TimedOut`], and the
+    /// closure is dropped without running. If the closure was already
+    /// running by then, it runs to completion, but its result is
+    /// thrown away.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{ErrorKind, Read, Write},
+    ///     sync::Arc,
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{Builder, testing::MockClock};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let clock = Arc::new(MockClock::new());
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, _interruptor) = Builder::new().clock(clock.clone()).pair(pipe_reader);
+    ///
+    /// // Gives the worker thread time to block on a read.
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// let timeout = Duration::from_millis(50);
+    /// std::thread::spawn({
+    ///     let (clock, watch) = (clock.clone(), reader.state_watch());
+    ///     move || clock.advance_when_waiting(&watch, timeout)
+    /// });
+    /// let err = reader.try_with_inner(|_| (), timeout).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::TimedOut);
+    ///
+    /// // The closure never ran, and the data is still there.
+    /// pipe_writer.write_all(b"hello")?;
+    /// let mut buf = [0; 5];
+    /// reader.read_exact(&mut buf)?;
+    /// assert_eq!(&buf, b"hello");
+    ///
+    /// // With no more reads blocking, the worker thread gets to it.
+    /// drop(pipe_writer);
+    /// assert_eq!(reader.try_with_inner(|_| 42, timeout)?, 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ErrorKind::TimedOut`]: std::io::ErrorKind::TimedOut
+    pub fn try_with_inner<F, T>(&mut self, f: F, timeout: Duration) -> std::io::Result<T>
+    where
+        F: FnOnce(&mut R) -> T + Send + 'static,
+        T: Send + 'static,
+        R: 'static,
+    {
+        let deadline = self.shared.clock.now() + timeout;
+        self.run_on_worker(f, false, Some(deadline))
+            .map(|(value, _)| value)
+    }
+
+    /// Replaces the underlying reader, keeping everything else
+    ///
+    /// The worker thread swaps the readers in between reads, and
+    /// goes on reading from `new_reader`, so the buffered data, the
+    /// settings, and every [`Interruptor`] of this pair stay the
+    /// same. This is useful for reconnecting to a source, like a
+    /// socket that was closed, without having to hand out new
+    /// `Interruptor`s.
+    ///
+    /// The bytes that were read from the old reader are still
+    /// delivered before those of the new one, but the ends of the
+    /// stream read from it that weren't delivered yet are dropped,
+    /// so the new reader's data follows seamlessly. The old reader is
+    /// returned in the [`ReplaceOutcome`], along with how many of
+    /// its bytes are yet to be delivered.
+    ///
+    /// Like [`InterruptReader::with_inner`], if the worker thread is
+    /// blocked on a `read` of the old reader, this waits for it to
+    /// return. For a socket whose peer went away, a way out of that
+    /// is shutting it down first, e.g. through a handle from
+    /// [`tcp::pair`]. It returns an [`Err`] if the worker thread has
+    /// stopped.
+    ///
+    /// A [`SourceReader`] has its own version of this, which also
+    /// duplicates the handle of `new_reader`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{BufRead, Read},
+    ///     sync::{Arc, Barrier},
+    /// };
+    ///
+    /// use interrupt_read::{
+    ///     is_interrupt, pair,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (mut reader, interruptor) = pair(ScriptedReader::new(vec![Step::Data(b"first ".to_vec())]));
+    ///
+    /// // The first chunk arrived, but wasn't read yet.
+    /// assert_eq!(reader.fill_buf()?, b"first ");
+    ///
+    /// let barrier = Arc::new(Barrier::new(2));
+    /// let outcome = reader.replace_inner(ScriptedReader::new(vec![
+    ///     Step::Data(b"second".to_vec()),
+    ///     Step::WaitForSignal(barrier.clone()),
+    /// ]))?;
+    /// assert_eq!(outcome.old_bytes_pending, 6);
+    /// assert_eq!(outcome.old.remaining().len(), 0);
+    ///
+    /// // The end of the first reader is skipped over.
+    /// let mut buf = [0; 12];
+    /// reader.read_exact(&mut buf)?;
+    /// assert_eq!(&buf, b"first second");
+    ///
+    /// // The old interruptor still works.
+    /// interruptor.interrupt()?;
+    /// assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+    ///
+    /// barrier.wait();
+    /// assert_eq!(reader.read(&mut buf)?, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`tcp::pair`]: crate::tcp::pair
+    pub fn replace_inner(&mut self, new_reader: R) -> std::io::Result<ReplaceOutcome<R>>
+    where
+        R: Send + 'static,
+    {
+        let (old, old_bytes_pending) = self.run_on_worker(
+            move |reader| std::mem::replace(reader, new_reader),
+            false,
+            None,
+        )?;
+
+        // Everything that is pending came from the old reader.
+        for event in std::mem::take(&mut self.pending) {
+            match event {
+                Event::Buf(buf, 0, _) => self.shared.give_buffer(buf, 0),
+                event => self.pending.push_back(event),
+            }
+        }
+
+        Ok(ReplaceOutcome { old, old_bytes_pending })
+    }
+
+    /// Makes the worker thread write what it reads into `sink`,
+    /// instead of sending it to this `InterruptReader`
+    ///
+    /// This is for when all the consumer would do is write the bytes
+    /// somewhere else, which the worker thread can do on its own,
+    /// without handing every chunk over. What was read before this
+    /// call is still returned by this `InterruptReader`, and
+    /// everything after that goes into `sink`, until
+    /// [`InterruptReader::undivert`] is called, so no bytes are lost
+    /// or duplicated.
+    ///
+    /// Interrupts, errors from the source, injected data and the end
+    /// of the stream still come through as usual. If writing into
+    /// `sink` fails, the diversion ends, and an error with a
+    /// [`DivertFailed`] payload is returned by this
+    /// `InterruptReader`, followed by the bytes that weren't written.
+    /// That error doesn't count towards poisoning the stream.
+    ///
+    /// Returns an [`Err`] if something is already being diverted, or
+    /// if the worker thread has stopped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (source, mut source_tx) = std::io::pipe()?;
+    /// let (mut sink_rx, sink) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(source);
+    ///
+    /// source_tx.write_all(b"hello ")?;
+    /// let mut out = vec![0; 6];
+    /// reader.read_exact(&mut out)?;
+    ///
+    /// reader.divert_to(sink)?;
+    /// source_tx.write_all(b"diverted ")?;
+    /// let mut diverted = [0; 9];
+    /// sink_rx.read_exact(&mut diverted)?;
+    /// assert_eq!(&diverted, b"diverted ");
+    ///
+    /// // Nothing comes through, but interrupts still do.
+    /// let join_handle = std::thread::spawn(move || {
+    ///     assert!(is_interrupt(&reader.read(&mut [0; 16]).unwrap_err()));
+    ///     reader
+    /// });
+    ///
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// interruptor.interrupt()?;
+    ///
+    /// let mut reader = join_handle.join().unwrap();
+    /// assert_eq!(reader.undivert()?.bytes_diverted, 9);
+    ///
+    /// source_tx.write_all(b"world")?;
+    /// drop(source_tx);
+    /// reader.read_to_end(&mut out)?;
+    /// assert_eq!(out, b"hello world");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Diverting into a file, in the middle of a bigger stream:
+    ///
+    /// ```rust
+    /// use std::{
+    ///     fs::File,
+    ///     io::Read,
+    ///     sync::{Arc, Barrier},
+    /// };
+    ///
+    /// use interrupt_read::{
+    ///     Builder,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let data: Vec<u8> = (0..900 * 1024u32).map(|i| (i % 251) as u8).collect();
+    /// let (diverted, resumed) = (300 * 1024, 600 * 1024);
+    /// let barriers: Vec<_> = (0..3).map(|_| Arc::new(Barrier::new(2))).collect();
+    ///
+    /// let (mut reader, _interruptor) =
+    ///     Builder::new()
+    ///         .buffer_size(4096)
+    ///         .pair(ScriptedReader::new(vec![
+    ///             Step::Data(data[..diverted].to_vec()),
+    ///             Step::WaitForSignal(barriers[0].clone()),
+    ///             Step::Data(data[diverted..resumed].to_vec()),
+    ///             Step::WaitForSignal(barriers[1].clone()),
+    ///             Step::WaitForSignal(barriers[2].clone()),
+    ///             Step::Data(data[resumed..].to_vec()),
+    ///         ]));
+    ///
+    /// let path = std::env::temp_dir().join(format!("divert-{}", std::process::id()));
+    ///
+    /// let mut out = vec![0; diverted];
+    /// reader.read_exact(&mut out)?;
+    /// reader.divert_to(File::create(&path)?)?;
+    /// barriers[0].wait();
+    /// // By now, the middle of the stream was diverted.
+    /// barriers[1].wait();
+    /// assert_eq!(
+    ///     reader.undivert()?.bytes_diverted,
+    ///     (resumed - diverted) as u64
+    /// );
+    /// barriers[2].wait();
+    /// reader.read_to_end(&mut out)?;
+    ///
+    /// let mut file = std::fs::read(&path)?;
+    /// std::fs::remove_file(&path)?;
+    /// out.splice(diverted..diverted, file.drain(..));
+    /// assert!(out == data);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn divert_to<W: Write + Send + 'static>(&mut self, sink: W) -> std::io::Result<()> {
+        if self.closed || self.worker.is_finished() {
+            return Err(Error::other("the worker thread has stopped"));
+        }
+
+        let mut diversion = self.shared.diversion();
+        if diversion.is_some() {
+            return Err(Error::other("already diverting to a sink"));
+        }
+
+        *diversion = Some(Diversion { sink: Box::new(sink), bytes: 0 });
+        self.shared.diverting.store(true, Relaxed);
+        drop(diversion);
+
+        // In case it is waiting for a buffer.
+        let pool = self
+            .shared
+            .pool
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.shared.pool_changed.notify_all();
+        drop(pool);
+
+        Ok(())
+    }
+
+    /// Stops diverting what is read, after
+    /// [`InterruptReader::divert_to`]
+    ///
+    /// Everything read after this call is returned by this
+    /// `InterruptReader` again. The sink is flushed and dropped.
+    ///
+    /// Returns an [`Err`] if nothing was being diverted, including
+    /// because writing into the sink failed, or if flushing it
+    /// fails.
+    pub fn undivert(&mut self) -> std::io::Result<DivertStats> {
+        let diversion = self.shared.diversion().take();
+        self.shared.diverting.store(false, Relaxed);
+
+        let Some(mut diversion) = diversion else {
+            return Err(Error::other("nothing is being diverted"));
+        };
+        diversion.sink.flush()?;
+
+        Ok(DivertStats { bytes_diverted: diversion.bytes })
+    }
+
+    /// Wether the reader thread is still active.
+    pub fn is_reading(&self) -> bool {
         self.is_reading.load(Relaxed)
     }
 
-    /// A function that returns `true` if the reader thread is still
-    /// active.
-    pub fn is_reading_fn(&self) -> impl Fn() -> bool + Send + Sync + 'static {
-        let is_reading = self.is_reading.clone();
-        move || is_reading.load(Relaxed)
+    /// A function that returns `true` if the reader thread is still
+    /// active.
+    pub fn is_reading_fn(&self) -> impl Fn() -> bool + Send + Sync + 'static {
+        let is_reading = self.is_reading.clone();
+        move || is_reading.load(Relaxed)
+    }
+
+    /// Returns a [`StateWatch`], which can be used to observe the
+    /// [`ReaderState`] of this `InterruptReader`
+    ///
+    /// The state is updated by the `InterruptReader` itself, as it
+    /// waits for data and consumes its internal buffer, without any
+    /// involvement from the worker thread.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{BufRead, Write},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{ReaderState, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, _interruptor) = pair(pipe_reader);
+    /// let watch = reader.state_watch();
+    ///
+    /// assert_eq!(watch.get(), ReaderState::Idle);
+    ///
+    /// let join_handle = std::thread::spawn(move || {
+    ///     let len = reader.fill_buf().map(|buf| buf.len());
+    ///     (len, reader)
+    /// });
+    ///
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// assert_eq!(watch.get(), ReaderState::WaitingForData);
+    ///
+    /// pipe_writer.write_all(b"hello\nworld\n")?;
+    /// let (len, mut reader) = join_handle.join().unwrap();
+    /// assert_eq!(len?, 12);
+    /// assert_eq!(watch.get(), ReaderState::Draining { buffered: 12 });
+    ///
+    /// reader.consume(6);
+    /// assert_eq!(watch.get(), ReaderState::Draining { buffered: 6 });
+    ///
+    /// reader.consume(6);
+    /// assert_eq!(watch.get(), ReaderState::Idle);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn state_watch(&self) -> StateWatch {
+        StateWatch(self.shared.0.clone())
+    }
+
+    /// Reads every byte that is currently available, without
+    /// blocking
+    ///
+    /// This appends the contents of the internal buffer to `out`,
+    /// followed by every chunk of data that the worker thread has
+    /// already sent, returning the total amount of bytes appended.
+    /// If nothing was available, this returns `Ok(0)`.
+    ///
+    /// If an interrupt (or an error from the underlying [`Read`]er)
+    /// is encountered while draining, the bytes gathered up to that
+    /// point are appended to `out` first, and then the error is
+    /// returned. The bytes that came before the interrupt are thus
+    /// never lost, but you won't know how many there were from the
+    /// return value alone, so check the length of `out` if you need
+    /// that.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, Write};
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// let mut out = Vec::new();
+    /// assert_eq!(reader.read_available(&mut out)?, 0);
+    ///
+    /// pipe_writer.write_all(b"hello")?;
+    /// reader.fill_buf()?;
+    /// reader.consume(2);
+    ///
+    /// // The bytes before the interrupt are appended first.
+    /// interruptor.interrupt()?;
+    /// assert!(is_interrupt(&reader.read_available(&mut out).unwrap_err()));
+    /// assert_eq!(out, b"llo");
+    ///
+    /// pipe_writer.write_all(b" world")?;
+    /// while out.len() < 9 {
+    ///     reader.read_available(&mut out)?;
+    /// #   std::thread::yield_now();
+    /// }
+    /// assert_eq!(out, b"llo world");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_available(&mut self, out: &mut Vec<u8>) -> std::io::Result<usize> {
+        self.check_persistent()?;
+        self.check_poisoned()?;
+        let start = out.len();
+
+        if let Some(buffer) = self.buffer.take() {
+            out.extend_from_slice(buffer.remaining());
+            self.shared.give_buffer(buffer.buf, buffer.len);
+            self.update_buffered();
+        }
+
+        while !self.closed {
+            match self.next_event(Wait::Never) {
+                Ok(Event::Buf(buffer, len, _)) if self.shared.is_discarding() => {
+                    self.shared.give_buffer(buffer, len);
+                }
+                Ok(Event::Buf(buffer, len, arrived)) => {
+                    self.last_chunk_arrival = Some(arrived);
+                    if len > 0 {
+                        self.consecutive_errors = 0;
+                    }
+                    out.extend_from_slice(&buffer[..len]);
+                    // Handing it back right away keeps the worker going.
+                    self.shared.give_buffer(buffer, len);
+                }
+                Ok(Event::Err(err)) => return Err(self.source_error(err)),
+                Ok(Event::Interrupt(interrupt)) => {
+                    return Err(self.interrupted(interrupt));
+                }
+                Ok(Event::Close) => self.close(),
+                Ok(Event::Reply(..)) => {}
+                Ok(Event::Latest(_)) => unreachable!("taken when received"),
+                Ok(Event::Relayed(_)) => unreachable!("only sent to a ChildOutputReader"),
+                Err(_) => break,
+            }
+        }
+
+        Ok(out.len() - start)
+    }
+
+    /// Skips over up to `n` bytes, without copying them anywhere
+    ///
+    /// This discards bytes directly from the internal buffer, and
+    /// whole chunks as they arrive from the worker thread, blocking
+    /// just like a read would if the bytes haven't arrived yet. It
+    /// returns how many bytes were skipped, which is less than `n` if
+    /// a read operation would've returned `Ok(0)` (i.e. at the end of
+    /// the stream).
+    ///
+    /// If an interrupt (or any other error) arrives, it is returned
+    /// as usual, and the amount of bytes that were skipped before
+    /// that can be retrieved through
+    /// [`InterruptReader::last_skipped`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    ///
+    /// use interrupt_read::{Builder, is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// // Across chunk boundaries, and past the end of the stream.
+    /// let bytes: Vec<u8> = (0..20).collect();
+    /// let (mut reader, _interruptor) = Builder::new()
+    ///     .buffer_size(4)
+    ///     .pair(std::io::Cursor::new(bytes));
+    ///
+    /// assert_eq!(reader.skip(10)?, 10);
+    /// let mut byte = [0];
+    /// reader.read_exact(&mut byte)?;
+    /// assert_eq!(byte, [10]);
+    /// assert_eq!(reader.skip(100)?, 9);
+    ///
+    /// // Interrupted halfway.
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// pipe_writer.write_all(b"abcd")?;
+    /// let join_handle = std::thread::spawn(move || {
+    ///     assert!(is_interrupt(&reader.skip(6).unwrap_err()));
+    ///     reader
+    /// });
+    ///
+    /// // Waits for the skip to be blocked on more bytes.
+    /// std::thread::sleep(std::time::Duration::from_millis(50));
+    /// interruptor.interrupt()?;
+    ///
+    /// let mut reader = join_handle.join().unwrap();
+    /// assert_eq!(reader.last_skipped(), 4);
+    ///
+    /// pipe_writer.write_all(b"efghij")?;
+    /// assert_eq!(reader.skip(2)?, 2);
+    /// reader.read_exact(&mut byte)?;
+    /// assert_eq!(byte, *b"g");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn skip(&mut self, n: u64) -> std::io::Result<u64> {
+        self.last_skipped = 0;
+
+        while self.last_skipped < n {
+            let len = self.fill_buf()?.len();
+            if len == 0 {
+                break;
+            }
+
+            let len = len.min(usize::try_from(n - self.last_skipped).unwrap_or(usize::MAX));
+            self.consume(len);
+            self.last_skipped += len as u64;
+        }
+
+        Ok(self.last_skipped)
+    }
+
+    /// How many bytes were skipped by the last call to
+    /// [`InterruptReader::skip`]
+    ///
+    /// This is mostly useful when said call returned an [`Err`],
+    /// since you'd have no other way of knowing how far it went.
+    pub fn last_skipped(&self) -> u64 {
+        self.last_skipped
+    }
+
+    /// Reads until `buf` is full, or the end of the stream is reached
+    ///
+    /// This is the middle ground between [`Read::read`], which
+    /// returns as soon as any bytes are available, and
+    /// [`Read::read_exact`], which fails at the end of the stream
+    /// without telling how much it read. It keeps taking chunks until
+    /// `buf` is full, and returns how many bytes were written to it,
+    /// which is `buf.len()`, unless the stream ended first.
+    ///
+    /// If an interrupt or error is returned, the bytes written so far
+    /// stay in `buf`, and how many there are can be retrieved through
+    /// [`InterruptReader::last_partial_fill`], so calling this again
+    /// with the rest of `buf` finishes it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{Cursor, Write};
+    ///
+    /// use interrupt_read::{Builder, is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// // Records spanning many chunks, and one cut short at the end.
+    /// let bytes: Vec<u8> = (0..22).collect();
+    /// let (mut reader, _interruptor) = Builder::new().buffer_size(3).pair(Cursor::new(bytes));
+    ///
+    /// let mut record = [0; 10];
+    /// assert_eq!(reader.read_fill(&mut record)?, 10);
+    /// assert_eq!(record, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    /// assert_eq!(reader.read_fill(&mut record)?, 10);
+    /// assert_eq!(reader.read_fill(&mut record)?, 2);
+    /// assert_eq!(record[..2], [20, 21]);
+    /// assert_eq!(reader.read_fill(&mut record)?, 0);
+    ///
+    /// // Interrupted halfway through a record.
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// pipe_writer.write_all(b"abcd")?;
+    /// let join_handle = std::thread::spawn(move || {
+    ///     let mut record = [0; 6];
+    ///     assert!(is_interrupt(&reader.read_fill(&mut record).unwrap_err()));
+    ///     (reader, record)
+    /// });
+    ///
+    /// // Waits for the fill to be blocked on more bytes.
+    /// std::thread::sleep(std::time::Duration::from_millis(50));
+    /// interruptor.interrupt()?;
+    ///
+    /// let (mut reader, mut record) = join_handle.join().unwrap();
+    /// let filled = reader.last_partial_fill();
+    /// assert_eq!(filled, 4);
+    ///
+    /// pipe_writer.write_all(b"efgh")?;
+    /// assert_eq!(reader.read_fill(&mut record[filled..])?, 2);
+    /// assert_eq!(&record, b"abcdef");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_fill(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.last_partial_fill = 0;
+
+        while self.last_partial_fill < buf.len() {
+            let unfilled = &mut buf[self.last_partial_fill..];
+            let available = self.fill_buf()?;
+            if available.is_empty() {
+                break;
+            }
+
+            let len = available.len().min(unfilled.len());
+            unfilled[..len].copy_from_slice(&available[..len]);
+            self.consume(len);
+            self.last_partial_fill += len;
+        }
+
+        Ok(self.last_partial_fill)
+    }
+
+    /// How many bytes were written by the last call to
+    /// [`InterruptReader::read_fill`]
+    ///
+    /// This is mostly useful when said call returned an [`Err`],
+    /// since you'd have no other way of knowing how far it went.
+    pub fn last_partial_fill(&self) -> usize {
+        self.last_partial_fill
+    }
+
+    /// Leaves the state set by [`Interruptor::interrupt_persistent`]
+    ///
+    /// After this, reads proceed as normal, starting with the data
+    /// that was buffered before the interrupt.
+    pub fn clear_interrupt(&mut self) {
+        let seq = self.shared.persistent.swap(0, Relaxed);
+        if seq > 0 {
+            // So the interrupt isn't observed again later.
+            self.shared.observe(seq);
+        }
+    }
+
+    /// Wether this `InterruptReader` is in the state set by
+    /// [`Interruptor::interrupt_persistent`]
+    pub fn is_interrupted(&self) -> bool {
+        self.shared.persistent.load(Relaxed) > 0
+    }
+
+    /// Changes how often interrupts are checked for while serving
+    /// buffered data
+    ///
+    /// Check out [`InterruptCheck`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    ///
+    /// use interrupt_read::{InterruptCheck, is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (mut reader, interruptor) = pair(b"abcdefgh".as_slice());
+    /// reader.set_interrupt_check(InterruptCheck::EveryNBytes(4));
+    ///
+    /// let mut buf = [0; 2];
+    /// assert_eq!(reader.read(&mut buf)?, 2);
+    ///
+    /// // Only 2 bytes were consumed since the last check.
+    /// interruptor.interrupt()?;
+    /// assert_eq!(reader.read(&mut buf)?, 2);
+    /// assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+    ///
+    /// // Back to the default.
+    /// reader.set_interrupt_check(InterruptCheck::EveryCall);
+    /// interruptor.interrupt()?;
+    /// assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+    /// assert_eq!(reader.read(&mut buf)?, 2);
+    /// assert_eq!(&buf, b"ef");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_interrupt_check(&mut self, check: InterruptCheck) {
+        self.interrupt_check = check;
+    }
+
+    /// The current [`InterruptCheck`] policy
+    pub fn interrupt_check(&self) -> InterruptCheck {
+        self.interrupt_check
+    }
+
+    /// Wether there is still an [`Interruptor`] for this reader
+    ///
+    /// Once every `Interruptor` is dropped, nothing can interrupt the
+    /// reader anymore, except for the interrupts that were already
+    /// sent, so a consumer can stop preparing for them. The
+    /// [`WeakInterruptor`]s don't count, but upgrading one of them,
+    /// or calling [`InterruptReader::interruptor`], makes this `true`
+    /// again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (mut reader, interruptor) = pair(std::io::repeat(b'a'));
+    /// let second = interruptor.clone();
+    /// let weak = interruptor.downgrade();
+    ///
+    /// drop(interruptor);
+    /// assert!(reader.interrupt_possible());
+    /// drop(second);
+    /// assert!(!reader.interrupt_possible());
+    ///
+    /// let interruptor = weak.upgrade().unwrap();
+    /// assert!(reader.interrupt_possible());
+    /// interruptor.interrupt()?;
+    /// assert!(is_interrupt(&reader.read(&mut [0; 4]).unwrap_err()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn interrupt_possible(&self) -> bool {
+        self.shared.interruptors.load(Acquire) > 0
+    }
+
+    /// Returns a new [`Interruptor`] for this reader
+    ///
+    /// This is the same as cloning the one returned along with it,
+    /// but works after every `Interruptor` was dropped, like
+    /// [`WeakInterruptor::upgrade`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (mut reader, interruptor) = pair(std::io::repeat(b'a'));
+    /// drop(interruptor);
+    /// assert!(!reader.interrupt_possible());
+    ///
+    /// let interruptor = reader.interruptor();
+    /// assert!(reader.interrupt_possible());
+    /// interruptor.interrupt()?;
+    /// assert!(is_interrupt(&reader.read(&mut [0; 4]).unwrap_err()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn interruptor(&self) -> Interruptor {
+        Interruptor::new(self.event_tx.clone(), self.shared.0.clone())
+    }
+
+    /// Registers a new claim, for interrupts that are only meant for
+    /// some of the read operations
+    ///
+    /// An interrupt sent through [`Interruptor::interrupt_for`] is
+    /// only returned by read operations made under its claim, through
+    /// [`InterruptReader::claimed`] or
+    /// [`InterruptReader::read_claimed`]. Until then, it is held,
+    /// without affecting any other read operation. Untargeted
+    /// interrupts are returned by whichever read operation comes
+    /// first, as usual.
+    ///
+    /// This is useful when the `InterruptReader` is shared, like
+    /// behind a [`Mutex`], so that an interrupt meant to stop one
+    /// thread isn't taken by another one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::Read,
+    ///     sync::{Arc, Mutex},
+    /// };
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (reader, interruptor) = pair(std::io::repeat(b'a'));
+    /// let reader = Arc::new(Mutex::new(reader));
+    /// let (main, diagnostics) = {
+    ///     let mut reader = reader.lock().unwrap();
+    ///     (reader.register_claim(), reader.register_claim())
+    /// };
+    ///
+    /// for _ in 0..100 {
+    ///     interruptor.interrupt_for(main)?;
+    ///
+    ///     // Another thread gets to the reader first, but not to the interrupt.
+    ///     let reader_clone = reader.clone();
+    ///     std::thread::spawn(move || {
+    ///         let mut reader = reader_clone.lock().unwrap();
+    ///         reader.read_claimed(diagnostics, &mut [0; 4])
+    ///     })
+    ///     .join()
+    ///     .unwrap()?;
+    ///
+    ///     let mut reader = reader.lock().unwrap();
+    ///     let err = reader.read_claimed(main, &mut [0; 4]).unwrap_err();
+    ///     assert!(is_interrupt(&err));
+    ///     assert_eq!(reader.read_claimed(main, &mut [0; 4])?, 4);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Mutex`]: std::sync::Mutex
+    pub fn register_claim(&mut self) -> ClaimId {
+        let mut claims = self.shared.claims();
+        claims.next += 1;
+        let claim = ClaimId(claims.next);
+        claims.registered.push(claim);
+        claim
+    }
+
+    /// Unregisters a claim, from [`InterruptReader::register_claim`]
+    ///
+    /// The interrupts that were held for it aren't dropped, but are
+    /// returned like untargeted ones, by the next read operation, as
+    /// are the ones sent for it afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (mut reader, interruptor) = pair(std::io::repeat(b'a'));
+    /// let claim = reader.register_claim();
+    ///
+    /// interruptor.interrupt_for(claim)?;
+    /// assert_eq!(reader.read(&mut [0; 4])?, 4);
+    ///
+    /// reader.unregister_claim(claim);
+    /// assert!(is_interrupt(&reader.read(&mut [0; 4]).unwrap_err()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn unregister_claim(&mut self, claim: ClaimId) {
+        self.shared
+            .claims()
+            .registered
+            .retain(|&other| other != claim);
+        self.sort_held_interrupts();
+    }
+
+    /// Returns a [`Claimed`] guard, under which read operations are
+    /// made under `claim`
+    ///
+    /// Check out [`InterruptReader::register_claim`] for more
+    /// information.
+    pub fn claimed(&mut self, claim: ClaimId) -> Claimed<'_, R> {
+        let previous = self.shared.claims().current.replace(claim);
+        self.sort_held_interrupts();
+        Claimed { reader: self, previous }
+    }
+
+    /// Reads like [`Read::read`], under `claim`
+    ///
+    /// Check out [`InterruptReader::register_claim`] for more
+    /// information.
+    pub fn read_claimed(&mut self, claim: ClaimId, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.claimed(claim).read(buf)
+    }
+
+    /// Holds the interrupts in `pending` that can't be returned under
+    /// the current claim, and puts back the held ones that can
+    fn sort_held_interrupts(&mut self) {
+        let mut claims = self.shared.claims();
+        if claims.held.is_empty() && claims.registered.is_empty() {
+            return;
+        }
+
+        for event in std::mem::take(&mut self.pending) {
+            match event {
+                Event::Interrupt(interrupt) if !claims.delivers(&interrupt) => {
+                    claims.held.push(interrupt);
+                }
+                event => self.pending.push_back(event),
+            }
+        }
+
+        let held = std::mem::take(&mut claims.held);
+        for interrupt in held.into_iter().rev() {
+            if claims.delivers(&interrupt) {
+                // Interrupts take precedence anyway.
+                self.pending.push_front(Event::Interrupt(interrupt));
+            } else {
+                claims.held.insert(0, interrupt);
+            }
+        }
+    }
+
+    /// Changes how fast the worker thread reads from the source
+    ///
+    /// If `rate` is [`None`], reading is no longer throttled. Check
+    /// out [`Builder::throttle`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{Cursor, Read},
+    ///     sync::Arc,
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{Builder, Rate, testing::MockClock};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// // The clock never moves, so the worker thread waits until woken up.
+    /// let clock = Arc::new(MockClock::new());
+    /// let data = vec![b'a'; 1024 * 1024];
+    /// let (mut reader, _interruptor) = Builder::new()
+    ///     .clock(clock.clone())
+    ///     .throttle(Rate::bytes_per_second(10))
+    ///     .pair(Cursor::new(data.clone()));
+    ///
+    /// let mut buf = [0; 16];
+    /// assert_eq!(reader.read(&mut buf)?, 1);
+    ///
+    /// // Also wakes up the worker thread, if it was waiting.
+    /// reader.set_throttle(None);
+    /// let mut out = buf[..1].to_vec();
+    /// reader.read_to_end(&mut out)?;
+    /// assert_eq!(out, data);
+    /// assert_eq!(clock.elapsed(), Duration::ZERO);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_throttle(&self, rate: Option<Rate>) {
+        self.shared.set_throttle(rate);
+    }
+
+    /// Mirrors every byte that is read into a [`Write`] sink
+    ///
+    /// Bytes are written into the sink when they are delivered to
+    /// the consumer: when they are put in the buffer of a call to
+    /// [`Read::read`], or when they are passed to
+    /// [`BufRead::consume`]. Interrupts and other errors produce no
+    /// writes, and neither does looking at the bytes returned by
+    /// [`BufRead::fill_buf`].
+    ///
+    /// Check out [`TeeReader::sink_errors`] for what happens when
+    /// writing to the sink fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, Read, Write};
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (reader, interruptor) = pair(pipe_reader);
+    /// let mut reader = reader.tee(Vec::new());
+    ///
+    /// pipe_writer.write_all(b"HELO example.com\nDATA 5\nhello")?;
+    /// drop(pipe_writer);
+    ///
+    /// let mut consumed = Vec::new();
+    ///
+    /// let mut line = String::new();
+    /// reader.read_line(&mut line)?;
+    /// consumed.extend_from_slice(line.as_bytes());
+    ///
+    /// interruptor.interrupt()?;
+    /// assert!(is_interrupt(&reader.fill_buf().unwrap_err()));
+    ///
+    /// // Only the consumed part of the buffer is mirrored.
+    /// let available = reader.fill_buf()?;
+    /// assert!(available.starts_with(b"DATA"));
+    /// consumed.extend_from_slice(&available[..4]);
+    /// reader.consume(4);
+    ///
+    /// let mut buf = [0; 64];
+    /// let len = reader.read(&mut buf)?;
+    /// consumed.extend_from_slice(&buf[..len]);
+    ///
+    /// let (_reader, transcript) = reader.into_inner();
+    /// assert_eq!(transcript, consumed);
+    /// assert_eq!(transcript, b"HELO example.com\nDATA 5\nhello");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`TeeReader::sink_errors`]: tee::TeeReader::sink_errors
+    pub fn tee<W: Write>(self, sink: W) -> tee::TeeReader<R, W> {
+        tee::TeeReader::new(self, sink)
+    }
+
+    /// Records the chunks read from the source, along with their
+    /// timing, into `sink`
+    ///
+    /// Unlike [`InterruptReader::tee`], this records what the worker
+    /// thread gets from the source, chunk by chunk, as well as the
+    /// time between them, the errors, and the interrupts received by
+    /// the consumer. The recording can be replayed by a
+    /// [`ReplayReader`], and its format is documented in the
+    /// [`record`] module, which also has an example.
+    ///
+    /// Only what is read after this call is recorded. If a recording
+    /// was already going on, it is stopped, like with
+    /// [`InterruptReader::stop_recording`], and if writing to the
+    /// `sink` fails, the recording stops as well. This returns an
+    /// [`Err`] if the header of the recording couldn't be written.
+    ///
+    /// [`ReplayReader`]: record::ReplayReader
+    pub fn record_to<W: Write + Send + 'static>(&mut self, sink: W) -> std::io::Result<()> {
+        let recorder = record::Recorder::new(Box::new(sink), self.shared.clock.clone())?;
+        let mut current = self
+            .shared
+            .recorder
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(mut old) = current.replace(recorder) {
+            _ = old.flush();
+        }
+
+        Ok(())
+    }
+
+    /// Stops recording, flushing and dropping the sink given to
+    /// [`InterruptReader::record_to`]
+    ///
+    /// This returns an [`Err`] if flushing fails, and does nothing if
+    /// there is no recording going on.
+    pub fn stop_recording(&mut self) -> std::io::Result<()> {
+        let recorder = self
+            .shared
+            .recorder
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        recorder.map_or(Ok(()), |mut recorder| recorder.flush())
+    }
+
+    /// Returns an [`Err`] if the stream was poisoned
+    fn check_poisoned(&self) -> std::io::Result<()> {
+        match self.last_error_kind {
+            Some(kind) if self.poisoned => Err(Error::new(kind, Poisoned {
+                kind,
+                errors: self.consecutive_errors,
+            })),
+            _ => Ok(()),
+        }
+    }
+
+    /// Keeps track of an error from the source, which may poison the
+    /// stream, before it is returned
+    fn source_error(&mut self, err: Error) -> Error {
+        let kind = err.kind();
+        // A failing sink is not the source's fault.
+        if kind == ErrorKind::Interrupted
+            || err.get_ref().is_some_and(|err| err.is::<DivertFailed>())
+        {
+            return err;
+        }
+
+        self.last_error_kind = Some(kind);
+        self.consecutive_errors = self.consecutive_errors.saturating_add(1);
+        let poisons = match self.poison_after {
+            Some(errors) => self.consecutive_errors >= errors,
+            None => matches!(kind, ErrorKind::BrokenPipe | ErrorKind::UnexpectedEof),
+        };
+
+        if poisons && !self.poisoned {
+            debug!(?kind, "the stream is poisoned");
+            self.poisoned = true;
+            self.shared.stop_worker();
+            self.shared.wake_worker();
+        }
+
+        err
+    }
+
+    /// Returns an [`Err`] if a persistent interrupt was sent
+    fn check_persistent(&mut self) -> std::io::Result<()> {
+        match self.shared.persistent.load(Relaxed) {
+            0 => Ok(()),
+            seq => Err(self.interrupted(Interrupt {
+                seq,
+                if_waiting: None,
+                reason: None,
+                label: None,
+                kind: ErrorKind::Other,
+                claim: None,
+            })),
+        }
+    }
+
+    /// The size of the buffer that the worker thread reads into
+    ///
+    /// This is the most that can be read from the source at once. It
+    /// changes as the buffer grows to match big reads (see
+    /// [`Builder::buffer_size`]), or as it adapts to the source, with
+    /// [`Builder::adaptive_buffer`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use interrupt_read::Builder;
+    ///
+    /// let (reader, _interruptor) = Builder::new().buffer_size(512).pair(std::io::empty());
+    /// assert_eq!(reader.worker_buffer_size(), 512);
+    /// ```
+    pub fn worker_buffer_size(&self) -> usize {
+        self.shared.buffer_size.load(Relaxed)
+    }
+
+    /// How many bytes were dropped because the reader fell behind
+    ///
+    /// This is always `0`, unless the pair was created with
+    /// [`Overflow::DropOldest`], through [`Builder::overflow`].
+    pub fn dropped_bytes(&self) -> u64 {
+        self.shared.dropped.load(Relaxed)
+    }
+
+    /// How many bytes are available without blocking
+    ///
+    /// This is the amount of unconsumed bytes in the internal buffer,
+    /// plus the size of every chunk that the worker thread has
+    /// already sent, but that hasn't been received yet.
+    ///
+    /// This number is inherently a snapshot, since the worker thread
+    /// may send more data at any moment.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    ///
+    /// use interrupt_read::pair;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, _interruptor) = pair(pipe_reader);
+    /// assert_eq!(reader.bytes_buffered(), 0);
+    ///
+    /// pipe_writer.write_all(b"hello")?;
+    /// while reader.bytes_buffered() < 5 {
+    ///     std::thread::yield_now();
+    /// }
+    ///
+    /// let mut buf = [0; 2];
+    /// reader.read_exact(&mut buf)?;
+    /// assert_eq!(reader.bytes_buffered(), 3);
+    ///
+    /// let mut buf = [0; 3];
+    /// reader.read_exact(&mut buf)?;
+    /// assert_eq!(reader.bytes_buffered(), 0);
+    ///
+    /// // At the end of the stream, there is nothing buffered.
+    /// drop(pipe_writer);
+    /// assert_eq!(reader.read(&mut buf)?, 0);
+    /// assert_eq!(reader.bytes_buffered(), 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bytes_buffered(&self) -> usize {
+        let pending: usize = (self.pending.iter())
+            .map(|event| match event {
+                Event::Buf(_, len, _) => *len,
+                _ => 0,
+            })
+            .sum();
+
+        self.buffer().len() + pending + self.shared.queued.load(Relaxed)
+    }
+
+    /// The unconsumed bytes in the internal buffer
+    ///
+    /// This mirrors [`BufReader::buffer`]: unlike [`fill_buf`], it
+    /// never blocks, and never looks for new data or interrupts, so
+    /// it returns an empty slice if nothing is buffered. This is
+    /// useful for hand-rolled parsers, which may want to re-inspect
+    /// what they've already got, without committing to a
+    /// [`consume`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, Write};
+    ///
+    /// use interrupt_read::pair;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, _interruptor) = pair(pipe_reader);
+    /// assert_eq!(reader.buffer(), b"");
+    ///
+    /// pipe_writer.write_all(b"hello world")?;
+    /// reader.fill_buf()?;
+    /// assert_eq!(reader.buffer(), b"hello world");
+    ///
+    /// reader.consume(6);
+    /// assert_eq!(reader.buffer(), b"world");
+    ///
+    /// reader.consume(5);
+    /// assert_eq!(reader.buffer(), b"");
+    ///
+    /// // Old bytes from the previous chunk are never exposed.
+    /// pipe_writer.write_all(b"hi")?;
+    /// assert_eq!(reader.buffer(), b"");
+    /// assert_eq!(reader.fill_buf()?, b"hi");
+    /// assert_eq!(reader.buffer(), b"hi");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`BufReader::buffer`]: std::io::BufReader::buffer
+    /// [`fill_buf`]: BufRead::fill_buf
+    /// [`consume`]: BufRead::consume
+    pub fn buffer(&self) -> &[u8] {
+        self.buffer.as_ref().map_or(&[], Buffer::remaining)
+    }
+
+    /// Prepends bytes, to be served before anything else
+    ///
+    /// This is useful when part of the stream was already read by
+    /// some other means, like a header that came in a separate
+    /// buffer, or for "unreading" bytes that were consumed but turned
+    /// out to not be needed yet.
+    ///
+    /// The `bytes` are put in front of the internal buffer, so they
+    /// are served without involving the worker thread, after which
+    /// reading falls through to the data from the worker thread as
+    /// usual. Interrupts still apply while the `bytes` are being
+    /// served, and [`Interruptor::interrupt_and_discard`] discards
+    /// them too, while other interrupts leave them be.
+    ///
+    /// Calling this multiple times stacks the `bytes`: the ones from
+    /// the latest call come out first, each in their original order.
+    /// They are counted by [`InterruptReader::bytes_buffered`], just
+    /// like the rest of the internal buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, Write};
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// reader.chain_front(b"HTTP/1.1 200 OK\r\nContent-".to_vec());
+    /// pipe_writer.write_all(b"Length: 5\r\n")?;
+    ///
+    /// let mut line = String::new();
+    /// reader.read_line(&mut line)?;
+    /// assert_eq!(line, "HTTP/1.1 200 OK\r\n");
+    ///
+    /// // Still inside of the prepended bytes.
+    /// interruptor.interrupt()?;
+    /// assert!(is_interrupt(&reader.fill_buf().unwrap_err()));
+    /// assert_eq!(reader.buffer(), b"Content-");
+    ///
+    /// // Reading across the seam.
+    /// line.clear();
+    /// reader.read_line(&mut line)?;
+    /// assert_eq!(line, "Content-Length: 5\r\n");
+    ///
+    /// // Putting a line back.
+    /// reader.chain_front(line.clone().into_bytes());
+    /// line.clear();
+    /// reader.read_line(&mut line)?;
+    /// assert_eq!(line, "Content-Length: 5\r\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Sniffing the format of a stream, before handing all of it to
+    /// something else:
+    ///
+    /// ```rust
+    /// use std::io::{Cursor, Read};
+    ///
+    /// use interrupt_read::pair;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let data = b"\x89PNG\r\n\x1a\nthe rest of the image".to_vec();
+    /// let (mut reader, _interruptor) = pair(Cursor::new(data.clone()));
+    ///
+    /// let mut magic = [0; 4];
+    /// reader.read_exact(&mut magic)?;
+    /// assert_eq!(&magic, b"\x89PNG");
+    ///
+    /// // Two pushbacks, one on top of the other.
+    /// reader.chain_front(magic[2..].to_vec());
+    /// reader.chain_front(magic[..2].to_vec());
+    /// assert!(reader.bytes_buffered() >= 4);
+    ///
+    /// let mut out = Vec::new();
+    /// reader.read_to_end(&mut out)?;
+    /// assert_eq!(out, data);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn chain_front(&mut self, mut bytes: Vec<u8>) {
+        if bytes.is_empty() {
+            return;
+        }
+
+        if let Some(buffer) = self.buffer.take() {
+            bytes.extend_from_slice(buffer.remaining());
+            self.shared.give_buffer(buffer.buf, buffer.len);
+        }
+
+        let len = bytes.len();
+        self.shared.held.fetch_add(len, Relaxed);
+        self.buffer = Some(Buffer {
+            buf: bytes,
+            pos: 0,
+            len,
+            arrived: self.shared.clock.now(),
+        });
+        self.update_buffered();
+    }
+
+    /// Reads until the source goes quiet for a duration
+    ///
+    /// This keeps appending the incoming data to `out`, returning
+    /// once no new data has arrived for `idle`, or when the end of
+    /// the stream is reached. The returned value is the amount of
+    /// bytes appended. This is useful when dealing with interactive
+    /// programs, where the natural unit isn't a line, but "everything
+    /// that was printed until the program stopped talking".
+    ///
+    /// The first wait, before any data arrives, also lasts for
+    /// `idle`, so if nothing arrives within that time, this returns
+    /// `Ok(0)`.
+    ///
+    /// Interrupts and errors from the underlying [`Read`]er are
+    /// returned right away, with all the data collected up to that
+    /// point preserved in `out`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{BufRead, PipeReader, Write},
+    ///     sync::Arc,
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{Builder, InterruptReader, testing::MockClock};
+    ///
+    /// const IDLE: Duration = Duration::from_millis(100);
+    ///
+    /// /// Reads on another thread, letting `IDLE` pass once it waits.
+    /// fn read_burst(
+    ///     reader: &mut InterruptReader<PipeReader>,
+    ///     clock: &MockClock,
+    ///     out: &mut Vec<u8>,
+    /// ) -> std::io::Result<usize> {
+    ///     let watch = reader.state_watch();
+    ///     std::thread::scope(|s| {
+    ///         let read = s.spawn(|| reader.read_until_idle(out, IDLE));
+    ///         clock.advance_when_waiting(&watch, IDLE);
+    ///         read.join().unwrap()
+    ///     })
+    /// }
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let clock = Arc::new(MockClock::new());
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, _interruptor) = Builder::new().clock(clock.clone()).pair(pipe_reader);
+    ///
+    /// // Nothing arrives within `IDLE`.
+    /// let mut out = Vec::new();
+    /// assert_eq!(read_burst(&mut reader, &clock, &mut out)?, 0);
+    ///
+    /// // Everything that arrived before the source went quiet.
+    /// pipe_writer.write_all(b"hello world\n> ")?;
+    /// assert_eq!(reader.fill_buf()?.len(), 14);
+    /// assert_eq!(read_burst(&mut reader, &clock, &mut out)?, 14);
+    /// assert_eq!(out, b"hello world\n> ");
+    ///
+    /// // The end of the stream doesn't wait.
+    /// out.clear();
+    /// pipe_writer.write_all(b"bye\n")?;
+    /// drop(pipe_writer);
+    /// assert_eq!(reader.read_until_idle(&mut out, IDLE)?, 4);
+    /// assert_eq!(out, b"bye\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_until_idle(&mut self, out: &mut Vec<u8>, idle: Duration) -> std::io::Result<usize> {
+        let start = out.len();
+
+        loop {
+            let deadline = self.shared.clock.now().checked_add(idle);
+            match self.fill_buf_with(Wait::Until(deadline), 0)? {
+                Some([]) | None => break Ok(out.len() - start),
+                Some(buf) => {
+                    out.extend_from_slice(buf);
+                    let len = buf.len();
+                    self.consume(len);
+                }
+            }
+        }
+    }
+
+    /// Reads until a multi-byte pattern is found
+    ///
+    /// This is like [`BufRead::read_until`], but the delimiter can
+    /// be more than one byte long, like a `"\r\n"` pair or a prompt
+    /// such as `"\n> "`. All scanned bytes, including the pattern,
+    /// if it was found, are appended to `out`, and no bytes past the
+    /// end of the pattern are consumed, so they can be read later.
+    ///
+    /// The pattern is detected even if it is split across multiple
+    /// chunks sent by the worker thread.
+    ///
+    /// The returned [`UntilOutcome`] tells you why this function
+    /// returned. If an interrupt arrived, the [`InterruptReceived`]
+    /// error is consumed, and [`UntilOutcome::Interrupted`] is
+    /// returned instead. Other errors are returned as [`Err`], and,
+    /// in both cases, the bytes scanned so far are kept in `out`.
+    ///
+    /// An empty `pattern` is found right away, without reading
+    /// anything.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use interrupt_read::{
+    ///     UntilOutcome, pair,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// // Each step is sent in a separate read.
+    /// let (mut reader, _interruptor) = pair(ScriptedReader::new(vec![
+    ///     // The pattern is right at the chunk boundary.
+    ///     Step::Data(b"hello".to_vec()),
+    ///     Step::Data(b"\n> world".to_vec()),
+    ///     // The pattern is split 1/3 across chunks.
+    ///     Step::Data(b"\n".to_vec()),
+    ///     Step::Data(b"> after".to_vec()),
+    /// ]));
+    ///
+    /// let mut out = Vec::new();
+    /// let outcome = reader.read_until_pattern(b"\n> ", &mut out)?;
+    /// assert_eq!(outcome, UntilOutcome::Found);
+    /// assert_eq!(out, b"hello\n> ");
+    ///
+    /// out.clear();
+    /// let outcome = reader.read_until_pattern(b"\n> ", &mut out)?;
+    /// assert_eq!(outcome, UntilOutcome::Found);
+    /// assert_eq!(out, b"world\n> ");
+    ///
+    /// // Nothing past the pattern was consumed.
+    /// out.clear();
+    /// let outcome = reader.read_until_pattern(b"\n> ", &mut out)?;
+    /// assert_eq!(outcome, UntilOutcome::Eof);
+    /// assert_eq!(out, b"after");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Interrupting in the middle of the search:
+    ///
+    /// ```rust
+    /// use std::{io::Write, time::Duration};
+    ///
+    /// use interrupt_read::{UntilOutcome, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// pipe_writer.write_all(b"partial\r")?;
+    ///
+    /// let join_handle = std::thread::spawn(move || {
+    ///     let mut out = Vec::new();
+    ///     let outcome = reader.read_until_pattern(b"\r\n", &mut out);
+    ///     (outcome.unwrap(), out, reader)
+    /// });
+    ///
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// interruptor.interrupt()?;
+    ///
+    /// let (outcome, out, mut reader) = join_handle.join().unwrap();
+    /// assert_eq!(outcome, UntilOutcome::Interrupted);
+    /// assert_eq!(out, b"partial\r");
+    ///
+    /// // A fresh search doesn't remember the partial match.
+    /// pipe_writer.write_all(b"\nline\r\n")?;
+    /// let mut out = Vec::new();
+    /// assert_eq!(
+    ///     reader.read_until_pattern(b"\r\n", &mut out)?,
+    ///     UntilOutcome::Found
+    /// );
+    /// assert_eq!(out, b"\nline\r\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`InterruptReceived`]: crate::InterruptReceived
+    pub fn read_until_pattern(
+        &mut self,
+        pattern: &[u8],
+        out: &mut Vec<u8>,
+    ) -> std::io::Result<UntilOutcome> {
+        let start = out.len();
+        self.scan_until_pattern(pattern, out, start)
+    }
+
+    /// Reads until `delim` is found, reporting how far it got
+    ///
+    /// This is like [`BufRead::read_until`], but instead of returning
+    /// an [`Err`] when interrupted, it returns an [`UntilProgress`],
+    /// which tells you how many bytes were appended to `out`, and
+    /// wether the delimiter, an interrupt, or the end of the stream
+    /// was reached. No bytes are lost, so calling this again with the
+    /// same `out` continues the same record.
+    ///
+    /// If the delimiter was found, then `terminated` is `true`, even
+    /// if an interrupt arrived as well, in which case it is returned
+    /// by the next read operation. Other errors are returned as
+    /// usual, and what was read so far is kept in `out`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{io::Write, time::Duration};
+    ///
+    /// use interrupt_read::{UntilProgress, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// // An empty record, followed by part of another one.
+    /// pipe_writer.write_all(b";rec")?;
+    ///
+    /// let mut out = Vec::new();
+    /// let progress = reader.read_until_interruptible(b';', &mut out)?;
+    /// assert_eq!(progress, UntilProgress {
+    ///     appended: 1,
+    ///     terminated: true,
+    ///     ..UntilProgress::default()
+    /// });
+    ///
+    /// // Interrupting while it waits for the rest of the record.
+    /// let join_handle = std::thread::spawn(move || {
+    ///     let mut out = Vec::new();
+    ///     let progress = reader.read_until_interruptible(b';', &mut out);
+    ///     (progress.unwrap(), out, reader)
+    /// });
+    ///
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// interruptor.interrupt()?;
+    ///
+    /// let (progress, mut out, mut reader) = join_handle.join().unwrap();
+    /// assert_eq!(progress, UntilProgress {
+    ///     appended: 3,
+    ///     interrupted: true,
+    ///     ..UntilProgress::default()
+    /// });
+    ///
+    /// // Continuing the same record.
+    /// pipe_writer.write_all(b"ord;last")?;
+    /// let progress = reader.read_until_interruptible(b';', &mut out)?;
+    /// assert_eq!((progress.appended, progress.terminated), (4, true));
+    /// assert_eq!(out, b"record;");
+    ///
+    /// drop(pipe_writer);
+    /// out.clear();
+    /// let progress = reader.read_until_interruptible(b';', &mut out)?;
+    /// assert_eq!(progress, UntilProgress {
+    ///     appended: 4,
+    ///     eof: true,
+    ///     ..UntilProgress::default()
+    /// });
+    /// assert_eq!(out, b"last");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_until_interruptible(
+        &mut self,
+        delim: u8,
+        out: &mut Vec<u8>,
+    ) -> std::io::Result<UntilProgress> {
+        let start = out.len();
+        let outcome = self.scan_until_pattern(&[delim], out, start)?;
+
+        Ok(UntilProgress {
+            appended: out.len() - start,
+            terminated: outcome == UntilOutcome::Found,
+            interrupted: outcome == UntilOutcome::Interrupted,
+            eof: outcome == UntilOutcome::Eof,
+        })
+    }
+
+    /// Skips until `delim` is found, reporting how far it got
+    ///
+    /// This is like [`BufRead::skip_until`], but like with
+    /// [`InterruptReader::read_until_interruptible`], an interrupt is
+    /// reported through the returned [`SkipProgress`], instead of as
+    /// an [`Err`]. The bytes, including the delimiter, are consumed
+    /// straight from the internal buffer, without being copied
+    /// anywhere.
+    ///
+    /// When interrupted, everything that was skipped stays skipped,
+    /// and nothing else is, so calling this again continues the same
+    /// skip. Other errors are returned as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, Cursor, Write};
+    ///
+    /// use interrupt_read::{Builder, SkipProgress, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let data = b"one\0a much longer record\0two\0no end".to_vec();
+    /// let (mut reader, _interruptor) = Builder::new().buffer_size(4).pair(Cursor::new(data));
+    ///
+    /// // The delimiter is in the first chunk.
+    /// let progress = reader.skip_until_interruptible(0)?;
+    /// assert_eq!(progress, SkipProgress {
+    ///     skipped: 4,
+    ///     terminated: true,
+    ///     ..SkipProgress::default()
+    /// });
+    ///
+    /// // And now it is several chunks away.
+    /// let progress = reader.skip_until_interruptible(0)?;
+    /// assert_eq!((progress.skipped, progress.terminated), (21, true));
+    ///
+    /// let mut token = Vec::new();
+    /// reader.read_until(0, &mut token)?;
+    /// assert_eq!(token, b"two\0");
+    ///
+    /// let progress = reader.skip_until_interruptible(0)?;
+    /// assert_eq!(progress, SkipProgress {
+    ///     skipped: 6,
+    ///     eof: true,
+    ///     ..SkipProgress::default()
+    /// });
+    ///
+    /// // Interrupting while it waits for the rest of the record.
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// pipe_writer.write_all(b"garbage")?;
+    /// let join_handle = std::thread::spawn(move || {
+    ///     let progress = reader.skip_until_interruptible(0);
+    ///     (progress.unwrap(), reader)
+    /// });
+    ///
+    /// std::thread::sleep(std::time::Duration::from_millis(50));
+    /// interruptor.interrupt()?;
+    ///
+    /// let (progress, mut reader) = join_handle.join().unwrap();
+    /// assert_eq!(progress, SkipProgress {
+    ///     skipped: 7,
+    ///     interrupted: true,
+    ///     ..SkipProgress::default()
+    /// });
+    ///
+    /// pipe_writer.write_all(b" and more\0next")?;
+    /// let progress = reader.skip_until_interruptible(0)?;
+    /// assert_eq!((progress.skipped, progress.terminated), (10, true));
+    /// assert_eq!(reader.fill_buf()?, b"next");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn skip_until_interruptible(&mut self, delim: u8) -> std::io::Result<SkipProgress> {
+        let mut progress = SkipProgress::default();
+
+        loop {
+            let buf = match self.fill_buf() {
+                Ok([]) => {
+                    progress.eof = true;
+                    break Ok(progress);
+                }
+                Ok(buf) => buf,
+                Err(err) if is_interrupt(&err) => {
+                    progress.interrupted = true;
+                    break Ok(progress);
+                }
+                Err(err) => break Err(err),
+            };
+
+            let (len, found) = match buf.iter().position(|byte| *byte == delim) {
+                Some(pos) => (pos + 1, true),
+                None => (buf.len(), false),
+            };
+            self.consume(len);
+            progress.skipped += len;
+
+            if found {
+                progress.terminated = true;
+                break Ok(progress);
+            }
+        }
+    }
+
+    /// Wether `delim` is among the bytes that already arrived
+    ///
+    /// This looks through the internal buffer, as well as the chunks
+    /// that the worker thread sent, and that were already taken in,
+    /// without blocking or consuming anything. If it returns `true`,
+    /// then [`InterruptReader::skip_until_interruptible`] won't have
+    /// to wait for the source, unless an interrupt comes first.
+    /// Chunks that are still on their way aren't looked through, so
+    /// `false` doesn't mean that it will block.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, Write};
+    ///
+    /// use interrupt_read::pair;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, _interruptor) = pair(pipe_reader);
+    ///
+    /// pipe_writer.write_all(b"token\0rest")?;
+    /// reader.fill_buf()?;
+    /// assert!(reader.buffered_contains(0));
+    ///
+    /// reader.skip_until_interruptible(0)?;
+    /// assert!(!reader.buffered_contains(0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn buffered_contains(&self, delim: u8) -> bool {
+        self.buffer().contains(&delim)
+            || self.pending.iter().any(|event| match event {
+                Event::Buf(buf, len, _) => buf[..*len].contains(&delim),
+                _ => false,
+            })
+    }
+
+    /// Reads a line, stopping at `deadline`
+    ///
+    /// This is like [`BufRead::read_line`], but the whole operation
+    /// stops once the `deadline` is reached, no matter how much data
+    /// is still arriving. This is unlike a timeout that restarts on
+    /// every chunk, which a source that trickles bytes in could keep
+    /// from ever running out.
+    ///
+    /// The returned [`DeadlineOutcome`] tells you why this function
+    /// returned, along with how many bytes were appended to `out`.
+    /// Interrupts take priority over the deadline, and, like other
+    /// errors, are returned as [`Err`], with the bytes read so far
+    /// kept in `out`. Bytes that weren't part of the line stay in the
+    /// internal buffer.
+    ///
+    /// If the deadline or an interrupt splits a multi-byte character,
+    /// the start of that character is put back into the internal
+    /// buffer, to be read along with the rest of it. Other invalid
+    /// UTF-8 results in an [`ErrorKind::InvalidData`] error, and
+    /// nothing is appended to `out`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{sync::Arc, time::Duration};
+    ///
+    /// use interrupt_read::{
+    ///     Builder, DeadlineOutcome, Readiness,
+    ///     clock::Clock,
+    ///     testing::{BlockingGate, MockClock},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let clock = Arc::new(MockClock::new());
+    /// let (gate, handle) = BlockingGate::new();
+    /// let (mut reader, _interruptor) = Builder::new().clock(clock.clone()).pair(gate);
+    ///
+    /// // One byte every 20ms, and never a newline, so a timeout of
+    /// // 100ms for each wait never runs out.
+    /// for _ in 0..10 {
+    ///     handle.release(b"a");
+    ///     let readiness = reader.poll_read_ready(Some(Duration::from_millis(100)))?;
+    ///     assert_eq!(readiness, Readiness::Data);
+    ///     clock.advance(Duration::from_millis(20));
+    /// }
+    ///
+    /// // But a deadline does.
+    /// let deadline = clock.now() + Duration::from_millis(200);
+    /// std::thread::spawn({
+    ///     let (clock, watch) = (clock.clone(), reader.state_watch());
+    ///     move || clock.advance_when_waiting(&watch, Duration::from_millis(200))
+    /// });
+    /// let mut line = String::new();
+    /// let outcome = reader.read_line_deadline(&mut line, deadline)?;
+    ///
+    /// assert_eq!(outcome, DeadlineOutcome::TimedOut(line.len()));
+    /// assert!(line.len() > 0 && line.bytes().all(|byte| byte == b'a'));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+    pub fn read_line_deadline(
+        &mut self,
+        out: &mut String,
+        deadline: Instant,
+    ) -> std::io::Result<DeadlineOutcome> {
+        let mut bytes = Vec::new();
+        let result = self.read_until_deadline(b'\n', &mut bytes, deadline);
+
+        let whole = matches!(
+            result,
+            Ok(DeadlineOutcome::Complete(_) | DeadlineOutcome::Eof(_))
+        );
+        let put_back = self.push_utf8(bytes, out, whole)?;
+
+        result.map(|outcome| match outcome {
+            DeadlineOutcome::TimedOut(len) => DeadlineOutcome::TimedOut(len - put_back),
+            outcome => outcome,
+        })
+    }
+
+    /// Reads until `byte` is found, stopping at `deadline`
+    ///
+    /// This is like [`BufRead::read_until`], but the whole operation
+    /// stops once the `deadline` is reached. Check out
+    /// [`InterruptReader::read_line_deadline`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{io::Write, sync::Arc, time::Duration};
+    ///
+    /// use interrupt_read::{Builder, DeadlineOutcome, clock::Clock, testing::MockClock};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let clock = Arc::new(MockClock::new());
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, _interruptor) = Builder::new().clock(clock.clone()).pair(pipe_reader);
+    ///
+    /// pipe_writer.write_all(b"one;tw")?;
+    /// let deadline = clock.now() + Duration::from_millis(100);
+    ///
+    /// let mut out = Vec::new();
+    /// let outcome = reader.read_until_deadline(b';', &mut out, deadline)?;
+    /// assert_eq!(outcome, DeadlineOutcome::Complete(4));
+    /// assert_eq!(out, b"one;");
+    ///
+    /// // The deadline is the same for both calls.
+    /// std::thread::spawn({
+    ///     let (clock, watch) = (clock.clone(), reader.state_watch());
+    ///     move || clock.advance_when_waiting(&watch, Duration::from_millis(100))
+    /// });
+    /// let outcome = reader.read_until_deadline(b';', &mut out, deadline)?;
+    /// assert_eq!(outcome, DeadlineOutcome::TimedOut(2));
+    /// assert_eq!(out, b"one;tw");
+    ///
+    /// drop(pipe_writer);
+    /// let deadline = clock.now() + Duration::from_secs(1);
+    /// let outcome = reader.read_until_deadline(b';', &mut out, deadline)?;
+    /// assert_eq!(outcome, DeadlineOutcome::Eof(0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_until_deadline(
+        &mut self,
+        byte: u8,
+        out: &mut Vec<u8>,
+        deadline: Instant,
+    ) -> std::io::Result<DeadlineOutcome> {
+        self.read_until_before(byte, out, Some(deadline))
+    }
+
+    /// Calls `f` on every line, without allocating them
+    ///
+    /// Each line is handed to `f` without the `b'\n'` at the end,
+    /// and without checking that it is valid UTF-8. Lines that are
+    /// within a single chunk are borrowed straight from the internal
+    /// buffer, and only the ones split between chunks are put
+    /// together in a scratch buffer, which is reused for all of them.
+    /// This makes it much cheaper than [`BufRead::lines`] for large
+    /// amounts of text.
+    ///
+    /// This goes on until `f` returns [`ControlFlow::Break`], an
+    /// interrupt arrives, or the end of the stream is reached, which
+    /// is told apart by the returned [`LoopEnd`]. A final line
+    /// without a `b'\n'` is handed to `f` at the end of the stream,
+    /// just like any other. Interrupts are checked for after every
+    /// line, and the part of a line that was read before one is kept
+    /// in the internal buffer, so calling this again continues from
+    /// where it left off.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     ops::ControlFlow,
+    ///     sync::{Arc, Barrier},
+    /// };
+    ///
+    /// use interrupt_read::{
+    ///     LoopEnd, pair,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let barrier = Arc::new(Barrier::new(2));
+    /// let (mut reader, interruptor) = pair(ScriptedReader::new(vec![
+    ///     // These lines cross chunk boundaries.
+    ///     Step::Data(b"one\ntw".to_vec()),
+    ///     Step::Data(b"o\nstop\nfour\n".to_vec()),
+    ///     Step::WaitForSignal(barrier.clone()),
+    ///     Step::Data(b"fi".to_vec()),
+    ///     // No newline at the end.
+    ///     Step::Data(b"ve\nsix".to_vec()),
+    /// ]));
+    ///
+    /// let mut lines = Vec::new();
+    /// let end = reader.for_each_line(|line| {
+    ///     lines.push(String::from_utf8_lossy(line).into_owned());
+    ///     match line {
+    ///         b"stop" => ControlFlow::Break(()),
+    ///         _ => ControlFlow::Continue(()),
+    ///     }
+    /// })?;
+    /// assert_eq!(end, LoopEnd::Break);
+    /// assert_eq!(lines, ["one", "two", "stop"]);
+    ///
+    /// // This arrives in between lines.
+    /// interruptor.interrupt().unwrap();
+    /// let mut collect = |line: &[u8]| {
+    ///     lines.push(String::from_utf8_lossy(line).into_owned());
+    ///     ControlFlow::Continue(())
+    /// };
+    /// assert_eq!(reader.for_each_line(&mut collect)?, LoopEnd::Interrupted);
+    /// barrier.wait();
+    ///
+    /// assert_eq!(reader.for_each_line(&mut collect)?, LoopEnd::Eof);
+    /// assert_eq!(lines, ["one", "two", "stop", "four", "five", "six"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn for_each_line<F>(&mut self, mut f: F) -> std::io::Result<LoopEnd>
+    where
+        F: FnMut(&[u8]) -> ControlFlow<()>,
+    {
+        let mut scratch = Vec::new();
+
+        loop {
+            let buf = match self.fill_buf() {
+                Ok([]) if scratch.is_empty() => break Ok(LoopEnd::Eof),
+                Ok([]) => match f(&scratch) {
+                    ControlFlow::Continue(()) => break Ok(LoopEnd::Eof),
+                    ControlFlow::Break(()) => break Ok(LoopEnd::Break),
+                },
+                Ok(buf) => buf,
+                Err(err) => {
+                    self.put_back(scratch);
+                    break if is_interrupt(&err) {
+                        Ok(LoopEnd::Interrupted)
+                    } else {
+                        Err(err)
+                    };
+                }
+            };
+
+            let Some(pos) = buf.iter().position(|byte| *byte == b'\n') else {
+                scratch.extend_from_slice(buf);
+                let len = buf.len();
+                self.consume(len);
+                continue;
+            };
+
+            let flow = if scratch.is_empty() {
+                f(&buf[..pos])
+            } else {
+                scratch.extend_from_slice(&buf[..pos]);
+                let flow = f(&scratch);
+                scratch.clear();
+                flow
+            };
+            self.consume(pos + 1);
+
+            if flow.is_break() {
+                break Ok(LoopEnd::Break);
+            }
+        }
+    }
+
+    /// Returns the next line, or [`LinePoll::TimedOut`] if it isn't
+    /// complete within `timeout`
+    ///
+    /// Unlike [`InterruptReader::read_line_deadline`], what was read
+    /// of the line is not handed out when timing out, but kept in
+    /// the internal buffer, so the next call returns the whole line.
+    /// The same goes for interrupts, which are returned as usual. So
+    /// this can be called in a loop, doing something else on every
+    /// timeout, without having to keep track of partial lines.
+    ///
+    /// Like with [`BufRead::lines`], the line doesn't include the
+    /// `"\n"` or `"\r\n"` at the end. Invalid UTF-8 results in an
+    /// [`ErrorKind::InvalidData`] error, and the line is consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{io::Write, sync::Arc, time::Duration};
+    ///
+    /// use interrupt_read::{Builder, LinePoll, is_interrupt, testing::MockClock};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let clock = Arc::new(MockClock::new());
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = Builder::new().clock(clock.clone()).pair(pipe_reader);
+    ///
+    /// pipe_writer.write_all(b"hel")?;
+    /// let timeout = Duration::from_millis(100);
+    /// std::thread::spawn({
+    ///     let (clock, watch) = (clock.clone(), reader.state_watch());
+    ///     move || clock.advance_when_waiting(&watch, timeout)
+    /// });
+    /// assert_eq!(reader.next_line_timeout(timeout)?, LinePoll::TimedOut);
+    ///
+    /// interruptor.interrupt()?;
+    /// assert!(is_interrupt(
+    ///     &reader.next_line_timeout(timeout).unwrap_err()
+    /// ));
+    ///
+    /// pipe_writer.write_all(b"lo\r\nworld")?;
+    /// let line = reader.next_line_timeout(timeout)?;
+    /// assert_eq!(line, LinePoll::Line("hello".to_string()));
+    ///
+    /// drop(pipe_writer);
+    /// let line = reader.next_line_timeout(timeout)?;
+    /// assert_eq!(line, LinePoll::Line("world".to_string()));
+    /// assert_eq!(reader.next_line_timeout(timeout)?, LinePoll::Eof);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+    pub fn next_line_timeout(&mut self, timeout: Duration) -> std::io::Result<LinePoll> {
+        let deadline = self.shared.clock.now().checked_add(timeout);
+        let mut line = Vec::new();
+
+        let mut line = match self.read_until_before(b'\n', &mut line, deadline) {
+            Ok(DeadlineOutcome::Complete(_)) => line,
+            Ok(DeadlineOutcome::Eof(_)) if !line.is_empty() => line,
+            Ok(DeadlineOutcome::Eof(_)) => return Ok(LinePoll::Eof),
+            Ok(DeadlineOutcome::TimedOut(_)) => {
+                self.put_back(line);
+                return Ok(LinePoll::TimedOut);
+            }
+            Err(err) => {
+                self.put_back(line);
+                return Err(err);
+            }
+        };
+
+        if line.ends_with(b"\n") {
+            line.pop();
+            if line.ends_with(b"\r") {
+                line.pop();
+            }
+        }
+
+        String::from_utf8(line).map(LinePoll::Line).map_err(|_| {
+            Error::new(
+                std::io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )
+        })
+    }
+
+    /// Returns an iterator over the lines of this reader, yielding
+    /// [`LinePoll::TimedOut`] if a line isn't complete within
+    /// `timeout`
+    ///
+    /// This calls [`InterruptReader::next_line_timeout`] on every
+    /// iteration, ending at the end of the stream, so it only yields
+    /// [`LinePoll::Line`] and [`LinePoll::TimedOut`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{io::Write, sync::Arc, time::Duration};
+    ///
+    /// use interrupt_read::{Builder, LinePoll, testing::MockClock};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let clock = Arc::new(MockClock::new());
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, _interruptor) = Builder::new().clock(clock.clone()).pair(pipe_reader);
+    /// let timeout = Duration::from_millis(300);
+    ///
+    /// // Half of a line, then the rest of it, after the timeout.
+    /// pipe_writer.write_all(b"hel")?;
+    /// std::thread::spawn({
+    ///     let (clock, watch) = (clock.clone(), reader.state_watch());
+    ///     move || clock.advance_when_waiting(&watch, timeout)
+    /// });
+    ///
+    /// let mut polls = reader.timed_lines(timeout);
+    /// assert_eq!(polls.next().transpose()?, Some(LinePoll::TimedOut));
+    ///
+    /// pipe_writer.write_all(b"lo\n")?;
+    /// drop(pipe_writer);
+    /// let line = LinePoll::Line("hello".to_string());
+    /// assert_eq!(polls.next().transpose()?, Some(line));
+    /// assert_eq!(polls.next().transpose()?, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn timed_lines(&mut self, timeout: Duration) -> TimedLines<'_, R> {
+        TimedLines { reader: self, timeout, done: false }
+    }
+
+    /// Reads a single UTF-8 encoded character
+    ///
+    /// This returns [`None`] at the end of the stream. Characters are
+    /// decoded even if their bytes are split between the chunks sent
+    /// by the worker thread. If an interrupt, or any other error,
+    /// arrives in the middle of a character, the bytes read of it are
+    /// put back into the internal buffer, so the next call starts
+    /// over with the same character.
+    ///
+    /// Invalid UTF-8 results in an [`ErrorKind::InvalidData`] error,
+    /// and the bytes of the invalid sequence are skipped. If the
+    /// sequence was invalid because of a byte that can't continue it,
+    /// that byte isn't skipped, as it may start the next character.
+    /// The same error is returned if the stream ends in the middle of
+    /// a character.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::ErrorKind,
+    ///     sync::{Arc, Barrier},
+    /// };
+    ///
+    /// use interrupt_read::{
+    ///     Builder, InterruptCheck, is_interrupt,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let barrier = Arc::new(Barrier::new(2));
+    /// // "😀" is encoded as [0xf0, 0x9f, 0x98, 0x80].
+    /// let (mut reader, interruptor) = Builder::new()
+    ///     .interrupt_check(InterruptCheck::PerChunk)
+    ///     .pair(ScriptedReader::new(vec![
+    ///         Step::Data(vec![b'a', 0xf0, 0x9f]),
+    ///         Step::WaitForSignal(barrier.clone()),
+    ///         Step::Data(vec![0x98, 0x80, b'b']),
+    ///         // A sequence broken by the '(', and a lone continuation byte.
+    ///         Step::Data(vec![0xe2, b'(', 0xa1, b')']),
+    ///     ]));
+    ///
+    /// assert_eq!(reader.read_char()?, Some('a'));
+    ///
+    /// // This only arrives once the first chunk is over, in the middle of
+    /// // the emoji.
+    /// interruptor.interrupt().unwrap();
+    /// assert!(is_interrupt(&reader.read_char().unwrap_err()));
+    ///
+    /// barrier.wait();
+    /// assert_eq!(reader.read_char()?, Some('😀'));
+    ///
+    /// let chars: Vec<_> = reader
+    ///     .chars()
+    ///     .map(|char| char.map_err(|err| err.kind()))
+    ///     .collect();
+    /// assert_eq!(chars, [
+    ///     Ok('b'),
+    ///     Err(ErrorKind::InvalidData),
+    ///     Ok('('),
+    ///     Err(ErrorKind::InvalidData),
+    ///     Ok(')'),
+    /// ]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_char(&mut self) -> std::io::Result<Option<char>> {
+        let invalid = || Error::new(ErrorKind::InvalidData, "stream did not contain valid UTF-8");
+        let mut bytes = [0; 4];
+        let mut len = 0;
+
+        loop {
+            let buf = match self.fill_buf() {
+                Ok([]) if len == 0 => return Ok(None),
+                Ok([]) => return Err(invalid()),
+                Ok(buf) => buf,
+                Err(err) => {
+                    self.put_back(bytes[..len].to_vec());
+                    return Err(err);
+                }
+            };
+
+            let byte = buf[0];
+            let width = utf8_width(if len == 0 { byte } else { bytes[0] });
+
+            if width == 0 || (len > 0 && byte & 0b1100_0000 != 0b1000_0000) {
+                // A byte that doesn't continue the sequence could start the next one.
+                if len == 0 {
+                    self.consume(1);
+                }
+                return Err(invalid());
+            }
+
+            bytes[len] = byte;
+            len += 1;
+            self.consume(1);
+
+            if len == width {
+                // Still catches overlong encodings and surrogates.
+                return match std::str::from_utf8(&bytes[..len]) {
+                    Ok(str) => Ok(str.chars().next()),
+                    Err(_) => Err(invalid()),
+                };
+            }
+        }
+    }
+
+    /// Returns an iterator over the characters of this reader
+    ///
+    /// This calls [`InterruptReader::read_char`] on every iteration,
+    /// ending at the end of the stream. Interrupts and invalid UTF-8
+    /// are yielded as [`Err`]s, and the iterator goes on after them.
+    pub fn chars(&mut self) -> Chars<'_, R> {
+        Chars { reader: self }
+    }
+
+    /// Returns an iterator over the bytes of this reader
+    ///
+    /// Unlike [`Read::bytes`], which goes through a whole
+    /// [`InterruptReader::read`] for every byte, this takes them
+    /// straight from the internal buffer, and only looks for
+    /// interrupts when that runs out, like with
+    /// [`InterruptCheck::PerChunk`]. Persistent interrupts are still
+    /// checked for on every byte.
+    ///
+    /// Interrupts and errors are yielded as [`Err`]s, and the
+    /// iterator goes on after them, ending at the end of the stream.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Cursor;
+    ///
+    /// use interrupt_read::pair;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let data: Vec<u8> = (0..300 * 1024u32).map(|i| (i % 251) as u8).collect();
+    /// let (mut reader, _interruptor) = pair(Cursor::new(data.clone()));
+    ///
+    /// let bytes = reader.bytes_fast().collect::<std::io::Result<Vec<u8>>>()?;
+    /// assert!(bytes == data);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bytes_fast(&mut self) -> BytesFast<'_, R> {
+        BytesFast { reader: self }
+    }
+
+    /// Returns at least `n` bytes from the front of the stream,
+    /// without consuming them
+    ///
+    /// This blocks until at least `n` bytes are in the internal
+    /// buffer, and then returns all of it, just like
+    /// [`BufRead::fill_buf`] would, so they can be looked at before
+    /// deciding what to [`consume`]. If the bytes span multiple
+    /// chunks from the worker thread, they are put together in the
+    /// internal buffer. Keep in mind that that has to be as big as
+    /// `n`, so this is meant for small windows, like the ones a
+    /// tokenizer deals with.
+    ///
+    /// If the end of the stream is reached first, fewer than `n`
+    /// bytes are returned. Interrupts and errors are returned as
+    /// usual, and nothing that was read is lost.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{BufRead, Write},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{
+    ///     is_interrupt, pair,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (mut reader, _interruptor) = pair(ScriptedReader::new(vec![
+    ///     Step::Data(b"GE".to_vec()),
+    ///     Step::Sleep(Duration::from_millis(20)),
+    ///     Step::Data(b"T /index".to_vec()),
+    /// ]));
+    ///
+    /// // The window spans both chunks.
+    /// assert_eq!(&reader.lookahead(4)?[..4], b"GET ");
+    /// assert_eq!(reader.fill_buf()?, b"GET /index");
+    /// reader.consume(4);
+    ///
+    /// // The end of the stream comes first.
+    /// assert_eq!(reader.lookahead(100)?, b"/index");
+    ///
+    /// // Interrupted while waiting for the rest of the window.
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// pipe_writer.write_all(b"PO")?;
+    /// let join_handle = std::thread::spawn(move || {
+    ///     assert!(is_interrupt(&reader.lookahead(4).unwrap_err()));
+    ///     reader
+    /// });
+    ///
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// interruptor.interrupt()?;
+    ///
+    /// let mut reader = join_handle.join().unwrap();
+    /// pipe_writer.write_all(b"ST /")?;
+    /// assert_eq!(&reader.lookahead(4)?[..4], b"POST");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`consume`]: BufRead::consume
+    pub fn lookahead(&mut self, n: usize) -> std::io::Result<&[u8]> {
+        if n == 0 {
+            return Ok(self.buffer());
+        }
+
+        loop {
+            match self.fill_buf()?.len() {
+                0 => return Ok(&[]),
+                len if len >= n => break,
+                _ => {}
+            }
+
+            // Fetches the next chunk, and then puts these in front of it.
+            let buffer = self.buffer.take().unwrap();
+            let head = buffer.remaining().to_vec();
+            self.shared.give_buffer(buffer.buf, buffer.len);
+
+            let result = self.fill_buf().map(<[u8]>::is_empty);
+            self.chain_front(head);
+            if result? {
+                break;
+            }
+        }
+
+        Ok(self.buffer())
+    }
+
+    /// Like [`InterruptReader::read_until_deadline`], but the
+    /// `deadline` is optional
+    fn read_until_before(
+        &mut self,
+        byte: u8,
+        out: &mut Vec<u8>,
+        deadline: Option<Instant>,
+    ) -> std::io::Result<DeadlineOutcome> {
+        let start = out.len();
+        let clock = self.shared.clock.clone();
+
+        loop {
+            let buf = match self.fill_buf_with(Wait::Until(deadline), 0)? {
+                Some([]) => break Ok(DeadlineOutcome::Eof(out.len() - start)),
+                Some(buf) if deadline.is_none_or(|deadline| clock.now() < deadline) => buf,
+                // Even if more data is available.
+                _ => break Ok(DeadlineOutcome::TimedOut(out.len() - start)),
+            };
+
+            if let Some(pos) = buf.iter().position(|b| *b == byte) {
+                out.extend_from_slice(&buf[..=pos]);
+                self.consume(pos + 1);
+                break Ok(DeadlineOutcome::Complete(out.len() - start));
+            }
+
+            let len = buf.len();
+            out.extend_from_slice(buf);
+            self.consume(len);
+        }
+    }
+
+    /// Appends `bytes` to `out`, returning how many bytes at the end
+    /// were put back into the internal buffer
+    ///
+    /// If the bytes aren't a `whole` line, a character split at the
+    /// end is put back, so it can be read along with its other half.
+    fn push_utf8(
+        &mut self,
+        mut bytes: Vec<u8>,
+        out: &mut String,
+        whole: bool,
+    ) -> std::io::Result<usize> {
+        let mut put_back = 0;
+        if !whole
+            && let Err(err) = std::str::from_utf8(&bytes)
+            && err.error_len().is_none()
+        {
+            let split = bytes.split_off(err.valid_up_to());
+            put_back = split.len();
+            self.put_back(split);
+        }
+
+        match String::from_utf8(bytes) {
+            Ok(line) => {
+                out.push_str(&line);
+                Ok(put_back)
+            }
+            Err(_) => Err(Error::new(
+                std::io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )),
+        }
+    }
+
+    /// Moves this reader into a thread that sends its lines through
+    /// a channel
+    ///
+    /// The lines are sent as [`LineMsg::Line`], without the newline
+    /// at the end. Interrupts don't stop the thread, they are sent as
+    /// [`LineMsg::Interrupted`], and, like errors, they don't lose
+    /// what was read of the current line, so reading goes on as with
+    /// the reader itself.
+    ///
+    /// The thread ends at the end of the stream, or when it fails to
+    /// send something because the [`Receiver`] was dropped, and it
+    /// returns the reader, so it can be taken back through the
+    /// [`JoinHandle`]. Whatever it read that wasn't sent, like the
+    /// start of a line, is put back into the reader first. Since the
+    /// thread only notices the `Receiver` was dropped when it has
+    /// something to send, an interrupt can be used to make it
+    /// stop right away.
+    ///
+    /// The channel is unbounded, for one that holds up reading when
+    /// it is full, use
+    /// [`InterruptReader::into_lines_receiver_bounded`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::Read,
+    ///     sync::{Arc, Barrier},
+    /// };
+    ///
+    /// use interrupt_read::{
+    ///     LineMsg, ReaderState, pair,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let barrier = Arc::new(Barrier::new(2));
+    /// let (reader, interruptor) = pair(ScriptedReader::new(vec![
+    ///     Step::Data(b"one\ntw".to_vec()),
+    ///     Step::WaitForSignal(barrier.clone()),
+    ///     Step::Data(b"o\nthree".to_vec()),
+    ///     Step::WaitForSignal(barrier.clone()),
+    /// ]));
+    /// let watch = reader.state_watch();
+    ///
+    /// let (lines, join_handle) = reader.into_lines_receiver();
+    /// assert_eq!(lines.recv()?.line(), Some("one"));
+    ///
+    /// // Interrupting in the middle of the second line.
+    /// for _ in 0..2 {
+    ///     interruptor.interrupt()?;
+    ///     assert!(matches!(lines.recv()?, LineMsg::Interrupted));
+    /// }
+    ///
+    /// barrier.wait();
+    /// assert_eq!(lines.recv()?.line(), Some("two"));
+    ///
+    /// // Once the thread has taken the start of the third line, it
+    /// // waits for the rest, so an interrupt makes it notice that the
+    /// // receiver was dropped.
+    /// while watch.get() != ReaderState::WaitingForData {
+    ///     std::thread::yield_now();
+    /// }
+    /// drop(lines);
+    /// interruptor.interrupt()?;
+    /// let mut reader = join_handle.join().unwrap();
+    ///
+    /// // What it took is put back, so nothing is lost.
+    /// barrier.wait();
+    /// let mut rest = String::new();
+    /// reader.read_to_string(&mut rest)?;
+    /// assert_eq!(rest, "three");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Receiver`]: mpsc::Receiver
+    pub fn into_lines_receiver(self) -> (mpsc::Receiver<LineMsg>, JoinHandle<Self>)
+    where
+        R: Send + 'static,
+    {
+        let (line_tx, line_rx) = mpsc::channel();
+        let join_handle = self.send_lines(move |msg| line_tx.send(msg).map_err(|err| err.0));
+
+        (line_rx, join_handle)
+    }
+
+    /// Like [`InterruptReader::into_lines_receiver`], but the channel
+    /// holds at most `bound` messages
+    ///
+    /// Once the channel is full, the thread stops reading until a
+    /// message is received, so lines pile up in the source instead.
+    /// A `bound` of `0` means each message is handed over directly,
+    /// like in [`mpsc::sync_channel`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{io::Cursor, time::Duration};
+    ///
+    /// use interrupt_read::{LineMsg, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (reader, _interruptor) = pair(Cursor::new(b"a\nb\nc\n".to_vec()));
+    ///
+    /// let (lines, join_handle) = reader.into_lines_receiver_bounded(1);
+    /// std::thread::sleep(Duration::from_millis(50));
+    ///
+    /// // Only one line fits in the channel, so the thread is waiting.
+    /// assert!(!join_handle.is_finished());
+    ///
+    /// let lines: Vec<String> = lines.into_iter().filter_map(LineMsg::into_line).collect();
+    /// assert_eq!(lines, ["a", "b", "c"]);
+    /// assert!(join_handle.join().unwrap().into_inner().is_ok());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_lines_receiver_bounded(
+        self,
+        bound: usize,
+    ) -> (mpsc::Receiver<LineMsg>, JoinHandle<Self>)
+    where
+        R: Send + 'static,
+    {
+        let (line_tx, line_rx) = mpsc::sync_channel(bound);
+        let join_handle = self.send_lines(move |msg| line_tx.send(msg).map_err(|err| err.0));
+
+        (line_rx, join_handle)
+    }
+
+    /// Spawns the thread of [`InterruptReader::into_lines_receiver`],
+    /// which stops once `send` gives the message back
+    fn send_lines(
+        mut self,
+        send: impl Fn(LineMsg) -> Result<(), LineMsg> + Send + 'static,
+    ) -> JoinHandle<Self>
+    where
+        R: Send + 'static,
+    {
+        std::thread::spawn(move || {
+            let mut line = Vec::new();
+            loop {
+                let mut ending: &[u8] = b"";
+                let mut invalid = Vec::new();
+                let msg = match self.read_until_interruptible(b'\n', &mut line) {
+                    Ok(progress) if progress.interrupted => LineMsg::Interrupted,
+                    Ok(_) if line.is_empty() => break self,
+                    Ok(progress) => {
+                        if progress.terminated {
+                            ending = if line.ends_with(b"\r\n") {
+                                b"\r\n"
+                            } else {
+                                b"\n"
+                            };
+                            line.truncate(line.len() - ending.len());
+                        }
+
+                        match String::from_utf8(std::mem::take(&mut line)) {
+                            Ok(line) => LineMsg::Line(line),
+                            Err(err) => {
+                                invalid = err.into_bytes();
+                                LineMsg::Err(Error::new(
+                                    ErrorKind::InvalidData,
+                                    "stream did not contain valid UTF-8",
+                                ))
+                            }
+                        }
+                    }
+                    Err(err) => LineMsg::Err(err),
+                };
+
+                if let Err(msg) = send(msg) {
+                    // Nobody got it, so it goes back where it came from, along
+                    // with the start of a line, if there is one.
+                    let mut unsent = match msg {
+                        LineMsg::Line(line) => line.into_bytes(),
+                        _ => invalid,
+                    };
+                    unsent.extend_from_slice(ending);
+                    unsent.append(&mut line);
+                    self.put_back(unsent);
+                    break self;
+                }
+            }
+        })
+    }
+
+    /// Returns an iterator over the frames separated by `delim`
+    ///
+    /// Each item is a [`FrameEvent`], which is either a complete
+    /// frame, with the delimiter stripped from its end, an
+    /// interrupt, or an error. The delimiter can be any amount of
+    /// bytes long, and is found even if it is split across chunks
+    /// sent by the worker thread.
+    ///
+    /// If an interrupt arrives, or an error is returned, in the
+    /// middle of a frame, the part of it that was read so far is kept
+    /// inside of the iterator, and the frame is completed on the next
+    /// iterations. At the end of the stream, whatever was read after
+    /// the last delimiter is returned as a [`FrameEvent::Partial`],
+    /// unless it is empty, and then the iterator ends.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delim` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Cursor;
+    ///
+    /// use interrupt_read::{FrameEvent, pair};
+    ///
+    /// // Like the output of `find -print0`.
+    /// let (mut reader, _interruptor) = pair(Cursor::new(b"a.txt\0\0b.txt\0c.t".to_vec()));
+    ///
+    /// let frames: Vec<_> = reader
+    ///     .delimited(b"\0")
+    ///     .map(|event| match event {
+    ///         FrameEvent::Frame(frame) => Ok(frame),
+    ///         FrameEvent::Partial(frame) => Err(frame),
+    ///         event => panic!("{event:?}"),
+    ///     })
+    ///     .collect();
+    ///
+    /// assert_eq!(frames, [
+    ///     Ok(b"a.txt".to_vec()),
+    ///     // Consecutive delimiters make an empty frame.
+    ///     Ok(Vec::new()),
+    ///     Ok(b"b.txt".to_vec()),
+    ///     Err(b"c.t".to_vec()),
+    /// ]);
+    /// ```
+    ///
+    /// Multi-byte delimiters, split across chunks and interrupts:
+    ///
+    /// ```rust
+    /// use std::{io::Write, time::Duration};
+    ///
+    /// use interrupt_read::{FrameEvent, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// let join_handle = std::thread::spawn(move || {
+    ///     reader
+    ///         .delimited(b"\r\n\r\n")
+    ///         .map(|event| match event {
+    ///             FrameEvent::Frame(frame) => String::from_utf8(frame).unwrap(),
+    ///             FrameEvent::Interrupted => "interrupted".to_string(),
+    ///             event => panic!("{event:?}"),
+    ///         })
+    ///         .collect::<Vec<_>>()
+    /// });
+    ///
+    /// pipe_writer.write_all(b"first\r\n\r\nsecond\r\n")?;
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// interruptor.interrupt()?;
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// pipe_writer.write_all(b"\r\n")?;
+    /// drop(pipe_writer);
+    ///
+    /// assert_eq!(join_handle.join().unwrap(), [
+    ///     "first",
+    ///     "interrupted",
+    ///     // The frame was completed after the interrupt.
+    ///     "second"
+    /// ]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn delimited(&mut self, delim: &[u8]) -> Delimited<'_, R> {
+        assert!(!delim.is_empty(), "the delimiter can't be empty");
+        Delimited {
+            reader: self,
+            delim: delim.to_vec(),
+            frame: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Returns a reader over at most the next `n` bytes
+    ///
+    /// This is like [`Read::take`], but the returned [`Limited`] only
+    /// borrows the `InterruptReader`, which continues exactly where
+    /// the limit ended once it is dropped, and it implements
+    /// [`BufRead`] on top of the internal buffer, so no bytes past
+    /// the limit are lost. This is useful for framed protocols, where
+    /// the length of each frame comes before it.
+    ///
+    /// Interrupts work as usual inside of the limit, and don't count
+    /// against it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, Cursor, Read};
+    ///
+    /// use interrupt_read::pair;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// // A frame with a header and a body, followed by the next frame.
+    /// let (mut reader, _interruptor) = pair(Cursor::new(b"\x09head:body\x01x".to_vec()));
+    ///
+    /// let mut len = [0; 1];
+    /// reader.read_exact(&mut len)?;
+    /// let mut frame = reader.limit(len[0] as u64);
+    ///
+    /// // Limits can be nested.
+    /// let mut header = String::new();
+    /// frame.limit(5).read_line(&mut header)?;
+    /// assert_eq!(header, "head:");
+    /// assert_eq!(frame.remaining(), 4);
+    ///
+    /// // This ends in the middle of the chunk.
+    /// let mut body = String::new();
+    /// frame.read_to_string(&mut body)?;
+    /// assert_eq!(body, "body");
+    /// drop(frame);
+    ///
+    /// // And the reader picks up right after it.
+    /// let mut rest = Vec::new();
+    /// reader.read_to_end(&mut rest)?;
+    /// assert_eq!(rest, b"\x01x");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Interrupting inside of a limit:
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// pipe_writer.write_all(b"abc")?;
+    /// let mut limited = reader.limit(6);
+    /// let mut buf = [0; 3];
+    /// limited.read_exact(&mut buf)?;
+    ///
+    /// interruptor.interrupt()?;
+    /// assert!(is_interrupt(&limited.read(&mut buf).unwrap_err()));
+    /// assert_eq!(limited.remaining(), 3);
+    ///
+    /// pipe_writer.write_all(b"defghi")?;
+    /// let mut out = String::new();
+    /// limited.read_to_string(&mut out)?;
+    /// assert_eq!(out, "def");
+    /// drop(limited);
+    ///
+    /// reader.read_exact(&mut buf)?;
+    /// assert_eq!(&buf, b"ghi");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn limit(&mut self, n: u64) -> Limited<'_, R> {
+        Limited {
+            reader: self,
+            remaining: n,
+            consumed: 0,
+            parent: None,
+        }
+    }
+
+    /// Takes the next chunk read by the worker thread, without
+    /// copying it
+    ///
+    /// This blocks like [`Read::read`] does, and returns [`None`] at
+    /// the end of the stream. Interrupts are returned as usual. The
+    /// [`Chunk`] owns the buffer that the worker thread read into,
+    /// which is handed back to it once the `Chunk` is dropped. Since
+    /// there are only a few of those buffers, the worker thread stops
+    /// reading while all of them are held.
+    ///
+    /// This yields whole chunks, unless a read operation already
+    /// started on the current one, in which case what is left of it
+    /// is returned. After that, the regular read operations go on
+    /// from the next chunk.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{collections::VecDeque, io::Write};
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// let data: Vec<u8> = (0..=u8::MAX).cycle().take(4 * 1024 * 1024).collect();
+    /// pipe_writer.write_all(&data[..10])?;
+    ///
+    /// let chunk = reader.next_chunk()?.unwrap();
+    /// assert_eq!(&*chunk, &data[..10]);
+    ///
+    /// interruptor.interrupt()?;
+    /// assert!(is_interrupt(&reader.next_chunk().unwrap_err()));
+    ///
+    /// let writer = std::thread::spawn({
+    ///     let data = data.clone();
+    ///     move || pipe_writer.write_all(&data[10..])
+    /// });
+    ///
+    /// let mut received = chunk.len();
+    /// let mut held = VecDeque::from([chunk]);
+    /// while let Some(chunk) = reader.next_chunk()? {
+    ///     assert_eq!(&*chunk, &data[received..received + chunk.len()]);
+    ///     received += chunk.len();
+    ///
+    ///     // Dropping a chunk hands its buffer back.
+    ///     held.push_back(chunk);
+    ///     if held.len() > 2 {
+    ///         held.pop_front();
+    ///     }
+    /// }
+    ///
+    /// writer.join().unwrap()?;
+    /// assert_eq!(received, data.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn next_chunk(&mut self) -> std::io::Result<Option<Chunk>> {
+        if self.fill_buf_with(Wait::Block, 0)?.unwrap().is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(self.take_chunk()))
+    }
+
+    /// Turns this into a [`RawInterruptReader`], which hands out the
+    /// events sent by the worker thread
+    ///
+    /// Check out the [`raw`] module for more information.
+    ///
+    /// [`RawInterruptReader`]: raw::RawInterruptReader
+    pub fn into_raw(self) -> raw::RawInterruptReader<R> {
+        raw::RawInterruptReader::new(self)
+    }
+
+    /// Takes the unconsumed part of the internal buffer, which must
+    /// not be empty, as a [`Chunk`]
+    fn take_chunk(&mut self) -> Chunk {
+        let Buffer { buf, pos, len, arrived } = self.buffer.take().unwrap();
+        self.unchecked += len - pos;
+        self.delivered += (len - pos) as u64;
+        self.update_buffered();
+
+        Chunk {
+            buf,
+            pos,
+            len,
+            arrived,
+            shared: self.shared.0.clone(),
+        }
+    }
+
+    /// Reads a length-prefixed frame
+    ///
+    /// Each frame starts with its length, encoded as configured by
+    /// the [`LengthPrefix`], followed by that many bytes, which are
+    /// returned in [`MessageEvent::Frame`], without the prefix.
+    ///
+    /// Unlike reading the prefix and payload with
+    /// [`Read::read_exact`], nothing is lost if an interrupt arrives
+    /// in the middle of a frame: what was read of it, prefix
+    /// included, is put back into the internal buffer, and
+    /// [`MessageEvent::Interrupted`] is returned, so the next call
+    /// resumes the same frame. The same is done for other errors,
+    /// which are returned as [`Err`].
+    ///
+    /// If the length is over [`LengthPrefix::max_frame_size`], or too
+    /// large to be held in memory at all, this returns an
+    /// [`ErrorKind::InvalidData`] error, with only the prefix
+    /// consumed, so the payload can still be skipped through
+    /// [`InterruptReader::skip`]. If the stream ends in the middle of
+    /// a frame, this returns an [`ErrorKind::UnexpectedEof`] error,
+    /// and what was read of it is discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::ErrorKind;
+    ///
+    /// use interrupt_read::{
+    ///     LengthPrefix, MessageEvent, pair,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let (mut reader, _interruptor) = pair(ScriptedReader::new(vec![
+    ///     // A frame split across a few chunks.
+    ///     Step::Data(vec![0, 0]),
+    ///     Step::Data(vec![0, 5, b'h']),
+    ///     Step::Data(b"ell".to_vec()),
+    ///     // The end of it, a zero-length frame, and an oversized one.
+    ///     Step::Data(vec![b'o', 0, 0, 0, 0, 0, 0, 1, 0]),
+    ///     Step::Data(vec![b'a'; 256]),
+    ///     Step::Data(vec![0, 0, 0, 3, b'e', b'n', b'd']),
+    /// ]));
+    ///
+    /// let cfg = LengthPrefix::new(4).max_frame_size(255);
+    /// assert_eq!(
+    ///     reader.read_message(cfg)?,
+    ///     MessageEvent::Frame(b"hello".to_vec())
+    /// );
+    /// assert_eq!(reader.read_message(cfg)?, MessageEvent::Frame(Vec::new()));
+    ///
+    /// let err = reader.read_message(cfg).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::InvalidData);
+    ///
+    /// // The payload of that frame wasn't consumed.
+    /// assert_eq!(reader.skip(256)?, 256);
+    /// assert_eq!(
+    ///     reader.read_message(cfg)?,
+    ///     MessageEvent::Frame(b"end".to_vec())
+    /// );
+    /// assert_eq!(reader.read_message(cfg)?, MessageEvent::Eof);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Interrupting in the middle of a frame:
+    ///
+    /// ```rust
+    /// use std::{io::Write, time::Duration};
+    ///
+    /// use interrupt_read::{LengthPrefix, MessageEvent, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    /// let cfg = LengthPrefix::new(2).little_endian();
+    ///
+    /// // Only the prefix was sent, then only part of the payload.
+    /// for part in [&[5, 0][..], b"wor"] {
+    ///     pipe_writer.write_all(part)?;
+    ///
+    ///     let join_handle = std::thread::spawn(move || {
+    ///         let event = reader.read_message(cfg);
+    ///         (event.unwrap(), reader)
+    ///     });
+    ///
+    ///     std::thread::sleep(Duration::from_millis(50));
+    ///     interruptor.interrupt()?;
+    ///
+    ///     let event;
+    ///     (event, reader) = join_handle.join().unwrap();
+    ///     assert_eq!(event, MessageEvent::Interrupted);
+    /// }
+    ///
+    /// // Nothing of the frame was lost.
+    /// pipe_writer.write_all(b"ld")?;
+    /// assert_eq!(
+    ///     reader.read_message(cfg)?,
+    ///     MessageEvent::Frame(b"world".to_vec())
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// A corrupt prefix, even without a limit:
+    ///
+    /// ```rust
+    /// use std::io::{Cursor, ErrorKind};
+    ///
+    /// use interrupt_read::{LengthPrefix, MessageEvent, pair};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let mut data = vec![0xff; 8];
+    /// data.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 2, b'o', b'k']);
+    /// let (mut reader, _interruptor) = pair(Cursor::new(data));
+    ///
+    /// let cfg = LengthPrefix::new(8);
+    /// let err = reader.read_message(cfg).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::InvalidData);
+    ///
+    /// // Only the prefix was consumed.
+    /// assert_eq!(
+    ///     reader.read_message(cfg)?,
+    ///     MessageEvent::Frame(b"ok".to_vec())
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_message(&mut self, cfg: LengthPrefix) -> std::io::Result<MessageEvent> {
+        let mut frame = Vec::new();
+
+        match self.read_frame_part(&mut frame, cfg.size) {
+            Ok(UntilOutcome::Found) => {}
+            Ok(UntilOutcome::Eof) if frame.is_empty() => return Ok(MessageEvent::Eof),
+            other => return self.frame_cut_short(frame, other),
+        }
+
+        let mut prefix = [0; 8];
+        let len = if cfg.little_endian {
+            prefix[..cfg.size].copy_from_slice(&frame);
+            u64::from_le_bytes(prefix)
+        } else {
+            prefix[8 - cfg.size..].copy_from_slice(&frame);
+            u64::from_be_bytes(prefix)
+        };
+
+        // The prefix may hold lengths that don't even fit in a usize.
+        let Some(total) = usize::try_from(len)
+            .ok()
+            .filter(|&len| len <= cfg.max_frame_size)
+            .and_then(|len| len.checked_add(cfg.size))
+        else {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "frame of {len} bytes is over the limit of {} bytes",
+                    cfg.max_frame_size
+                ),
+            ));
+        };
+
+        match self.read_frame_part(&mut frame, total) {
+            Ok(UntilOutcome::Found) => Ok(MessageEvent::Frame(frame.split_off(cfg.size))),
+            other => self.frame_cut_short(frame, other),
+        }
+    }
+
+    /// Reads into `frame` until it is `len` bytes long
+    ///
+    /// Returns [`UntilOutcome::Found`] once it is.
+    fn read_frame_part(
+        &mut self,
+        frame: &mut Vec<u8>,
+        len: usize,
+    ) -> std::io::Result<UntilOutcome> {
+        while frame.len() < len {
+            let buf = match self.fill_buf() {
+                Ok([]) => return Ok(UntilOutcome::Eof),
+                Ok(buf) => buf,
+                Err(err) if is_interrupt(&err) => return Ok(UntilOutcome::Interrupted),
+                Err(err) => return Err(err),
+            };
+
+            let amount = buf.len().min(len - frame.len());
+            frame.extend_from_slice(&buf[..amount]);
+            self.consume(amount);
+        }
+
+        Ok(UntilOutcome::Found)
+    }
+
+    /// Deals with a frame that couldn't be read in full
+    fn frame_cut_short(
+        &mut self,
+        frame: Vec<u8>,
+        outcome: std::io::Result<UntilOutcome>,
+    ) -> std::io::Result<MessageEvent> {
+        match outcome {
+            Ok(UntilOutcome::Eof) => Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "stream ended in the middle of a frame",
+            )),
+            Ok(_) => {
+                self.put_back(frame);
+                Ok(MessageEvent::Interrupted)
+            }
+            Err(err) => {
+                self.put_back(frame);
+                Err(err)
+            }
+        }
+    }
+
+    /// Puts bytes that were consumed back into the internal buffer
+    fn put_back(&mut self, bytes: Vec<u8>) {
+        // They weren't delivered after all.
+        self.delivered -= bytes.len() as u64;
+        self.chain_front(bytes);
+    }
+
+    /// Reads until `pattern` is found, only looking for it from
+    /// `start` onwards in `out`
+    fn scan_until_pattern(
+        &mut self,
+        pattern: &[u8],
+        out: &mut Vec<u8>,
+        start: usize,
+    ) -> std::io::Result<UntilOutcome> {
+        if pattern.is_empty() {
+            return Ok(UntilOutcome::Found);
+        }
+
+        loop {
+            let buf = match self.fill_buf() {
+                Ok([]) => break Ok(UntilOutcome::Eof),
+                Ok(buf) => buf,
+                Err(err) if is_interrupt(&err) => break Ok(UntilOutcome::Interrupted),
+                Err(err) => break Err(err),
+            };
+
+            // Keeps the end of the previous chunks around, in case the
+            // pattern was split between them and this one.
+            let scanned = out.len();
+            let window = scanned.saturating_sub(pattern.len() - 1).max(start);
+            out.extend_from_slice(buf);
+
+            if let Some(pos) = out[window..]
+                .windows(pattern.len())
+                .position(|w| w == pattern)
+            {
+                let end = window + pos + pattern.len();
+                out.truncate(end);
+                self.consume(end - scanned);
+                break Ok(UntilOutcome::Found);
+            }
+
+            let len = buf.len();
+            self.consume(len);
+        }
+    }
+
+    /// Reads a line, but no more than `max` bytes of it
+    ///
+    /// This is like [`BufRead::read_line`], but it stops once `max`
+    /// bytes (including the newline) have been appended to `out`, so
+    /// a source that never sends a newline can't make `out` grow
+    /// forever. The limit is enforced as the chunks arrive, and the
+    /// bytes past it are left in the internal buffer, so they can
+    /// still be read later.
+    ///
+    /// The returned [`LineOutcome`] tells you why this function
+    /// returned, along with how many bytes were appended to `out`.
+    /// Errors, including the [`InterruptReceived`] one, are returned
+    /// as [`Err`], and, like with `read_line`, the bytes read so far
+    /// are kept in `out`.
+    ///
+    /// If the limit or an interrupt splits a multi-byte character,
+    /// the start of that character is put back into the internal
+    /// buffer, to be read along with the rest of it. Other invalid
+    /// UTF-8 results in an [`ErrorKind::InvalidData`] error, and
+    /// nothing is appended to `out`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, Cursor};
+    ///
+    /// use interrupt_read::{LineOutcome, pair};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// // A 1 MiB line, followed by a short one.
+    /// let mut data = vec![b'a'; 1024 * 1024];
+    /// data.extend_from_slice(b"\nabc\n");
+    /// let (mut reader, _interruptor) = pair(Cursor::new(data));
+    ///
+    /// let mut line = String::new();
+    /// let outcome = reader.read_line_limited(&mut line, 4096)?;
+    /// assert_eq!(outcome, LineOutcome::LimitReached(4096));
+    /// assert_eq!(line.len(), 4096);
+    ///
+    /// // The rest of the line is still there.
+    /// line.clear();
+    /// reader.read_line(&mut line)?;
+    /// assert_eq!(line.len(), 1024 * 1024 - 4096 + 1);
+    ///
+    /// // A line exactly at the limit is complete.
+    /// line.clear();
+    /// let outcome = reader.read_line_limited(&mut line, 4)?;
+    /// assert_eq!(outcome, LineOutcome::Complete(4));
+    /// assert_eq!(line, "abc\n");
+    ///
+    /// line.clear();
+    /// let outcome = reader.read_line_limited(&mut line, 4)?;
+    /// assert_eq!(outcome, LineOutcome::Eof(0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Multi-byte characters aren't split, and interrupts keep what
+    /// was read so far:
+    ///
+    /// ```rust
+    /// use std::{io::Write, time::Duration};
+    ///
+    /// use interrupt_read::{LineOutcome, is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// pipe_writer.write_all("héllo\n".as_bytes())?;
+    /// let mut line = String::new();
+    /// let outcome = reader.read_line_limited(&mut line, 2)?;
+    /// assert_eq!(outcome, LineOutcome::LimitReached(1));
+    /// assert_eq!(line, "h");
+    ///
+    /// let outcome = reader.read_line_limited(&mut line, 100)?;
+    /// assert_eq!(outcome, LineOutcome::Complete(6));
+    /// assert_eq!(line, "héllo\n");
+    ///
+    /// pipe_writer.write_all(b"partial")?;
+    /// let join_handle = std::thread::spawn(move || {
+    ///     let mut line = String::new();
+    ///     let result = reader.read_line_limited(&mut line, 100);
+    ///     (result, line)
+    /// });
+    ///
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// interruptor.interrupt()?;
+    ///
+    /// let (result, line) = join_handle.join().unwrap();
+    /// assert!(is_interrupt(&result.unwrap_err()));
+    /// assert_eq!(line, "partial");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`InterruptReceived`]: crate::InterruptReceived
+    /// [`ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+    pub fn read_line_limited(
+        &mut self,
+        out: &mut String,
+        max: usize,
+    ) -> std::io::Result<LineOutcome> {
+        let mut bytes = Vec::new();
+        let result = self.read_until_limited(b'\n', &mut bytes, max);
+
+        let whole = matches!(result, Ok(LineOutcome::Complete(_) | LineOutcome::Eof(_)));
+        let put_back = self.push_utf8(bytes, out, whole)?;
+
+        result.map(|outcome| match outcome {
+            LineOutcome::LimitReached(len) => LineOutcome::LimitReached(len - put_back),
+            outcome => outcome,
+        })
+    }
+
+    /// Reads until `byte` is found, but no more than `max` bytes
+    ///
+    /// This is like [`BufRead::read_until`], but it stops once `max`
+    /// bytes (including the delimiter) have been appended to `out`.
+    /// Check out [`InterruptReader::read_line_limited`] for more
+    /// information.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{Cursor, Read};
+    ///
+    /// use interrupt_read::{LineOutcome, pair};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let (mut reader, _interruptor) = pair(Cursor::new(b"key=value;rest".to_vec()));
+    ///
+    /// let mut out = Vec::new();
+    /// let outcome = reader.read_until_limited(b';', &mut out, 4)?;
+    /// assert_eq!(outcome, LineOutcome::LimitReached(4));
+    /// assert_eq!(out, b"key=");
+    ///
+    /// out.clear();
+    /// let outcome = reader.read_until_limited(b';', &mut out, 100)?;
+    /// assert_eq!(outcome, LineOutcome::Complete(6));
+    /// assert_eq!(out, b"value;");
+    ///
+    /// out.clear();
+    /// reader.read_to_end(&mut out)?;
+    /// assert_eq!(out, b"rest");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_until_limited(
+        &mut self,
+        byte: u8,
+        out: &mut Vec<u8>,
+        max: usize,
+    ) -> std::io::Result<LineOutcome> {
+        let mut read = 0;
+
+        loop {
+            if read == max {
+                break Ok(LineOutcome::LimitReached(read));
+            }
+
+            let buf = match self.fill_buf()? {
+                [] => break Ok(LineOutcome::Eof(read)),
+                buf => &buf[..buf.len().min(max - read)],
+            };
+
+            if let Some(pos) = buf.iter().position(|b| *b == byte) {
+                out.extend_from_slice(&buf[..=pos]);
+                self.consume(pos + 1);
+                break Ok(LineOutcome::Complete(read + pos + 1));
+            }
+
+            let len = buf.len();
+            out.extend_from_slice(buf);
+            self.consume(len);
+            read += len;
+        }
+    }
+
+    /// Reads a line, replacing invalid UTF-8 instead of failing
+    ///
+    /// This is like [`BufRead::read_line`], but invalid UTF-8 is
+    /// replaced with [`U+FFFD REPLACEMENT CHARACTER`], just like
+    /// [`String::from_utf8_lossy`] does, instead of resulting in an
+    /// [`ErrorKind::InvalidData`] error that loses the whole line.
+    /// This returns how many bytes were read, not how many were
+    /// appended to `out`, so `Ok(0)` still means the end of the
+    /// stream.
+    ///
+    /// Interrupts and other errors are returned as usual, with what
+    /// was read of the line appended to `out`. If they split a
+    /// multi-byte character, its start is put back into the internal
+    /// buffer, so the next call reads it along with the rest of it,
+    /// instead of replacing it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use interrupt_read::{
+    ///     pair,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let (mut reader, _interruptor) = pair(ScriptedReader::new(vec![
+    ///     Step::Data(b"Latin-1: caf\xe9, garbage: \xff\xff!\n".to_vec()),
+    ///     // An "é" split between two chunks.
+    ///     Step::Data(b"caf\xc3".to_vec()),
+    ///     Step::Data(b"\xa9\n".to_vec()),
+    ///     Step::Data(b"cut short \xc3".to_vec()),
+    /// ]));
+    ///
+    /// let mut line = String::new();
+    /// assert_eq!(reader.read_line_lossy(&mut line)?, 28);
+    /// assert_eq!(line, "Latin-1: caf\u{fffd}, garbage: \u{fffd}\u{fffd}!\n");
+    ///
+    /// line.clear();
+    /// assert_eq!(reader.read_line_lossy(&mut line)?, 6);
+    /// assert_eq!(line, "café\n");
+    ///
+    /// // The end of the stream cuts the character short.
+    /// line.clear();
+    /// assert_eq!(reader.read_line_lossy(&mut line)?, 11);
+    /// assert_eq!(line, "cut short \u{fffd}");
+    /// assert_eq!(reader.read_line_lossy(&mut line)?, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Interrupts keep what was read so far, even if it splits a
+    /// character:
+    ///
+    /// ```rust
+    /// use std::{io::Write, time::Duration};
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// pipe_writer.write_all(b"\xffcaf\xc3")?;
+    /// let join_handle = std::thread::spawn(move || {
+    ///     let mut line = String::new();
+    ///     let result = reader.read_line_lossy(&mut line);
+    ///     (result, line, reader)
+    /// });
+    ///
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// interruptor.interrupt()?;
+    ///
+    /// let (result, mut line, mut reader) = join_handle.join().unwrap();
+    /// assert!(is_interrupt(&result.unwrap_err()));
+    /// assert_eq!(line, "\u{fffd}caf");
+    ///
+    /// pipe_writer.write_all(b"\xa9\n")?;
+    /// assert_eq!(reader.read_line_lossy(&mut line)?, 3);
+    /// assert_eq!(line, "\u{fffd}café\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`U+FFFD REPLACEMENT CHARACTER`]: char::REPLACEMENT_CHARACTER
+    pub fn read_line_lossy(&mut self, out: &mut String) -> std::io::Result<usize> {
+        let mut bytes = Vec::new();
+        let result = self.read_until(b'\n', &mut bytes);
+
+        if result.is_err() {
+            let split = bytes.split_off(bytes.len() - incomplete_utf8_tail(&bytes));
+            self.put_back(split);
+        }
+
+        out.push_str(&String::from_utf8_lossy(&bytes));
+        result
+    }
+
+    /// Like [`BufRead::fill_buf`], but doesn't block
+    ///
+    /// If there is some data in the internal buffer, or the worker
+    /// thread has already sent some, this returns `Ok(Some(buf))`,
+    /// and [`BufRead::consume`] works on `buf` just like it does
+    /// after calling [`BufRead::fill_buf`]. If nothing has arrived
+    /// yet, this returns `Ok(None)` instead of waiting, so you can
+    /// come back later.
+    ///
+    /// An empty slice means that the end of the stream was reached,
+    /// just like with `fill_buf`. And if an interrupt is pending,
+    /// the usual [`InterruptReceived`] error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{BufRead, Write},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::pair;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, _interruptor) = pair(pipe_reader);
+    ///
+    /// // The producer hasn't written anything yet.
+    /// assert_eq!(reader.try_fill_buf()?, None);
+    ///
+    /// pipe_writer.write_all(b"slow")?;
+    /// std::thread::sleep(Duration::from_millis(50));
+    ///
+    /// assert_eq!(reader.try_fill_buf()?, Some(&b"slow"[..]));
+    /// reader.consume(2);
+    /// assert_eq!(reader.try_fill_buf()?, Some(&b"ow"[..]));
+    /// reader.consume(2);
+    ///
+    /// // Everything was consumed, and nothing else arrived.
+    /// assert_eq!(reader.try_fill_buf()?, None);
+    ///
+    /// drop(pipe_writer);
+    /// std::thread::sleep(Duration::from_millis(50));
+    ///
+    /// // The end of the stream.
+    /// assert_eq!(reader.try_fill_buf()?, Some(&b""[..]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`InterruptReceived`]: crate::InterruptReceived
+    pub fn try_fill_buf(&mut self) -> std::io::Result<Option<&[u8]>> {
+        #[cfg(unix)]
+        if self.shared.ready.get().is_some() && self.fill_buf_with(Wait::Never, 0)?.is_none() {
+            // Checking again after the reset, so nothing is missed.
+            self.shared.reset_ready();
+        }
+
+        self.fill_buf_with(Wait::Never, 0)
+    }
+
+    /// Waits until a read wouldn't block, without reading anything
+    ///
+    /// This returns [`Readiness::Data`] if the next read operation
+    /// will return right away, be it with data, the end of the
+    /// stream, or an error, and [`Readiness::Interrupted`] if it will
+    /// return the [`InterruptReceived`] error. The interrupt is not
+    /// consumed by this function, so it is still returned by the next
+    /// read.
+    ///
+    /// If neither is the case, this waits for at most `timeout` (or
+    /// forever, if it is [`None`]), returning [`Readiness::TimedOut`]
+    /// if nothing arrived by then. A timeout of zero makes this a
+    /// non-blocking check. Whatever arrives in the meantime is kept
+    /// for the next read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     sync::Arc,
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{Builder, Readiness, is_interrupt, testing::MockClock};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let clock = Arc::new(MockClock::new());
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = Builder::new().clock(clock.clone()).pair(pipe_reader);
+    ///
+    /// assert_eq!(
+    ///     reader.poll_read_ready(Some(Duration::ZERO))?,
+    ///     Readiness::TimedOut
+    /// );
+    /// let timeout = Some(Duration::from_millis(50));
+    /// std::thread::spawn({
+    ///     let (clock, watch) = (clock.clone(), reader.state_watch());
+    ///     move || clock.advance_when_waiting(&watch, Duration::from_millis(50))
+    /// });
+    /// assert_eq!(reader.poll_read_ready(timeout)?, Readiness::TimedOut);
+    ///
+    /// std::thread::spawn(move || {
+    ///     std::thread::sleep(Duration::from_millis(50));
+    ///     pipe_writer.write_all(b"hi")
+    /// });
+    ///
+    /// // Wakes up as soon as the data shows up.
+    /// assert_eq!(reader.poll_read_ready(None)?, Readiness::Data);
+    /// let mut buf = [0; 2];
+    /// reader.read_exact(&mut buf)?;
+    /// assert_eq!(&buf, b"hi");
+    ///
+    /// // The interrupt is reported, but not consumed.
+    /// interruptor.interrupt().unwrap();
+    /// assert_eq!(reader.poll_read_ready(timeout)?, Readiness::Interrupted);
+    /// assert_eq!(reader.poll_read_ready(timeout)?, Readiness::Interrupted);
+    /// assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`InterruptReceived`]: crate::InterruptReceived
+    pub fn poll_read_ready(&mut self, timeout: Option<Duration>) -> std::io::Result<Readiness> {
+        if self.shared.persistent.load(Relaxed) > 0 {
+            return Ok(Readiness::Interrupted);
+        } else if self.poisoned {
+            // The error is returned right away.
+            return Ok(Readiness::Data);
+        }
+
+        // Takes in everything that has already arrived.
+        let disconnected = loop {
+            match try_recv_event(&self.event_rx, &self.shared) {
+                Ok(event) => self.pending.push_back(event),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        self.forget_observed_interrupts();
+
+        // With a buffer, interrupts are looked for before anything else.
+        let has_data = !self.buffer().is_empty();
+        if self.buffer.is_some()
+            && (!has_data || self.should_check_interrupts())
+            && self
+                .pending
+                .iter()
+                .any(|event| matches!(event, Event::Interrupt(_)))
+        {
+            return Ok(Readiness::Interrupted);
+        } else if has_data || self.closed {
+            return Ok(Readiness::Data);
+        } else if let Some(readiness) = self.pending_readiness() {
+            return Ok(readiness);
+        } else if disconnected {
+            return Ok(Readiness::Data);
+        }
+
+        let deadline = match timeout {
+            Some(Duration::ZERO) => return Ok(Readiness::TimedOut),
+            Some(timeout) => self.shared.clock.now().checked_add(timeout),
+            None => None,
+        };
+
+        loop {
+            match recv_event(&self.event_rx, &self.shared, deadline) {
+                Ok(event) => {
+                    self.pending.push_back(event);
+                    if let Some(readiness) = self.pending_readiness() {
+                        break Ok(readiness);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => break Ok(Readiness::TimedOut),
+                Err(RecvTimeoutError::Disconnected) => break Ok(Readiness::Data),
+            }
+        }
+    }
+
+    /// Wether a read operation should check for interrupts before
+    /// returning what is in the internal buffer
+    fn should_check_interrupts(&mut self) -> bool {
+        if self.skip_checks_without_interruptors && !self.interrupt_possible() {
+            // One last check, for what was sent by the ones created since the
+            // last one.
+            let created = self.shared.interruptors_created.load(Relaxed);
+            return self.checked_without_interruptors.replace(created) != Some(created);
+        }
+        self.checked_without_interruptors = None;
+
+        match self.interrupt_check {
+            InterruptCheck::EveryCall => true,
+            InterruptCheck::PerChunk => false,
+            InterruptCheck::EveryNBytes(bytes) => self.unchecked >= bytes,
+        }
+    }
+
+    /// The [`Readiness`] given by the first event in `pending` that
+    /// a read operation would return
+    fn pending_readiness(&self) -> Option<Readiness> {
+        self.pending.iter().find_map(|event| match event {
+            Event::Buf(..) if self.shared.is_discarding() => None,
+            Event::Buf(..) | Event::Err(_) | Event::Close => Some(Readiness::Data),
+            Event::Interrupt(_) => Some(Readiness::Interrupted),
+            Event::Reply(..) => None,
+            Event::Latest(_) => unreachable!("taken when received"),
+            Event::Relayed(_) => unreachable!("only sent to a ChildOutputReader"),
+        })
+    }
+
+    /// Like [`BufRead::fill_buf`], but returns [`None`] if it had to
+    /// stop waiting
+    ///
+    /// `read_size` is the size of the [`Read::read`] call that
+    /// requested the data, if any.
+    fn fill_buf_with(&mut self, wait: Wait, read_size: usize) -> std::io::Result<Option<&[u8]>> {
+        self.check_persistent()?;
+        self.check_poisoned()?;
+
+        loop {
+            if self.buffer.is_some() {
+                // Interrupts are always checked for before fetching a new chunk.
+                if self.should_check_interrupts() || self.buffer().is_empty() {
+                    self.unchecked = 0;
+                    self.deal_with_interrupt()?;
+                }
+
+                if !self.buffer().is_empty() {
+                    break;
+                }
+
+                if !self.return_buffer(read_size) {
+                    // Now we handle that.
+                    return Ok(Some(&[]));
+                }
+            } else if self.closed {
+                return Ok(Some(&[]));
+            } else {
+                match self.next_event(wait) {
+                    Ok(Event::Buf(buf, len, _)) if self.shared.is_discarding() => {
+                        self.shared.give_buffer(buf, len);
+                    }
+                    Ok(Event::Buf(buf, len, arrived)) => {
+                        self.buffer = Some(Buffer { buf, pos: 0, len, arrived });
+                        self.last_chunk_arrival = Some(arrived);
+                        self.update_buffered();
+                        if len == 0 {
+                            return Ok(Some(&[]));
+                        }
+                        self.consecutive_errors = 0;
+                    }
+                    Ok(Event::Err(err)) => return Err(self.source_error(err)),
+                    Ok(Event::Interrupt(interrupt)) => {
+                        return Err(self.interrupted(interrupt));
+                    }
+                    Ok(Event::Close) => self.close(),
+                    // A reply that came after InterruptReader::try_with_inner gave up.
+                    Ok(Event::Reply(..)) => {}
+                    Ok(Event::Latest(_)) => unreachable!("taken when received"),
+                    Ok(Event::Relayed(_)) => unreachable!("only sent to a ChildOutputReader"),
+                    Err(RecvTimeoutError::Timeout) => return Ok(None),
+                    Err(RecvTimeoutError::Disconnected) => return Ok(Some(&[])),
+                }
+            }
+        }
+
+        Ok(Some(self.buffer.as_ref().unwrap().remaining()))
+    }
+
+    /// Returns an [`Err`] if an interrupt has arrived
+    ///
+    /// Interrupts take precedence over the chunks that were already
+    /// sent by the worker thread, which are put in `pending`.
+    fn deal_with_interrupt(&mut self) -> std::io::Result<()> {
+        let is_interrupt = |event: &Event| matches!(event, Event::Interrupt(_));
+        // Left there by InterruptReader::poll_read_ready. This is done on
+        // every read, so the chunks in `pending` are only looked at once.
+        if self.pending.iter().any(is_interrupt) {
+            self.forget_observed_interrupts();
+            if let Some(pos) = self.pending.iter().position(is_interrupt)
+                && let Some(Event::Interrupt(interrupt)) = self.pending.remove(pos)
+            {
+                return Err(self.interrupted(interrupt));
+            }
+        }
+
+        loop {
+            match try_recv_event(&self.event_rx, &self.shared) {
+                Ok(Event::Interrupt(interrupt)) => break Err(self.interrupted(interrupt)),
+                Ok(event) => self.pending.push_back(event),
+                // The channel was dropped, but no need to handle that right now.
+                Err(_) => break Ok(()),
+            }
+        }
+    }
+
+    /// Removes the interrupts in `pending` that were observed through
+    /// an [`InterruptToken`]
+    fn forget_observed_interrupts(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let observed = self.shared.observed.load(Acquire);
+        self.pending.retain(|event| match event {
+            Event::Interrupt(interrupt) => interrupt.claim.is_some() || interrupt.seq > observed,
+            _ => true,
+        });
+    }
+
+    /// The next [`Event`], either from `pending` or from the worker
+    /// thread
+    fn next_event(&mut self, wait: Wait) -> Result<Event, RecvTimeoutError> {
+        self.forget_observed_interrupts();
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(event);
+        }
+
+        match wait {
+            Wait::Block => recv_event(&self.event_rx, &self.shared, None),
+            Wait::Until(deadline) => recv_event(&self.event_rx, &self.shared, deadline),
+            Wait::Never => try_recv_event(&self.event_rx, &self.shared).map_err(|err| match err {
+                TryRecvError::Empty => RecvTimeoutError::Timeout,
+                TryRecvError::Disconnected => RecvTimeoutError::Disconnected,
+            }),
+        }
+    }
+
+    /// Hands the internal buffer back to the worker thread,
+    /// returning `false` if it is gone, without leaving anything to
+    /// be read
+    fn return_buffer(&mut self, read_size: usize) -> bool {
+        let Some(buffer) = self.buffer.take() else {
+            return true;
+        };
+
+        // Lets the next chunk be as big as the read requesting it. The
+        // worker thread grows the buffers, so only the ones it reads into
+        // are grown.
+        if !self.shared.adaptive {
+            self.shared
+                .read_size
+                .fetch_max(read_size.min(MAX_READ_SIZE), Relaxed);
+        }
+
+        self.shared.give_buffer(buffer.buf, buffer.len);
+        if !self.worker.is_finished() {
+            return true;
+        }
+
+        // Like what it sent before a ReadDirective::Stop.
+        while let Ok(event) = try_recv_event(&self.event_rx, &self.shared) {
+            self.pending.push_back(event);
+        }
+        self.pending
+            .iter()
+            .any(|event| matches!(event, Event::Buf(..) | Event::Err(_) | Event::Close))
+    }
+
+    /// Replaces the exhausted internal buffer with a chunk that has
+    /// already arrived, returning `false` if there is none
+    ///
+    /// Other events are left in `pending`, to be handled on the next
+    /// read operation.
+    fn top_up(&mut self, read_size: usize) -> bool {
+        if !self.return_buffer(read_size) {
+            return false;
+        }
+
+        while !self.closed {
+            match self.next_event(Wait::Never) {
+                Ok(Event::Buf(buf, len, _)) if self.shared.is_discarding() => {
+                    self.shared.give_buffer(buf, len);
+                }
+                Ok(Event::Buf(buf, len, arrived)) if len > 0 => {
+                    self.buffer = Some(Buffer { buf, pos: 0, len, arrived });
+                    self.last_chunk_arrival = Some(arrived);
+                    self.update_buffered();
+                    self.consecutive_errors = 0;
+                    return true;
+                }
+                Ok(Event::Close) => self.close(),
+                Ok(event) => {
+                    self.pending.push_front(event);
+                    return false;
+                }
+                Err(_) => return false,
+            }
+        }
+
+        false
+    }
+
+    /// Runs `f` on the worker thread, returning its result and how
+    /// many bytes were read ahead of the consumer before it ran
+    ///
+    /// If `discard` is `true`, everything that was read before that
+    /// is thrown away, except for interrupts and such, which are left
+    /// in `pending`, otherwise it is all kept in `pending`.
+    fn run_on_worker<T: Send + 'static>(
+        &mut self,
+        f: impl FnOnce(&mut R) -> T + Send + 'static,
+        discard: bool,
+        deadline: Option<Instant>,
+    ) -> std::io::Result<(T, u64)>
+    where
+        R: 'static,
+    {
+        let stopped = || Error::other("the worker thread has stopped");
+        let mut ahead = self.buffer().len() as u64;
+
+        if discard {
+            if let Some(buffer) = self.buffer.take() {
+                self.shared.give_buffer(buffer.buf, buffer.len);
+                self.update_buffered();
+            }
+
+            for event in std::mem::take(&mut self.pending) {
+                match event {
+                    Event::Buf(buf, len, _) => {
+                        ahead += len as u64;
+                        self.shared.give_buffer(buf, len);
+                    }
+                    Event::Err(_) | Event::Reply(..) => {}
+                    event => self.pending.push_back(event),
+                }
+            }
+        } else {
+            for event in self.pending.iter() {
+                if let Event::Buf(_, len, _) = event {
+                    ahead += *len as u64;
+                }
+            }
+        }
+
+        self.commands += 1;
+        let command = Command {
+            id: self.commands,
+            f: Box::new(move |reader| {
+                let reader = reader.downcast_mut::<R>().unwrap();
+                Box::new(f(reader))
+            }),
+        };
+
+        if self.closed || !self.shared.send_command(command) {
+            return Err(stopped());
+        }
+
+        loop {
+            // In case the source has panicked.
+            let wake_up = self.shared.clock.now() + Duration::from_millis(50);
+            let wake_up = deadline.map_or(wake_up, |deadline| deadline.min(wake_up));
+
+            match recv_event(&self.event_rx, &self.shared, Some(wake_up)) {
+                Ok(Event::Reply(id, reply)) if id == self.commands => {
+                    break Ok((*reply.downcast::<T>().unwrap(), ahead));
+                }
+                // From a command that timed out.
+                Ok(Event::Reply(..)) => {}
+                Ok(Event::Buf(buf, len, _)) if discard => {
+                    ahead += len as u64;
+                    self.shared.give_buffer(buf, len);
+                }
+                Ok(Event::Err(_)) if discard => {}
+                // The source has panicked, without waiting for the clock.
+                Ok(Event::Close) if self.shared.state().worker_finished => {
+                    self.pending.push_back(Event::Close);
+                    break Err(stopped());
+                }
+                Ok(event) => {
+                    if let Event::Buf(_, len, _) = event {
+                        ahead += len as u64;
+                    }
+                    self.pending.push_back(event);
+                }
+                Err(RecvTimeoutError::Timeout)
+                    if deadline.is_some_and(|deadline| self.shared.clock.now() >= deadline) =>
+                {
+                    self.shared.take_command();
+                    break Err(Error::new(
+                        std::io::ErrorKind::TimedOut,
+                        "the worker thread didn't get to the closure in time",
+                    ));
+                }
+                Err(RecvTimeoutError::Timeout) if !self.worker.is_finished() => {}
+                Err(_) => break Err(stopped()),
+            }
+        }
+    }
+
+    /// Stops reading, after an [`Interruptor::close`]
+    fn close(&mut self) {
+        self.closed = true;
+        // The worker thread will stop after its current read.
+        self.shared.stop_worker();
+    }
+
+    /// Marks an [`Interrupt`] as observed, returning the error for it
+    fn interrupted(&mut self, interrupt: Interrupt) -> Error {
+        // Targeted ones aren't part of the sequence.
+        let discard = interrupt.claim.is_none() && self.shared.observe(interrupt.seq);
+        debug!(seq = interrupt.seq, discard, "interrupt observed");
+        self.shared.record(|recorder| recorder.interrupt());
+
+        if discard {
+            if let Some(buffer) = self.buffer.take() {
+                self.shared.give_buffer(buffer.buf, buffer.len);
+                self.update_buffered();
+            }
+
+            // Everything in here arrived before the interrupt.
+            for event in std::mem::take(&mut self.pending) {
+                match event {
+                    Event::Buf(buf, len, _) => self.shared.give_buffer(buf, len),
+                    event => self.pending.push_back(event),
+                }
+            }
+        }
+
+        Error::new(interrupt.kind, InterruptReceived {
+            reason: interrupt.reason,
+            label: interrupt.label,
+            bytes_delivered: self.delivered,
+        })
+    }
+
+    /// Publishes the amount of bytes left in the internal buffer
+    fn update_buffered(&self) {
+        let buffered = self.buffer();
+        self.shared.buffered.store(buffered.len(), Relaxed);
+    }
+}
+
+impl<R: Read> Read for InterruptReader<R> {
+    /// Reads some bytes, blocking until at least one is available
+    ///
+    /// Once some bytes have been copied, this never blocks again,
+    /// but it keeps copying from the chunks that the worker thread
+    /// has already sent, until `buf` is full, or no more data is
+    /// immediately available.
+    ///
+    /// If an interrupt, error, or the end of the stream is found
+    /// while doing so, it is deferred: the bytes that were already
+    /// copied are returned, and the next read operation is the one
+    /// to handle it. Note that an interrupt that had already arrived
+    /// before this call takes precedence over any data that wasn't
+    /// in the internal buffer, so it is returned right away.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::pair;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, _interruptor) = pair(pipe_reader);
+    ///
+    /// // Each of these is read in a separate chunk.
+    /// for chunk in [b"one ", b"two ", b"six "] {
+    ///     pipe_writer.write_all(chunk)?;
+    ///     std::thread::sleep(Duration::from_millis(20));
+    /// }
+    /// drop(pipe_writer);
+    /// std::thread::sleep(Duration::from_millis(20));
+    ///
+    /// let mut buf = [0; 64];
+    /// assert_eq!(reader.read(&mut buf)?, 12);
+    /// assert_eq!(&buf[..12], b"one two six ");
+    ///
+    /// // The end of the stream was deferred to the next read.
+    /// assert_eq!(reader.read(&mut buf)?, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// An empty `buf` returns `Ok(0)` right away, without waiting for
+    /// data or checking for interrupts:
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let (pipe_reader, _pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = interrupt_read::pair(pipe_reader);
+    ///
+    /// interruptor.interrupt().unwrap();
+    /// assert_eq!(reader.read(&mut [])?, 0);
+    /// assert!(reader.read(&mut [0; 8]).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.read_vectored(&mut [IoSliceMut::new(buf)])
+    }
+
+    /// Like [`InterruptReader::read`], but for multiple buffers
+    ///
+    /// [`InterruptReader::read`]: #method.read
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> std::io::Result<usize> {
+        let read_size = bufs.iter().map(|buf| buf.len()).sum();
+        // Like other readers, there is nothing to wait for.
+        if read_size == 0 {
+            return Ok(0);
+        }
+
+        if self
+            .fill_buf_with(Wait::Block, read_size)?
+            .unwrap()
+            .is_empty()
+        {
+            return Ok(0);
+        }
+
+        let mut copied = 0;
+        for buf in bufs.iter_mut() {
+            let mut filled = 0;
+            while filled < buf.len() {
+                if self.buffer().is_empty() && !self.top_up(read_size - copied) {
+                    return Ok(copied);
+                }
+
+                let available = self.buffer();
+                let len = available.len().min(buf.len() - filled);
+                buf[filled..filled + len].copy_from_slice(&available[..len]);
+                self.consume(len);
+                filled += len;
+                copied += len;
+            }
+        }
+
+        Ok(copied)
+    }
+
+    /// Like [`InterruptReader::read`], but for uninitialized buffers
+    ///
+    /// The bytes are copied straight from the internal buffer into
+    /// the `cursor`, so the destination doesn't have to be
+    /// initialized first. This requires the `read_buf` feature, and
+    /// a nightly compiler.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(core_io_borrowed_buf, read_buf)]
+    /// use std::{
+    ///     io::{BorrowedBuf, Read, Write},
+    ///     mem::MaybeUninit,
+    /// };
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// let mut memory = [MaybeUninit::uninit(); 64];
+    /// let mut buf = BorrowedBuf::from(memory.as_mut_slice());
+    ///
+    /// pipe_writer.write_all(b"hello")?;
+    /// reader.read_buf(buf.unfilled())?;
+    /// assert_eq!(buf.filled(), b"hello");
+    /// assert_eq!((buf.len(), buf.capacity()), (5, 64));
+    ///
+    /// interruptor.interrupt()?;
+    /// assert!(is_interrupt(&reader.read_buf(buf.unfilled()).unwrap_err()));
+    ///
+    /// // Fills the rest of a buffer over multiple chunks.
+    /// let mut memory = [MaybeUninit::uninit(); 6];
+    /// let mut buf = BorrowedBuf::from(memory.as_mut_slice());
+    /// pipe_writer.write_all(b" wo")?;
+    /// std::thread::spawn(move || pipe_writer.write_all(b"rld"));
+    /// reader.read_buf_exact(buf.unfilled())?;
+    /// assert_eq!(buf.filled(), b" world");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`InterruptReader::read`]: #method.read
+    #[cfg(feature = "read_buf")]
+    fn read_buf(&mut self, mut cursor: std::io::BorrowedCursor<'_>) -> std::io::Result<()> {
+        let read_size = cursor.capacity();
+        if read_size == 0 {
+            return Ok(());
+        }
+
+        if self
+            .fill_buf_with(Wait::Block, read_size)?
+            .unwrap()
+            .is_empty()
+        {
+            return Ok(());
+        }
+
+        while cursor.capacity() > 0 {
+            if self.buffer().is_empty() && !self.top_up(cursor.capacity()) {
+                break;
+            }
+
+            let available = self.buffer();
+            let len = available.len().min(cursor.capacity());
+            cursor.append(&available[..len]);
+            self.consume(len);
+        }
+
+        Ok(())
+    }
+
+    /// Reads everything until the end of the stream, appending it to
+    /// `buf`
+    ///
+    /// Unlike the default implementation, which goes through
+    /// [`InterruptReader::read`] with ever bigger buffers, every
+    /// chunk that the worker thread sends is appended as a whole,
+    /// and its buffer is handed back right away, so the bytes are
+    /// only copied once. The chunks get bigger as more is read,
+    /// up to 1 MiB.
+    ///
+    /// Otherwise, this works just like the default: it stops at the
+    /// first `Ok(0)`, errors of kind [`ErrorKind::Interrupted`] are
+    /// retried, and any other error, including interrupts, is
+    /// returned right away, leaving what was read until then in
+    /// `buf`. With [`Eof::Final`], the `Ok(0)`s from the source are
+    /// skipped instead, check out [`Builder::eof`] for more
+    /// information.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let data: Vec<u8> = (0..8 * 1024 * 1024u32)
+    ///     .map(|i| (i.wrapping_mul(2654435761) >> 24) as u8)
+    ///     .collect();
+    ///
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// let writer = std::thread::spawn({
+    ///     let data = data.clone();
+    ///     move || {
+    ///         // Writes of all kinds of sizes, to get all kinds of chunks.
+    ///         let mut rest = data.as_slice();
+    ///         for size in (1..).map(|i| (i * 7919) % 100_000 + 1) {
+    ///             let (chunk, tail) = rest.split_at(size.min(rest.len()));
+    ///             pipe_writer.write_all(chunk).unwrap();
+    ///             rest = tail;
+    ///             if rest.is_empty() {
+    ///                 break;
+    ///             }
+    ///         }
+    ///     }
+    /// });
+    ///
+    /// let mut out = b"head".to_vec();
+    /// assert_eq!(reader.read_to_end(&mut out)?, data.len());
+    /// assert_eq!(&out[..4], b"head");
+    /// assert!(out[4..] == data);
+    /// writer.join().unwrap();
+    ///
+    /// // An interrupt is returned as usual.
+    /// interruptor.interrupt()?;
+    /// assert!(is_interrupt(&reader.read_to_end(&mut out).unwrap_err()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`InterruptReader::read`]: #method.read
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> std::io::Result<usize> {
+        let start = buf.len();
+        loop {
+            let read_size = (buf.len() - start).min(MAX_READ_SIZE);
+            let chunk = match self.fill_buf_with(Wait::Block, read_size) {
+                Ok(chunk) => chunk.unwrap(),
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+
+            if chunk.is_empty() {
+                // Only an Ok(0) from the source leaves a chunk behind.
+                if self.eof == Eof::Empty || self.buffer.is_none() {
+                    return Ok(buf.len() - start);
+                }
+                continue;
+            }
+
+            let len = chunk.len();
+            buf.extend_from_slice(chunk);
+            self.consume(len);
+        }
+    }
+
+    /// Reads everything until the end of the stream, appending it to
+    /// `buf`
+    ///
+    /// This goes through [`InterruptReader::read_to_end`], checking
+    /// that the bytes are valid UTF-8 at the end. Just like the
+    /// default, if they aren't, nothing is appended, and an error of
+    /// kind [`ErrorKind::InvalidData`] is returned, unless reading
+    /// failed first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{Cursor, ErrorKind, Read};
+    ///
+    /// use interrupt_read::pair;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let (mut reader, _interruptor) = pair(Cursor::new("ñandú".as_bytes().to_vec()));
+    /// let mut out = String::from("> ");
+    /// assert_eq!(reader.read_to_string(&mut out)?, 7);
+    /// assert_eq!(out, "> ñandú");
+    ///
+    /// let (mut reader, _interruptor) = pair(Cursor::new(b"ok \xff".to_vec()));
+    /// let err = reader.read_to_string(&mut out).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::InvalidData);
+    /// assert_eq!(out, "> ñandú");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`InterruptReader::read_to_end`]: #method.read_to_end
+    fn read_to_string(&mut self, buf: &mut String) -> std::io::Result<usize> {
+        let start = buf.len();
+        let mut bytes = std::mem::take(buf).into_bytes();
+        let result = self.read_to_end(&mut bytes);
+
+        match String::from_utf8(bytes) {
+            Ok(string) => {
+                *buf = string;
+                result
+            }
+            Err(err) => {
+                let mut bytes = err.into_bytes();
+                bytes.truncate(start);
+                *buf = String::from_utf8(bytes).expect("it was a String before");
+                result.and_then(|_| {
+                    Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "stream did not contain valid UTF-8",
+                    ))
+                })
+            }
+        }
+    }
+}
+
+impl<R: Read> BufRead for InterruptReader<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.fill_buf_with(Wait::Block, 0).map(Option::unwrap)
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if let Some(buffer) = self.buffer.as_mut() {
+            let pos = (buffer.pos + amount).min(buffer.len);
+            self.unchecked += pos - buffer.pos;
+            self.delivered += (pos - buffer.pos) as u64;
+            buffer.pos = pos;
+            self.update_buffered();
+        }
+    }
+}
+
+impl<R: Read + Write + 'static> Write for InterruptReader<R> {
+    /// Writes to the underlying reader, in the worker thread
+    ///
+    /// This is for sources that are written to as well, like the
+    /// master side of a PTY, when they can't be split with
+    /// [`duplex::pair_cloned`]. Writes are sent to the worker thread,
+    /// which does them in between reads, just like with
+    /// [`InterruptReader::with_inner`], and data that was read before
+    /// is kept.
+    ///
+    /// This means that, if the worker thread is blocked on a `read`,
+    /// the write has to wait for it to return. With
+    /// [`interrupt_read::pair_fd`], or with the `windows-cancel`
+    /// feature on Windows, the worker thread is woken up instead, so
+    /// writes go through right away.
+    ///
+    /// Each call to [`Write::write_all`] is sent as a whole, so it is
+    /// never interleaved with other operations on the source.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(unix)]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::{
+    ///     io::{BufRead, BufReader, Write},
+    ///     os::unix::net::UnixStream,
+    /// };
+    ///
+    /// let (stream, mut peer) = UnixStream::pair()?;
+    ///
+    /// // Echoes every line back.
+    /// std::thread::spawn(move || {
+    ///     for line in BufReader::new(peer.try_clone()?).lines() {
+    ///         writeln!(peer, "{}", line?)?;
+    ///     }
+    ///     std::io::Result::Ok(())
+    /// });
+    ///
+    /// let (mut reader, _interruptor) = interrupt_read::pair_fd(stream)?;
+    ///
+    /// for prompt in ["first", "second", "third"] {
+    ///     writeln!(reader, "{prompt}")?;
+    ///     let mut line = String::new();
+    ///     reader.read_line(&mut line)?;
+    ///     assert_eq!(line.trim_end(), prompt);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(unix))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// [`duplex::pair_cloned`]: duplex::pair_cloned
+    /// [`interrupt_read::pair_fd`]: pair_fd
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let buf = buf.to_vec();
+        self.run_on_worker(move |reader: &mut R| reader.write(&buf), false, None)?
+            .0
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        let buf = buf.to_vec();
+        self.run_on_worker(move |reader: &mut R| reader.write_all(&buf), false, None)?
+            .0
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.run_on_worker(|reader: &mut R| reader.flush(), false, None)?
+            .0
+    }
+}
+
+impl<R: Read + Seek + 'static> Seek for InterruptReader<R> {
+    /// Seeks to an offset, in bytes, in the underlying reader
+    ///
+    /// The seek is done by the worker thread, in between reads, and
+    /// everything that it had read ahead is thrown away. Like with
+    /// [`BufReader`], [`SeekFrom::Current`] is relative to the
+    /// position of the consumer, not to how far the worker thread
+    /// has read. If the worker thread is blocked on a read, this
+    /// waits for that read to finish.
+    ///
+    /// Seeking by a small amount with [`Seek::seek_relative`], within
+    /// the internal buffer, doesn't involve the worker thread.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     fs::File,
+    ///     io::{Read, Seek, SeekFrom},
+    /// };
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let data: Vec<u8> = (0..=u8::MAX).cycle().take(64 * 1024).collect();
+    /// let path = std::env::temp_dir().join("interrupt_read_seek");
+    /// std::fs::write(&path, &data)?;
+    ///
+    /// let (mut reader, _interruptor) = interrupt_read::pair(File::open(&path)?);
+    /// let mut buf = [0; 100];
+    /// reader.read_exact(&mut buf)?;
+    ///
+    /// // Forwards.
+    /// assert_eq!(reader.seek(SeekFrom::Start(30_000))?, 30_000);
+    /// reader.read_exact(&mut buf)?;
+    /// assert_eq!(buf, data[30_000..30_100]);
+    ///
+    /// // Relative to what was consumed, not to what was read ahead.
+    /// assert_eq!(reader.seek(SeekFrom::Current(-200))?, 29_900);
+    /// reader.read_exact(&mut buf)?;
+    /// assert_eq!(buf, data[29_900..30_000]);
+    ///
+    /// // Backwards, from the end.
+    /// assert_eq!(reader.seek(SeekFrom::End(-100))?, data.len() as u64 - 100);
+    /// let mut end = Vec::new();
+    /// reader.read_to_end(&mut end)?;
+    /// assert_eq!(end, data[data.len() - 100..]);
+    ///
+    /// // Small seeks are served from the internal buffer.
+    /// reader.seek(SeekFrom::Start(0))?;
+    /// reader.read_exact(&mut buf)?;
+    /// reader.seek_relative(-50)?;
+    /// reader.read_exact(&mut buf[..50])?;
+    /// assert_eq!(buf[..50], data[50..100]);
+    /// assert_eq!(reader.stream_position()?, 100);
+    /// # std::fs::remove_file(&path)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`BufReader`]: std::io::BufReader
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let pos = match pos {
+            // Where the worker thread is has to be found out first.
+            SeekFrom::Current(offset) => {
+                let (result, ahead) =
+                    self.run_on_worker(|reader: &mut R| reader.stream_position(), true, None)?;
+                let pos = result? as i64 - ahead as i64 + offset;
+                SeekFrom::Start(u64::try_from(pos).map_err(|_| {
+                    Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "invalid seek to a negative position",
+                    )
+                })?)
+            }
+            pos => pos,
+        };
+
+        self.run_on_worker(move |reader: &mut R| reader.seek(pos), true, None)?
+            .0
+    }
+
+    fn seek_relative(&mut self, offset: i64) -> std::io::Result<()> {
+        if let Some(buffer) = self.buffer.as_mut()
+            && let Some(pos) = buffer.pos.checked_add_signed(offset as isize)
+            && pos <= buffer.len
+        {
+            buffer.pos = pos;
+            self.update_buffered();
+            return Ok(());
+        }
+
+        self.seek(SeekFrom::Current(offset)).map(|_| ())
+    }
+}
+
+/// An [`InterruptReader`] that keeps a duplicate of the handle of
+/// its source
+///
+/// This is returned by [`pair_fd`], [`tcp::pair`] and
+/// [`child::pair`], which duplicate the file descriptor of the
+/// source before it is moved to the worker thread, so it stays valid
+/// for as long as the `SourceReader` exists. It is reached through
+/// [`AsFd`] and [`AsRawFd`], or through `AsHandle` and `AsSocket` on
+/// Windows. Other than that, the `SourceReader` dereferences to the
+/// `InterruptReader`.
+///
+/// This is meant for things like registering the source somewhere,
+/// or calling `getsockopt` on a socket. _Reading from it directly
+/// races with the worker thread, and corrupts the stream._
+///
+/// # Examples
+///
+/// ```rust
+/// use std::{
+///     net::{TcpListener, TcpStream},
+///     os::fd::AsFd,
+/// };
+///
+/// use interrupt_read::pair_fd;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let listener = TcpListener::bind("127.0.0.1:0")?;
+/// let stream = TcpStream::connect(listener.local_addr()?)?;
+/// let local_addr = stream.local_addr()?;
+///
+/// let (reader, _interruptor) = pair_fd(stream)?;
+///
+/// // This calls getsockname on it.
+/// let fd = reader.as_fd().try_clone_to_owned()?;
+/// assert_eq!(TcpStream::from(fd).local_addr()?, local_addr);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`AsFd`]: https://doc.rust-lang.org/std/os/fd/trait.AsFd.html
+/// [`AsRawFd`]: https://doc.rust-lang.org/std/os/fd/trait.AsRawFd.html
+/// [`tcp::pair`]: crate::tcp::pair
+/// [`child::pair`]: crate::child::pair
+#[derive(Debug)]
+pub struct SourceReader<R: Read, H> {
+    reader: InterruptReader<R>,
+    handle: H,
+    /// How `handle` was taken from the source.
+    duplicate: fn(&R) -> std::io::Result<H>,
+}
+
+impl<R: Read, H> SourceReader<R, H> {
+    /// Wraps an [`InterruptReader`], with the `handle` that was
+    /// taken from its source through `duplicate`
+    pub(crate) fn new(
+        reader: InterruptReader<R>,
+        handle: H,
+        duplicate: fn(&R) -> std::io::Result<H>,
+    ) -> Self {
+        Self { reader, handle, duplicate }
+    }
+
+    /// Unwraps this `SourceReader`, returning the underlying reader
+    ///
+    /// This works just like [`InterruptReader::into_inner`].
+    pub fn into_inner(self) -> std::thread::Result<R> {
+        self.reader.into_inner()
+    }
+
+    /// Returns the [`InterruptReader`], closing the duplicate of the
+    /// handle of its source
+    pub fn into_reader(self) -> InterruptReader<R> {
+        self.reader
+    }
+
+    /// Replaces the underlying reader, keeping everything else
+    ///
+    /// This works just like [`InterruptReader::replace_inner`], but
+    /// the handle of `new_reader` is duplicated first, and replaces
+    /// the one of the old reader. It returns an [`Err`] if that
+    /// fails, in which case the reader isn't replaced.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     fs::File,
+    ///     os::{fd::AsFd, unix::fs::MetadataExt},
+    /// };
+    ///
+    /// use interrupt_read::pair_fd;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let inode = |fd: &dyn AsFd| -> std::io::Result<u64> {
+    ///     Ok(File::from(fd.as_fd().try_clone_to_owned()?)
+    ///         .metadata()?
+    ///         .ino())
+    /// };
+    ///
+    /// let (first, _first_writer) = std::io::pipe()?;
+    /// let (second, _second_writer) = std::io::pipe()?;
+    /// let second_inode = inode(&second)?;
+    ///
+    /// let (mut reader, _interruptor) = pair_fd(first)?;
+    /// assert_ne!(inode(&reader)?, second_inode);
+    ///
+    /// // The duplicate is now of the second pipe.
+    /// reader.replace_inner(second)?;
+    /// assert_eq!(inode(&reader)?, second_inode);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn replace_inner(&mut self, new_reader: R) -> std::io::Result<ReplaceOutcome<R>>
+    where
+        R: Send + 'static,
+    {
+        let handle = (self.duplicate)(&new_reader)?;
+        let outcome = self.reader.replace_inner(new_reader)?;
+        self.handle = handle;
+
+        Ok(outcome)
+    }
+}
+
+impl<R: Read, H> std::ops::Deref for SourceReader<R, H> {
+    type Target = InterruptReader<R>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.reader
+    }
+}
+
+impl<R: Read, H> std::ops::DerefMut for SourceReader<R, H> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.reader
+    }
+}
+
+impl<R: Read, H> Read for SourceReader<R, H> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reader.read(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> std::io::Result<usize> {
+        self.reader.read_vectored(bufs)
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> std::io::Result<usize> {
+        self.reader.read_to_end(buf)
+    }
+
+    fn read_to_string(&mut self, buf: &mut String) -> std::io::Result<usize> {
+        self.reader.read_to_string(buf)
+    }
+}
+
+impl<R: Read, H> BufRead for SourceReader<R, H> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.reader.consume(amount);
+    }
+}
+
+impl<R: Read + Write + 'static, H> Write for SourceReader<R, H> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.reader.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.reader.flush()
+    }
+}
+
+#[cfg(unix)]
+impl<R: Read> AsFd for SourceReader<R, OwnedFd> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.handle.as_fd()
+    }
+}
+
+#[cfg(unix)]
+impl<R: Read> AsRawFd for SourceReader<R, OwnedFd> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.handle.as_raw_fd()
+    }
+}
+
+#[cfg(windows)]
+impl<R: Read> std::os::windows::io::AsHandle
+    for SourceReader<R, std::os::windows::io::OwnedHandle>
+{
+    fn as_handle(&self) -> std::os::windows::io::BorrowedHandle<'_> {
+        self.handle.as_handle()
+    }
+}
+
+#[cfg(windows)]
+impl<R: Read> std::os::windows::io::AsSocket
+    for SourceReader<R, std::os::windows::io::OwnedSocket>
+{
+    fn as_socket(&self) -> std::os::windows::io::BorrowedSocket<'_> {
+        self.handle.as_socket()
+    }
+}
+
+/// An interruptor for an [`InterruptReader`].
+///
+/// This struct serves the purpose of interrupting any of the [`Read`]
+/// or [`BufRead`] functions being performend on the `InterruptReader`
+///
+/// Once every `Interruptor` of an `InterruptReader` is dropped, it
+/// can no longer be interrupted, which
+/// [`InterruptReader::interrupt_possible`] tells. A
+/// [`WeakInterruptor`], acquired through [`Interruptor::downgrade`],
+/// doesn't count, but can be turned back into an `Interruptor`.
+///
+/// Code that doesn't read can still check for interrupts, through an
+/// [`InterruptToken`], acquired through
+/// [`Interruptor::checkpoint_token`].
+///
+/// # Ordering
+///
+/// Interrupts, chunks read by the worker thread, and bytes sent
+/// through [`Interruptor::inject`] all go through the same channel,
+/// in the order that they were sent, and every interrupt carries a
+/// sequence number, which is what the `InterruptReader` goes by when
+/// observing them. This makes for the following guarantees, which you
+/// can rely on when using interrupts as boundaries between sessions:
+///
+/// - Data that was sent after an interrupt is never returned before
+///   the error of that interrupt, including data the source produced
+///   after [`Interruptor::interrupt`] returned.
+/// - Data that was sent before an interrupt may be returned after its
+///   error, since interrupts take precedence over data that isn't in
+///   the internal buffer yet, but it is never lost or reordered,
+///   unless [`Interruptor::interrupt_and_discard`] is used.
+/// - The errors of interrupts are returned in the order that the
+///   interrupts were sent, and each happens once, unless interrupts
+///   are merged by [`Builder::coalesce_interrupts`].
+///
+/// With [`Overflow::DropOldest`], chunks can be dropped, but the ones
+/// that are kept keep their place relative to the interrupts.
+///
+/// ```rust
+/// use std::{
+///     io::{Read, Write},
+///     time::Duration,
+/// };
+///
+/// use interrupt_read::{is_interrupt, pair};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+/// let (mut reader, interruptor) = pair(pipe_reader);
+///
+/// pipe_writer.write_all(b"before ")?;
+/// std::thread::sleep(Duration::from_millis(50));
+/// interruptor.interrupt()?;
+/// pipe_writer.write_all(b"after")?;
+/// drop(pipe_writer);
+/// std::thread::sleep(Duration::from_millis(50));
+///
+/// // Everything already arrived, but the interrupt comes first.
+/// assert!(is_interrupt(&reader.read(&mut [0; 64]).unwrap_err()));
+/// let mut data = String::new();
+/// reader.read_to_string(&mut data)?;
+/// assert_eq!(data, "before after");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Interruptor {
+    event_tx: mpsc::Sender<Event>,
+    shared: Arc<Shared>,
+    label: Option<Cow<'static, str>>,
+}
+
+impl Interruptor {
+    /// Returns a new, unlabeled `Interruptor`
+    pub(crate) fn new(event_tx: mpsc::Sender<Event>, shared: Arc<Shared>) -> Self {
+        shared.interruptors.fetch_add(1, Relaxed);
+        shared.interruptors_created.fetch_add(1, Relaxed);
+        Self { event_tx, shared, label: None }
+    }
+
+    /// Interrupts the [`InterruptReader`]
+    ///
+    /// This will send an interrupt event to the reader, which makes
+    /// the next `read` operation return [`Err`], with an
+    /// [`ErrorKind::Other`] with a payload of [`InterruptReceived`].
+    ///
+    /// You can check if an [`std::io::Error`] is of this type by
+    /// calling the [`is_interrupt`] function.
+    ///
+    /// Subsequent `read` operations proceed as normal.
+    ///
+    /// [`ErrorKind::Other`]: std::io::ErrorKind::Other
+    pub fn interrupt(&self) -> Result<(), InterruptSendError> {
+        self.send_interrupt(false, None, ErrorKind::Other)
+            .map(|_| ())
+    }
+
+    /// Interrupts the [`InterruptReader`], with a reason for doing so
+    ///
+    /// This is the same as [`Interruptor::interrupt`], but the
+    /// [`InterruptReceived`] error also carries the `reason`, which
+    /// is accessible through [`InterruptReceived::reason`] and is
+    /// shown when formatting the error. This is useful for figuring
+    /// out why a read was interrupted, once the error ends up in the
+    /// logs.
+    ///
+    /// If the interrupt is coalesced into one that is still pending
+    /// (see [`Builder::coalesce_interrupts`]), the
+    /// reason of the pending one is kept.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    ///
+    /// use interrupt_read::{IoErrorExt, is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, _pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// interruptor.interrupt_with_reason("the user pressed Ctrl+C")?;
+    /// let err = reader.read(&mut [0; 5]).unwrap_err();
+    /// assert!(is_interrupt(&err));
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Interruptor has interrupted: the user pressed Ctrl+C"
+    /// );
+    ///
+    /// let interrupt = err.into_interrupt().unwrap();
+    /// assert_eq!(interrupt.reason(), Some("the user pressed Ctrl+C"));
+    ///
+    /// // Without a reason, the message is the usual one.
+    /// interruptor.interrupt()?;
+    /// let err = reader.read(&mut [0; 5]).unwrap_err();
+    /// assert_eq!(err.to_string(), "Interruptor has interrupted");
+    /// assert_eq!(err.into_interrupt().unwrap().reason(), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn interrupt_with_reason(
+        &self,
+        reason: impl Into<String>,
+    ) -> Result<(), InterruptSendError> {
+        let reason = Arc::from(reason.into());
+        self.send_interrupt(false, Some(reason), ErrorKind::Other)
+            .map(|_| ())
+    }
+
+    /// Interrupts the [`InterruptReader`], with a specific
+    /// [`ErrorKind`]
+    ///
+    /// This is the same as [`Interruptor::interrupt`], but the
+    /// returned error is of the given `kind`, rather than
+    /// [`ErrorKind::Other`]. It still carries an
+    /// [`InterruptReceived`], so [`is_interrupt`] works as usual.
+    /// This lets the same reader be interrupted for different
+    /// reasons, like a cancellation by the user and a timeout, which
+    /// are then handled by code that only looks at the
+    /// [`ErrorKind`].
+    ///
+    /// If the interrupt is coalesced into one that is still pending
+    /// (see [`Builder::coalesce_interrupts`]), the
+    /// kind of the pending one is kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `kind` is [`ErrorKind::Interrupted`], since
+    /// functions like [`Read::read_exact`] and [`Read::read_to_end`]
+    /// retry on errors of that kind, so the interrupt would just be
+    /// ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{ErrorKind, Read};
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, _pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// interruptor.interrupt_with_kind(ErrorKind::TimedOut)?;
+    /// interruptor.interrupt_with_kind(ErrorKind::ConnectionAborted)?;
+    ///
+    /// let err = reader.read(&mut [0; 5]).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::TimedOut);
+    /// assert!(is_interrupt(&err));
+    ///
+    /// let err = reader.read(&mut [0; 5]).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::ConnectionAborted);
+    /// assert!(is_interrupt(&err));
+    ///
+    /// // They work just like regular interrupts.
+    /// interruptor.interrupt()?;
+    /// let err = reader.read(&mut [0; 5]).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::Other);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn interrupt_with_kind(&self, kind: ErrorKind) -> Result<(), InterruptSendError> {
+        assert!(
+            kind != ErrorKind::Interrupted,
+            "interrupts of kind ErrorKind::Interrupted would be retried"
+        );
+        self.send_interrupt(false, None, kind).map(|_| ())
+    }
+
+    /// Interrupts the read operations of the [`InterruptReader`] that
+    /// are made under `claim`
+    ///
+    /// The interrupt is held until a read operation is made under
+    /// `claim`, and no other read operation returns it. If `claim`
+    /// isn't registered, or stops being registered, it works like
+    /// [`Interruptor::interrupt`] instead. Check out
+    /// [`InterruptReader::register_claim`] for more information.
+    ///
+    /// Targeted interrupts are never coalesced, and aren't seen by
+    /// [`InterruptToken`]s.
+    ///
+    /// # Examples
+    ///
+    /// Two threads sharing a reader, each of them only stopped by the
+    /// interrupt that was meant for it:
+    ///
+    /// ```rust
+    /// use std::sync::{Arc, Barrier, Mutex};
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (mut reader, interruptor) = pair(std::io::repeat(b'a'));
+    /// let claims = [reader.register_claim(), reader.register_claim()];
+    /// let reader = Arc::new(Mutex::new(reader));
+    /// let barrier = Arc::new(Barrier::new(3));
+    ///
+    /// let join_handles = claims.map(|claim| {
+    ///     let reader = reader.clone();
+    ///     let barrier = barrier.clone();
+    ///     std::thread::spawn(move || {
+    ///         let mut read = |reads: usize| {
+    ///             let mut interrupts = 0;
+    ///             for _ in 0..reads {
+    ///                 let mut reader = reader.lock().unwrap();
+    ///                 match reader.read_claimed(claim, &mut [0; 16]) {
+    ///                     Ok(_) => {}
+    ///                     Err(err) if is_interrupt(&err) => interrupts += 1,
+    ///                     Err(err) => panic!("{err}"),
+    ///                 }
+    ///             }
+    ///             interrupts
+    ///         };
+    ///
+    ///         let before = read(50);
+    ///         barrier.wait();
+    ///         // Reading until the interrupt for this claim shows up.
+    ///         while read(1) == 0 {}
+    ///         (before, read(200))
+    ///     })
+    /// });
+    ///
+    /// barrier.wait();
+    /// for claim in claims {
+    ///     interruptor.interrupt_for(claim)?;
+    /// }
+    ///
+    /// // Each interrupt was seen once, by the thread it was meant for.
+    /// for join_handle in join_handles {
+    ///     assert_eq!(join_handle.join().unwrap(), (0, 0));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn interrupt_for(&self, claim: ClaimId) -> Result<(), InterruptSendError> {
+        self.event_tx
+            .send(Event::Interrupt(Interrupt {
+                seq: 0,
+                if_waiting: None,
+                reason: None,
+                label: self.label.clone(),
+                kind: ErrorKind::Other,
+                claim: Some(claim),
+            }))
+            .map_err(|_| InterruptSendError)?;
+        self.shared.signal_ready();
+        debug!(?claim, "targeted interrupt sent");
+        Ok(())
+    }
+
+    /// Interrupts the [`InterruptReader`], discarding the data that
+    /// was already read
+    ///
+    /// This is useful when an interrupt means that the old data is
+    /// stale, like when the output of a request that is now obsolete
+    /// is still arriving. Along with the usual [`InterruptReceived`]
+    /// error, the `InterruptReader` will throw away the contents of
+    /// its internal buffer, as well as any chunks that the worker
+    /// thread sent before the interrupt.
+    ///
+    /// Data read by the worker thread after the interrupt was
+    /// observed flows normally. Keep in mind that this includes data
+    /// that was already produced, but was still in the source (e.g.
+    /// in the buffer of a pipe), since that can't be told apart from
+    /// new data.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// // This chunk is partially read.
+    /// pipe_writer.write_all(b"stale")?;
+    /// let mut buf = [0; 2];
+    /// reader.read_exact(&mut buf)?;
+    /// assert_eq!(&buf, b"st");
+    ///
+    /// interruptor.interrupt_and_discard()?;
+    /// assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+    ///
+    /// // This one is never read.
+    /// pipe_writer.write_all(b"old")?;
+    /// std::thread::sleep(Duration::from_millis(50));
+    ///
+    /// interruptor.interrupt_and_discard()?;
+    /// assert!(is_interrupt(&reader.read(&mut buf).unwrap_err()));
+    ///
+    /// pipe_writer.write_all(b"new")?;
+    /// drop(pipe_writer);
+    ///
+    /// let mut rest = String::new();
+    /// reader.read_to_string(&mut rest)?;
+    /// assert_eq!(rest, "new");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn interrupt_and_discard(&self) -> Result<(), InterruptSendError> {
+        self.send_interrupt(true, None, ErrorKind::Other)
+            .map(|_| ())
+    }
+
+    /// Returns a clone of this `Interruptor`, with a label
+    ///
+    /// Every interrupt sent through the returned `Interruptor`, or
+    /// through its clones, carries the `label` in its
+    /// [`InterruptReceived`] error, which is accessible through
+    /// [`InterruptReceived::label`] and is shown when formatting the
+    /// error. This is useful for figuring out which part of an
+    /// application interrupted a read, when many of them hold a clone
+    /// of the same `Interruptor`. This `Interruptor` is unaffected.
+    ///
+    /// If the interrupt is coalesced into one that is still pending
+    /// (see [`Builder::coalesce_interrupts`]), the label of the
+    /// pending one is kept. The errors returned during an
+    /// [`Interruptor::interrupt_persistent`] don't carry a label.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    ///
+    /// use interrupt_read::{IoErrorExt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, _pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// let ui = interruptor.labeled("ui");
+    /// let network = interruptor.labeled(format!("network #{}", 2)).clone();
+    ///
+    /// ui.interrupt_with_reason("the user pressed Ctrl+C")?;
+    /// network.interrupt()?;
+    /// interruptor.interrupt()?;
+    ///
+    /// let err = reader.read(&mut [0; 5]).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Interruptor \"ui\" has interrupted: the user pressed Ctrl+C"
+    /// );
+    /// assert_eq!(err.into_interrupt().unwrap().label(), Some("ui"));
+    ///
+    /// let err = reader.read(&mut [0; 5]).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Interruptor \"network #2\" has interrupted"
+    /// );
+    /// assert_eq!(err.into_interrupt().unwrap().label(), Some("network #2"));
+    ///
+    /// // Unlabeled ones work as usual.
+    /// let err = reader.read(&mut [0; 5]).unwrap_err();
+    /// assert_eq!(err.to_string(), "Interruptor has interrupted");
+    /// assert_eq!(err.into_interrupt().unwrap().label(), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn labeled(&self, label: impl Into<Cow<'static, str>>) -> Interruptor {
+        let mut interruptor = self.clone();
+        interruptor.label = Some(label.into());
+        interruptor
+    }
+
+    /// The label of this `Interruptor`, if it has one
+    ///
+    /// Check out [`Interruptor::labeled`] for more information.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Returns a [`WeakInterruptor`], which doesn't count towards
+    /// [`InterruptReader::interrupt_possible`]
+    ///
+    /// The label, if there is one, is kept. Check out
+    /// [`Builder::skip_checks_without_interruptors`] for an example.
+    pub fn downgrade(&self) -> WeakInterruptor {
+        WeakInterruptor {
+            event_tx: self.event_tx.clone(),
+            shared: Arc::downgrade(&self.shared),
+            label: self.label.clone(),
+        }
+    }
+
+    /// Returns an [`InterruptToken`], for checking for interrupts
+    /// without reading
+    ///
+    /// Check out [`InterruptToken`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (mut reader, interruptor) = pair(std::io::repeat(b'a'));
+    /// let token = interruptor.checkpoint_token();
+    ///
+    /// // Some work that takes a while, without ever reading.
+    /// let parser = std::thread::spawn(move || {
+    ///     let mut parsed = 0u64;
+    ///     while token.check().is_ok() {
+    ///         parsed = parsed.wrapping_add(1);
+    ///     }
+    ///     parsed
+    /// });
+    ///
+    /// interruptor.interrupt()?;
+    /// parser.join().unwrap();
+    ///
+    /// // The token consumed the interrupt, so reads go on as usual.
+    /// assert_eq!(reader.read(&mut [0; 4])?, 4);
+    ///
+    /// // And once a read has consumed one, the token doesn't see it.
+    /// let token = interruptor.checkpoint_token();
+    /// interruptor.interrupt()?;
+    /// assert!(token.is_interrupted());
+    /// assert!(is_interrupt(&reader.read(&mut [0; 4]).unwrap_err()));
+    /// assert!(!token.is_interrupted());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn checkpoint_token(&self) -> InterruptToken {
+        InterruptToken {
+            shared: self.shared.clone(),
+            sticky_since: None,
+        }
+    }
+
+    /// Returns an [`InterruptScope`], which interrupts the
+    /// [`InterruptReader`] when dropped
+    ///
+    /// This makes sure that the reader is interrupted on every way
+    /// out of a block of code, including early returns through `?`
+    /// and panics. If the work finished successfully and the reader
+    /// shouldn't be interrupted, call [`InterruptScope::disarm`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::BufRead;
+    ///
+    /// use interrupt_read::{Interruptor, is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, _pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// // Nothing is ever written, so this blocks until interrupted.
+    /// let join_handle = std::thread::spawn(move || reader.read_line(&mut String::new()));
+    ///
+    /// fn work(interruptor: &Interruptor) {
+    ///     let _scope = interruptor.scope();
+    ///     panic!("something went wrong");
+    /// }
+    ///
+    /// let interruptor = std::panic::AssertUnwindSafe(interruptor);
+    /// assert!(std::panic::catch_unwind(|| work(&interruptor)).is_err());
+    /// assert!(is_interrupt(&join_handle.join().unwrap().unwrap_err()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// On success, the scope can be disarmed:
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    ///
+    /// use interrupt_read::pair;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// fn work(pipe_writer: &mut impl Write) -> std::io::Result<()> {
+    ///     pipe_writer.write_all(b"done")
+    /// }
+    ///
+    /// let scope = interruptor.scope();
+    /// work(&mut pipe_writer)?;
+    /// scope.disarm();
+    ///
+    /// let mut buf = [0; 4];
+    /// reader.read_exact(&mut buf)?;
+    /// assert_eq!(&buf, b"done");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn scope(&self) -> InterruptScope<'_> {
+        InterruptScope(Some(Cow::Borrowed(self)))
+    }
+
+    /// Returns an [`InterruptScope`] that owns this `Interruptor`
+    ///
+    /// This is the same as [`Interruptor::scope`], but the scope
+    /// isn't tied to a reference, so it can be sent to other threads
+    /// or stored in a struct.
+    pub fn into_scope(self) -> InterruptScope<'static> {
+        InterruptScope(Some(Cow::Owned(self)))
+    }
+
+    /// Interrupts the [`InterruptReader`] until told otherwise
+    ///
+    /// While [`Interruptor::interrupt`] only makes one read
+    /// operation fail, this makes every [`Read`] and [`BufRead`]
+    /// operation return the [`InterruptReceived`] error right away,
+    /// until [`InterruptReader::clear_interrupt`] is called. This is
+    /// useful for "this whole thing is shutting down" scenarios,
+    /// where there are many places reading from the same
+    /// `InterruptReader`.
+    ///
+    /// Buffered data is retained, it can be seen through
+    /// [`InterruptReader::buffer`], and will be read once the
+    /// interrupt is cleared.
+    ///
+    /// This returns an [`Err`] if the `InterruptReader` has been
+    /// dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{BufRead, Read, Write},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// pipe_writer.write_all(b"kept\n")?;
+    /// std::thread::sleep(Duration::from_millis(50));
+    ///
+    /// interruptor.interrupt_persistent()?;
+    /// assert!(reader.is_interrupted());
+    ///
+    /// let mut line = String::new();
+    /// assert!(is_interrupt(&reader.read_line(&mut line).unwrap_err()));
+    /// assert!(is_interrupt(&reader.read(&mut [0; 4]).unwrap_err()));
+    /// assert!(is_interrupt(&reader.fill_buf().unwrap_err()));
+    ///
+    /// reader.clear_interrupt();
+    /// assert!(!reader.is_interrupted());
+    ///
+    /// reader.read_line(&mut line)?;
+    /// assert_eq!(line, "kept\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`InterruptReceived`]: crate::InterruptReceived
+    pub fn interrupt_persistent(&self) -> Result<(), InterruptSendError> {
+        // Also wakes up the reader, if it is waiting.
+        let seq = self.send_interrupt(false, None, ErrorKind::Other)?;
+        self.shared.persistent.store(seq, Relaxed);
+        Ok(())
+    }
+
+    /// Makes the [`InterruptReader`] reach the end of the stream
+    ///
+    /// While an interrupt means "stop what you're doing, but keep
+    /// going", this means "we're done". Once the `InterruptReader`
+    /// gets to the close, [`read`] will return `Ok(0)` and
+    /// [`fill_buf`] will return an empty slice, permanently, so
+    /// things like [`read_to_end`] and [`lines`] terminate naturally,
+    /// without an error.
+    ///
+    /// The close doesn't skip ahead, all the data that was read
+    /// before it, including what is in the internal buffer and the
+    /// chunks that the worker thread has already sent, is still
+    /// served first. Interrupts sent before the close are still
+    /// observed, while those sent after it are ignored.
+    ///
+    /// The worker thread is also told to stop, so it will exit right
+    /// after its current call to [`Read::read`] returns.
+    ///
+    /// Calling this more than once has no additional effect. This
+    /// returns an [`Err`] if the `InterruptReader` has been dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{BufRead, Read, Write},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::pair;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// pipe_writer.write_all(b"first\nsecond\nthird")?;
+    /// let mut line = String::new();
+    /// reader.read_line(&mut line)?;
+    /// assert_eq!(line, "first\n");
+    ///
+    /// interruptor.close()?;
+    /// interruptor.close()?;
+    ///
+    /// // The data from before the close is not lost.
+    /// let lines: Vec<String> = reader.by_ref().lines().collect::<Result<_, _>>()?;
+    /// assert_eq!(lines, ["second", "third"]);
+    ///
+    /// // And the stream has ended, even though it's still open.
+    /// pipe_writer.write_all(b"ignored\n")?;
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// assert_eq!(reader.read(&mut [0; 16])?, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`read`]: Read::read
+    /// [`fill_buf`]: BufRead::fill_buf
+    /// [`read_to_end`]: Read::read_to_end
+    /// [`lines`]: BufRead::lines
+    pub fn close(&self) -> Result<(), InterruptSendError> {
+        let mut state = self.shared.state();
+        if state.reader_dropped {
+            return Err(InterruptSendError);
+        } else if state.closed {
+            return Ok(());
+        }
+
+        self.event_tx
+            .send(Event::Close)
+            .map_err(|_| InterruptSendError)?;
+        self.shared.signal_ready();
+        state.closed = true;
+        drop(state);
+        self.shared.state_changed.notify_all();
+        self.shared.wake_worker();
+        Ok(())
+    }
+
+    /// Changes how fast the worker thread reads from the source
+    ///
+    /// This is the same as [`InterruptReader::set_throttle`], but it
+    /// can be called from other threads.
+    pub fn set_throttle(&self, rate: Option<Rate>) {
+        self.shared.set_throttle(rate);
+    }
+
+    /// Interrupts the [`InterruptReader`] when a
+    /// [`CancellationToken`] is cancelled
+    ///
+    /// This spawns a task on the current tokio runtime, which calls
+    /// [`Interruptor::interrupt`] exactly once, when the `token` is
+    /// cancelled. If it already was cancelled, the interrupt is sent
+    /// right away. Dropping the returned [`CancellationBinding`]
+    /// detaches the `token`, so cancelling it afterwards does
+    /// nothing.
+    ///
+    /// This function is only available with the `tokio` feature
+    /// enabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{io::BufRead, time::Duration};
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    /// use tokio_util::sync::CancellationToken;
+    ///
+    /// #[tokio::main(flavor = "multi_thread")]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let (pipe_reader, _pipe_writer) = std::io::pipe()?;
+    ///     let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    ///     let token = CancellationToken::new();
+    ///     let _binding = interruptor.bind_cancellation_token(token.clone());
+    ///
+    ///     let join_handle = std::thread::spawn(move || {
+    ///         let mut line = String::new();
+    ///         reader.read_line(&mut line)
+    ///     });
+    ///
+    ///     tokio::time::sleep(Duration::from_millis(50)).await;
+    ///     token.cancel();
+    ///
+    ///     assert!(is_interrupt(&join_handle.join().unwrap().unwrap_err()));
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`CancellationToken`]: tokio_util::sync::CancellationToken
+    #[cfg(feature = "tokio")]
+    pub fn bind_cancellation_token(
+        &self,
+        token: tokio_util::sync::CancellationToken,
+    ) -> CancellationBinding {
+        if token.is_cancelled() {
+            _ = self.interrupt();
+            return CancellationBinding(None);
+        }
+
+        let interruptor = self.clone();
+        let task = tokio::runtime::Handle::current().spawn(async move {
+            token.cancelled().await;
+            _ = interruptor.interrupt();
+        });
+
+        CancellationBinding(Some(task.abort_handle()))
+    }
+
+    /// Returns a [`CancellationToken`] that is cancelled on the next
+    /// interrupt
+    ///
+    /// The token is cancelled as soon as an interrupt is sent by any
+    /// [`Interruptor`] of the same [`InterruptReader`], even before
+    /// said interrupt is observed.
+    ///
+    /// This function is only available with the `tokio` feature
+    /// enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use interrupt_read::pair;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (_reader, interruptor) = pair(std::io::empty());
+    ///
+    /// let token = interruptor.as_cancellation_token();
+    /// assert!(!token.is_cancelled());
+    ///
+    /// interruptor.interrupt()?;
+    /// assert!(token.is_cancelled());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`CancellationToken`]: tokio_util::sync::CancellationToken
+    #[cfg(feature = "tokio")]
+    pub fn as_cancellation_token(&self) -> tokio_util::sync::CancellationToken {
+        let token = tokio_util::sync::CancellationToken::new();
+        self.shared.state().tokens.push(token.clone());
+        token
+    }
+
+    /// Returns a [`RawTrigger`], which can interrupt from a signal
+    /// handler
+    ///
+    /// Sending an interrupt allocates and takes locks, which is not
+    /// allowed inside of a signal handler. The `RawTrigger` gets
+    /// around that by only setting an atomic flag and writing a byte
+    /// to a pipe. A relay thread, spawned by this function, waits on
+    /// the other end of that pipe, and sends the actual interrupt
+    /// when the flag is set.
+    ///
+    /// The relay thread exits once every clone of the `RawTrigger`
+    /// is dropped, or once it fails to interrupt, because the
+    /// [`InterruptReader`] was dropped.
+    ///
+    /// This returns an [`Err`] if the pipe couldn't be created.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{io::BufRead, sync::OnceLock};
+    ///
+    /// use interrupt_read::{RawTrigger, is_interrupt, pair};
+    ///
+    /// static TRIGGER: OnceLock<RawTrigger> = OnceLock::new();
+    ///
+    /// # #[cfg(unix)]
+    /// extern "C" fn on_signal(_: libc::c_int) {
+    ///     if let Some(trigger) = TRIGGER.get() {
+    ///         trigger.trigger();
+    ///     }
+    /// }
+    ///
+    /// # #[cfg(unix)]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, _pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// _ = TRIGGER.set(interruptor.raw_trigger()?);
+    /// let handler = on_signal as extern "C" fn(libc::c_int);
+    /// unsafe { libc::signal(libc::SIGUSR1, handler as libc::sighandler_t) };
+    ///
+    /// std::thread::spawn(|| {
+    ///     std::thread::sleep(std::time::Duration::from_millis(50));
+    ///     unsafe { libc::raise(libc::SIGUSR1) };
+    /// });
+    ///
+    /// let mut line = String::new();
+    /// assert!(is_interrupt(&reader.read_line(&mut line).unwrap_err()));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(unix))]
+    /// # fn main() {}
+    /// ```
+    pub fn raw_trigger(&self) -> std::io::Result<RawTrigger> {
+        let (mut pipe_rx, pipe_tx) = std::io::pipe()?;
+
+        // A full pipe already has a byte waiting, so this can't block.
+        #[cfg(unix)]
+        set_nonblocking(pipe_tx.as_fd());
+
+        let triggered = Arc::new(AtomicBool::new(false));
+
+        std::thread::spawn({
+            let triggered = triggered.clone();
+            let interruptor = self.clone();
+            move || {
+                let mut buf = [0; 64];
+                loop {
+                    match pipe_rx.read(&mut buf) {
+                        // Every RawTrigger has been dropped.
+                        Ok(0) => break,
+                        Ok(_) => {
+                            if triggered.swap(false, Acquire) && interruptor.interrupt().is_err() {
+                                break;
+                            }
+                        }
+                        Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                        Err(_) => break,
+                    }
+                }
+            }
+        });
+
+        Ok(RawTrigger { triggered, pipe: Arc::new(pipe_tx) })
+    }
+
+    /// Cancels the `read` that the worker thread is blocked on
+    ///
+    /// On Windows, a thread blocked on a `ReadFile` call, like one
+    /// reading from the console or a pipe, can be forcibly woken up
+    /// with [`CancelSynchronousIo`]. The worker thread doesn't treat
+    /// the cancelled read as an error, it just starts reading again,
+    /// so this is mostly useful along with
+    /// [`Interruptor::interrupt`], if you want to avoid waiting
+    /// for the next byte to arrive.
+    ///
+    /// Dropping the [`InterruptReader`] also does this, so the
+    /// worker thread can actually exit, instead of staying blocked
+    /// forever.
+    ///
+    /// This is best effort, if the worker thread isn't blocked on a
+    /// cancellable read when this is called, nothing happens. Returns
+    /// an [`Err`] if the read couldn't be cancelled.
+    ///
+    /// This function is only available on Windows, with the
+    /// `windows-cancel` feature enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use interrupt_read::pair;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// // The writer never writes.
+    /// let (pipe_reader, _pipe_writer) = std::io::pipe()?;
+    /// let (reader, interruptor) = pair(pipe_reader);
+    ///
+    /// let is_reading = reader.is_reading_fn();
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// interruptor.cancel_blocking_read()?;
+    /// assert!(is_reading());
+    ///
+    /// // Without the cancellation, this would spin forever.
+    /// drop(reader);
+    /// while is_reading() {
+    ///     std::thread::yield_now();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`CancelSynchronousIo`]: https://learn.microsoft.com/en-us/windows/win32/fileio/cancelsynchronousio-func
+    #[cfg(all(windows, feature = "windows-cancel"))]
+    pub fn cancel_blocking_read(&self) -> std::io::Result<()> {
+        self.shared.cancel_worker_read()
+    }
+
+    /// Interrupts the [`InterruptReader`] and waits for the
+    /// interrupt to be observed
+    ///
+    /// This works like [`Interruptor::interrupt`], but it then
+    /// blocks until the `InterruptReader` has actually returned the
+    /// corresponding [`InterruptReceived`] error to its caller. This
+    /// is useful as a synchronization point, for example, if you
+    /// mustn't proceed until the reading thread has returned from a
+    /// blocked [`BufRead::read_line`].
+    ///
+    /// Data arriving in the meantime has no effect on this function,
+    /// only the observation of this interrupt (or of one that was
+    /// sent after it) counts as an acknowledgement.
+    ///
+    /// This function returns an [`Err`] if:
+    ///
+    /// - The `timeout` expires before the interrupt is observed, in
+    ///   which case, the interrupt is still pending, and will be
+    ///   observed by the next read operation.
+    /// - The `InterruptReader` was dropped (or consumed through
+    ///   [`InterruptReader::into_inner`]) before observing the
+    ///   interrupt.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{io::BufRead, time::Duration};
+    ///
+    /// use interrupt_read::{InterruptWaitError, is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// // The writer is kept alive, but never writes anything.
+    /// let (pipe_reader, _pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// // No read is in flight, so no one will observe the interrupt.
+    /// let result = interruptor.interrupt_and_wait(Some(Duration::from_millis(50)));
+    /// assert_eq!(result, Err(InterruptWaitError::TimedOut));
+    ///
+    /// let join_handle = std::thread::spawn(move || {
+    ///     let mut line = String::new();
+    ///     // This one observes the interrupt sent above.
+    ///     assert!(is_interrupt(&reader.read_line(&mut line).unwrap_err()));
+    ///     // And this one the interrupt sent below.
+    ///     assert!(is_interrupt(&reader.read_line(&mut line).unwrap_err()));
+    ///     reader
+    /// });
+    ///
+    /// interruptor.interrupt_and_wait(None)?;
+    /// let reader = join_handle.join().unwrap();
+    ///
+    /// // Dropping the reader from another thread also wakes us up.
+    /// std::thread::spawn(move || {
+    ///     std::thread::sleep(Duration::from_millis(50));
+    ///     drop(reader);
+    /// });
+    ///
+    /// let result = interruptor.interrupt_and_wait(None);
+    /// assert_eq!(result, Err(InterruptWaitError::ReaderDropped));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn interrupt_and_wait(&self, timeout: Option<Duration>) -> Result<(), InterruptWaitError> {
+        let seq = self
+            .send_interrupt(false, None, ErrorKind::Other)
+            .map_err(|_| InterruptWaitError::ReaderDropped)?;
+        let clock = &self.shared.clock;
+        let deadline = timeout.and_then(|timeout| clock.now().checked_add(timeout));
+
+        let mut state = self.shared.state();
+        loop {
+            if state.interrupts_observed >= seq {
+                break Ok(());
+            } else if state.reader_dropped {
+                break Err(InterruptWaitError::ReaderDropped);
+            }
+
+            let state_changed = &self.shared.state_changed;
+            state = match deadline {
+                Some(deadline) => {
+                    if clock.now() >= deadline {
+                        break Err(InterruptWaitError::TimedOut);
+                    }
+                    match state_changed.wait_timeout(state, clock.timeout_until(deadline)) {
+                        Ok((state, _)) => state,
+                        Err(err) => err.into_inner().0,
+                    }
+                }
+                None => state_changed
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+
+    /// Waits for the [`InterruptReader`] to be dropped
+    ///
+    /// This blocks until the `InterruptReader` is dropped, or
+    /// unwrapped through [`InterruptReader::into_inner`] or
+    /// [`InterruptReader::into_parts`], returning `true` if that
+    /// happened before the `timeout`, and `false` otherwise. If it
+    /// was already dropped, this returns `true` right away. Any
+    /// number of [`Interruptor`]s can wait at the same time, and all
+    /// of them are woken up.
+    ///
+    /// This is useful for knowing when the consumer is done with a
+    /// stream, in order to free whatever was tied to it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{sync::Arc, time::Duration};
+    ///
+    /// use interrupt_read::{Builder, testing::MockClock};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// // The clock never moves, so only the drop can end the waits.
+    /// let clock = Arc::new(MockClock::new());
+    /// let (pipe_reader, _pipe_writer) = std::io::pipe()?;
+    /// let (reader, interruptor) = Builder::new().clock(clock.clone()).pair(pipe_reader);
+    ///
+    /// assert!(!interruptor.wait_reader_dropped(Some(Duration::ZERO)));
+    ///
+    /// let waiters: Vec<_> = (0..3)
+    ///     .map(|_| {
+    ///         let interruptor = interruptor.clone();
+    ///         std::thread::spawn(move || {
+    ///             interruptor.wait_reader_dropped(Some(Duration::from_secs(5)))
+    ///         })
+    ///     })
+    ///     .collect();
+    ///
+    /// drop(reader);
+    /// for waiter in waiters {
+    ///     assert!(waiter.join().unwrap());
+    /// }
+    ///
+    /// // Already dropped, so this returns right away.
+    /// assert!(interruptor.wait_reader_dropped(Some(Duration::from_secs(5))));
+    /// assert_eq!(clock.elapsed(), Duration::ZERO);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wait_reader_dropped(&self, timeout: Option<Duration>) -> bool {
+        let clock = &self.shared.clock;
+        let deadline = timeout.and_then(|timeout| clock.now().checked_add(timeout));
+
+        let mut state = self.shared.state();
+        loop {
+            if state.reader_dropped {
+                break true;
+            }
+
+            let state_changed = &self.shared.state_changed;
+            state = match deadline {
+                Some(deadline) => {
+                    if clock.now() >= deadline {
+                        break false;
+                    }
+                    match state_changed.wait_timeout(state, clock.timeout_until(deadline)) {
+                        Ok((state, _)) => state,
+                        Err(err) => err.into_inner().0,
+                    }
+                }
+                None => state_changed
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+
+    /// Interrupts the [`InterruptReader`], but only if it is blocked
+    ///
+    /// If the `InterruptReader` is currently blocked inside of a
+    /// read operation, waiting for the worker thread to send some
+    /// data, this sends an interrupt and returns `Ok(true)`.
+    /// Otherwise, nothing is done, and `Ok(false)` is returned.
+    ///
+    /// This is useful for watchdogs, which want to kick the reader
+    /// if it is stuck, but don't want to make the next perfectly
+    /// healthy read fail if the consumer happens to be between reads.
+    ///
+    /// This is best-effort: the interrupt sent is targeted at the
+    /// specific wait that was observed, so if that wait ends (e.g.
+    /// because data arrived) before the interrupt reaches the reader,
+    /// the interrupt is discarded, and won't affect any subsequent
+    /// read operation. This means that `Ok(true)` can be returned
+    /// even if no read ended up being interrupted, but a read that
+    /// starts after this call will never be wrongly interrupted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{BufRead, Write},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// // The reader is idle, so nothing happens.
+    /// assert!(!interruptor.interrupt_if_blocked()?);
+    ///
+    /// let join_handle = std::thread::spawn(move || {
+    ///     let mut line = String::new();
+    ///     assert!(is_interrupt(&reader.read_line(&mut line).unwrap_err()));
+    ///     reader
+    /// });
+    ///
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// // Now the reader is blocked, waiting for data.
+    /// assert!(interruptor.interrupt_if_blocked()?);
+    /// let mut reader = join_handle.join().unwrap();
+    ///
+    /// // And reads are unaffected afterwards.
+    /// pipe_writer.write_all(b"hello\n")?;
+    /// let mut line = String::new();
+    /// reader.read_line(&mut line)?;
+    /// assert_eq!(line, "hello\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn interrupt_if_blocked(&self) -> Result<bool, InterruptSendError> {
+        let waiting = self.shared.waiting.load(Acquire);
+        if waiting & 1 == 0 {
+            if self.shared.state().reader_dropped {
+                return Err(InterruptSendError);
+            }
+            return Ok(false);
+        }
+
+        let mut state = self.shared.state();
+        if state.reader_dropped {
+            return Err(InterruptSendError);
+        }
+        let seq = state.interrupts_sent + 1;
+        let interrupt = Interrupt {
+            seq,
+            if_waiting: Some(waiting >> 1),
+            reason: None,
+            label: self.label.clone(),
+            kind: ErrorKind::Other,
+            claim: None,
+        };
+        self.event_tx
+            .send(Event::Interrupt(interrupt))
+            .map_err(|_| InterruptSendError)?;
+        self.shared.signal_ready();
+        debug!(seq, "interrupt sent, if still blocked");
+        state.interrupts_sent = seq;
+
+        Ok(true)
+    }
+
+    /// Pushes bytes into the stream, as if the source had produced
+    /// them
+    ///
+    /// The bytes are sent through the same channel as the chunks read
+    /// by the worker thread, so they are read in the order they were
+    /// sent, and are served just like any other chunk. This is useful
+    /// for things like local echo, or for testing code that reads
+    /// from an [`InterruptReader`]. Injecting an empty [`Vec`] does
+    /// nothing.
+    ///
+    /// Like other chunks, injected bytes are discarded by
+    /// [`Interruptor::interrupt_and_discard`], and an interrupt sent
+    /// after them may still be returned first, since interrupts take
+    /// precedence over data that isn't in the internal buffer yet.
+    /// The bytes are not lost in that case, and are read afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{
+    ///     io::{BufRead, Read},
+    ///     sync::{Arc, Barrier},
+    /// };
+    ///
+    /// use interrupt_read::{
+    ///     is_interrupt, pair,
+    ///     testing::{ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let barrier = Arc::new(Barrier::new(2));
+    /// let (mut reader, interruptor) = pair(ScriptedReader::new(vec![
+    ///     Step::Data(b"one\n".to_vec()),
+    ///     Step::WaitForSignal(barrier.clone()),
+    ///     Step::Data(b"four\n".to_vec()),
+    /// ]));
+    ///
+    /// let mut line = String::new();
+    /// reader.read_line(&mut line)?;
+    ///
+    /// // The source is waiting, so these come before the rest of it.
+    /// interruptor.inject(b"two\n".to_vec()).unwrap();
+    /// interruptor.inject(Vec::new()).unwrap();
+    /// interruptor.inject(b"three\n".to_vec()).unwrap();
+    /// interruptor.interrupt().unwrap();
+    /// barrier.wait();
+    ///
+    /// assert!(is_interrupt(&reader.fill_buf().unwrap_err()));
+    /// reader.read_to_string(&mut line)?;
+    /// assert_eq!(line, "one\ntwo\nthree\nfour\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn inject(&self, data: Vec<u8>) -> Result<(), InterruptSendError> {
+        if data.is_empty() {
+            return self.inject_event(None);
+        }
+
+        let len = data.len();
+        let arrived = self.shared.clock.now();
+        self.inject_event(Some(Event::Buf(data, len, arrived)))?;
+        debug!(len, "bytes injected");
+
+        Ok(())
+    }
+
+    /// Pushes an error into the stream, as if the source had
+    /// returned it
+    ///
+    /// Like with [`Interruptor::inject`], the error is sent through
+    /// the same channel as the chunks read by the worker thread, so
+    /// it is returned by the read operation after those are
+    /// consumed, and reading goes on normally after that. This is
+    /// useful for failing the consumer's loop with a specific error,
+    /// or for testing how those errors are dealt with.
+    ///
+    /// The error is returned untouched, so [`is_interrupt`] returns
+    /// `false` for it, unless it carries an [`InterruptReceived`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, Error, ErrorKind, Write};
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// interruptor.inject_err(Error::new(ErrorKind::ConnectionReset, "protocol violation"))?;
+    ///
+    /// let mut line = String::new();
+    /// let err = reader.read_line(&mut line).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::ConnectionReset);
+    /// assert_eq!(err.to_string(), "protocol violation");
+    /// assert!(!is_interrupt(&err));
+    ///
+    /// // Reads go on normally.
+    /// pipe_writer.write_all(b"hello\n")?;
+    /// reader.read_line(&mut line)?;
+    /// assert_eq!(line, "hello\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn inject_err(&self, err: Error) -> Result<(), InterruptSendError> {
+        debug!(error = %err, "error injected");
+        self.inject_event(Some(Event::Err(err)))
+    }
+
+    /// Sends an [`Event`] as if it came from the worker thread, or
+    /// checks if the reader is still there, if there is none
+    fn inject_event(&self, event: Option<Event>) -> Result<(), InterruptSendError> {
+        // The lock is held while sending, to keep the order with interrupts.
+        let state = self.shared.state();
+        if state.reader_dropped {
+            return Err(InterruptSendError);
+        }
+
+        let Some(event) = event else {
+            return Ok(());
+        };
+
+        if let Event::Buf(_, len, _) = &event {
+            self.shared.queued.fetch_add(*len, Relaxed);
+            self.shared.held.fetch_add(*len, Relaxed);
+        }
+        self.event_tx.send(event).map_err(|_| InterruptSendError)?;
+        self.shared.signal_ready();
+        Ok(())
+    }
+
+    /// Sends an interrupt, returning its sequence number
+    fn send_interrupt(
+        &self,
+        discard: bool,
+        reason: Option<Arc<str>>,
+        kind: ErrorKind,
+    ) -> Result<u64, InterruptSendError> {
+        let mut state = self.shared.state();
+        if state.reader_dropped {
+            return Err(InterruptSendError);
+        }
+
+        #[cfg(feature = "tokio")]
+        for token in state.tokens.drain(..) {
+            token.cancel();
+        }
+
+        // Set before sending, so the chunks ahead of the interrupt are
+        // discarded.
+        if discard {
+            let seq = if self.shared.coalesce_interrupts && state.interrupt_pending {
+                state.interrupts_sent
+            } else {
+                state.interrupts_sent + 1
+            };
+            self.shared.discard_until.store(seq, Relaxed);
+        }
+
+        // An interrupt is still pending, so this one is coalesced into it.
+        if self.shared.coalesce_interrupts && state.interrupt_pending {
+            return Ok(state.interrupts_sent);
+        }
+
+        // The lock is held while sending, so sequence numbers arrive in
+        // order.
+        let seq = state.interrupts_sent + 1;
+        self.event_tx
+            .send(Event::Interrupt(Interrupt {
+                seq,
+                if_waiting: None,
+                reason,
+                label: self.label.clone(),
+                kind,
+                claim: None,
+            }))
+            .map_err(|_| InterruptSendError)?;
+        self.shared.signal_ready();
+        debug!(seq, discard, "interrupt sent");
+
+        state.interrupts_sent = seq;
+        state.interrupt_pending = true;
+        self.shared.token_interrupt.store(seq, Release);
+        Ok(seq)
+    }
+}
+
+impl Clone for Interruptor {
+    fn clone(&self) -> Self {
+        let mut interruptor = Interruptor::new(self.event_tx.clone(), self.shared.clone());
+        interruptor.label = self.label.clone();
+        interruptor
+    }
+}
+
+impl Drop for Interruptor {
+    fn drop(&mut self) {
+        // Anything sent through it is visible once this is.
+        self.shared.interruptors.fetch_sub(1, Release);
+    }
+}
+
+/// An [`Interruptor`] that doesn't count towards
+/// [`InterruptReader::interrupt_possible`]
+///
+/// This is acquired through [`Interruptor::downgrade`], and can't
+/// interrupt on its own, but can be turned back into an
+/// `Interruptor` through [`WeakInterruptor::upgrade`], for as long
+/// as the [`InterruptReader`] exists.
+#[derive(Debug, Clone)]
+pub struct WeakInterruptor {
+    event_tx: mpsc::Sender<Event>,
+    shared: Weak<Shared>,
+    label: Option<Cow<'static, str>>,
+}
+
+impl WeakInterruptor {
+    /// Returns an [`Interruptor`], or [`None`] if the
+    /// [`InterruptReader`] was dropped
+    pub fn upgrade(&self) -> Option<Interruptor> {
+        let shared = self.shared.upgrade()?;
+        if shared.state().reader_dropped {
+            return None;
+        }
+
+        let mut interruptor = Interruptor::new(self.event_tx.clone(), shared);
+        interruptor.label = self.label.clone();
+        Some(interruptor)
+    }
+}
+
+/// A cheap way to check for interrupts, in code that doesn't read
+///
+/// This is acquired through [`Interruptor::checkpoint_token`], and
+/// is meant for CPU-bound work, like parsing what was already read,
+/// which should stop when the [`InterruptReader`] is interrupted,
+/// but never calls into it. Checking only loads a couple of atomics.
+///
+/// An interrupt is seen by every `InterruptToken` once
+/// [`Interruptor::interrupt`] or any of its variants returns, and
+/// stops being seen once it was consumed, either by a read
+/// operation returning its error, or by [`InterruptToken::check`].
+/// Consuming it through a token means no read operation returns it,
+/// and it doesn't discard any data.
+///
+/// A persistent interrupt, sent through
+/// [`Interruptor::interrupt_persistent`], is seen until it is
+/// cleared, and interrupts sent through
+/// [`Interruptor::interrupt_if_blocked`] are never seen, since they
+/// only apply to a read operation that is blocked.
+///
+/// A [`sticky`] token keeps seeing an interrupt after it was
+/// consumed, and never consumes one.
+///
+/// [`sticky`]: InterruptToken::sticky
+#[derive(Debug, Clone)]
+pub struct InterruptToken {
+    shared: Arc<Shared>,
+    /// The last interrupt that was consumed when it became sticky, if
+    /// it is.
+    sticky_since: Option<u64>,
+}
+
+impl InterruptToken {
+    /// Makes this token sticky
+    ///
+    /// It then sees every interrupt that wasn't consumed by the time
+    /// this was called, or that was sent afterwards, even after it
+    /// was consumed, and [`InterruptToken::check`] no longer consumes
+    /// them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    ///
+    /// use interrupt_read::{is_interrupt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (mut reader, interruptor) = pair(std::io::repeat(b'a'));
+    /// let token = interruptor.checkpoint_token().sticky();
+    ///
+    /// interruptor.interrupt()?;
+    /// assert!(token.check().is_err());
+    ///
+    /// // Nothing was consumed, so the read is interrupted as well.
+    /// assert!(is_interrupt(&reader.read(&mut [0; 4]).unwrap_err()));
+    /// assert!(token.is_interrupted());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sticky(mut self) -> Self {
+        self.sticky_since = Some(self.shared.observed.load(Acquire));
+        self
+    }
+
+    /// Wether there is an interrupt that this token sees
+    ///
+    /// This doesn't consume it.
+    pub fn is_interrupted(&self) -> bool {
+        let since = self
+            .sticky_since
+            .unwrap_or_else(|| self.shared.observed.load(Acquire));
+        self.shared.persistent.load(Relaxed) > 0
+            || self.shared.token_interrupt.load(Acquire) > since
+    }
+
+    /// Returns an [`Err`] if there is an interrupt that this token
+    /// sees, consuming it unless the token is [`sticky`]
+    ///
+    /// Since the token doesn't receive the interrupt itself, the
+    /// [`InterruptReceived`] doesn't carry its reason or label.
+    ///
+    /// [`sticky`]: InterruptToken::sticky
+    pub fn check(&self) -> Result<(), InterruptReceived> {
+        if !self.is_interrupted() {
+            return Ok(());
+        } else if self.sticky_since.is_some() || self.shared.persistent.load(Relaxed) > 0 {
+            return Err(InterruptReceived::new());
+        }
+
+        let seq = self.shared.token_interrupt.load(Acquire);
+        let mut state = self.shared.state();
+        // Only one of the tokens checking at the same time consumes it.
+        if state.interrupts_observed >= seq {
+            return Ok(());
+        }
+        state.interrupt_pending = false;
+        state.interrupts_observed = seq;
+        self.shared.observed.store(seq, Release);
+        drop(state);
+        self.shared.state_changed.notify_all();
+
+        Err(InterruptReceived::new())
+    }
+}
+
+/// Identifies a registered claim of an [`InterruptReader`]
+///
+/// This is acquired through [`InterruptReader::register_claim`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClaimId(u64);
+
+/// An [`InterruptReader`] whose read operations are made under a
+/// claim
+///
+/// This is acquired through [`InterruptReader::claimed`], and
+/// dereferences to the `InterruptReader`. Once it is dropped, read
+/// operations are no longer made under the claim.
+#[derive(Debug)]
+pub struct Claimed<'a, R: Read> {
+    reader: &'a mut InterruptReader<R>,
+    /// The claim that was current before this one.
+    previous: Option<ClaimId>,
+}
+
+impl<R: Read> std::ops::Deref for Claimed<'_, R> {
+    type Target = InterruptReader<R>;
+
+    fn deref(&self) -> &Self::Target {
+        self.reader
+    }
+}
+
+impl<R: Read> std::ops::DerefMut for Claimed<'_, R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.reader
+    }
+}
+
+impl<R: Read> Read for Claimed<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<R: Read> BufRead for Claimed<'_, R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.reader.consume(amount);
+    }
+}
+
+impl<R: Read> Drop for Claimed<'_, R> {
+    fn drop(&mut self) {
+        self.reader.shared.claims().current = self.previous;
+        self.reader.sort_held_interrupts();
+    }
+}
+
+/// An error occurred while calling [`Interruptor::interrupt`].
+///
+/// This means that the receiving [`InterruptReader`] has been
+/// dropped.
+#[derive(Debug, Clone, Copy)]
+pub struct InterruptSendError;
+
+impl std::fmt::Display for InterruptSendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("InterruptReader has been dropped")
+    }
+}
+
+impl std::error::Error for InterruptSendError {}
+
+/// An error occurred while calling
+/// [`Interruptor::interrupt_and_wait`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptWaitError {
+    /// The [`InterruptReader`] was dropped before observing the
+    /// interrupt.
+    ReaderDropped,
+    /// The timeout expired before the [`InterruptReader`] observed
+    /// the interrupt.
+    TimedOut,
+}
+
+impl std::fmt::Display for InterruptWaitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterruptWaitError::ReaderDropped => f.write_str("InterruptReader has been dropped"),
+            InterruptWaitError::TimedOut => f.write_str("timed out waiting for the interrupt"),
+        }
+    }
+}
+
+impl std::error::Error for InterruptWaitError {}
+
+/// Indicates that an [`Interruptor`] has called
+/// [`Interruptor::interrupt`], causing a read operation to be
+/// interrupted.
+///
+/// If the interrupt was sent through
+/// [`Interruptor::interrupt_with_reason`], this also carries the
+/// reason, and if it was sent through an [`Interruptor::labeled`]
+/// one, it carries the label, both of which are included when it is
+/// formatted.
+///
+/// It also carries how many bytes the [`InterruptReader`] had
+/// delivered when it was interrupted, accessible through
+/// [`InterruptReceived::bytes_delivered`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InterruptReceived {
+    reason: Option<Arc<str>>,
+    label: Option<Cow<'static, str>>,
+    bytes_delivered: u64,
+}
+
+impl InterruptReceived {
+    /// Returns a new `InterruptReceived`, without a reason
+    pub const fn new() -> Self {
+        Self {
+            reason: None,
+            label: None,
+            bytes_delivered: 0,
+        }
+    }
+
+    /// Returns a new `InterruptReceived`, with a reason
+    pub fn with_reason(reason: impl Into<String>) -> Self {
+        Self {
+            reason: Some(Arc::from(reason.into())),
+            label: None,
+            bytes_delivered: 0,
+        }
+    }
+
+    /// The reason given for the interrupt, if there is one
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+
+    /// The label of the [`Interruptor`] that sent the interrupt, if
+    /// it had one
+    ///
+    /// Check out [`Interruptor::labeled`] for more information.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// How many bytes the [`InterruptReader`] had delivered to its
+    /// consumer, since it was created, when this interrupt was
+    /// returned
+    ///
+    /// This counts the bytes put in the buffers of [`Read`] methods,
+    /// as well as the ones passed to [`BufRead::consume`], so it is
+    /// the position to resume a transfer from. Bytes that were only
+    /// looked at through [`BufRead::fill_buf`] are not counted.
+    ///
+    /// For an `InterruptReceived` that didn't come from an
+    /// `InterruptReader`, like one made through
+    /// [`InterruptReceived::new`], this is always `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, Read, Write};
+    ///
+    /// use interrupt_read::{IoErrorExt, pair};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+    /// let (mut reader, interruptor) = pair(pipe_reader);
+    ///
+    /// pipe_writer.write_all(b"hello\nworld, how are you?")?;
+    ///
+    /// let mut line = String::new();
+    /// reader.read_line(&mut line)?;
+    /// let mut buf = [0; 5];
+    /// reader.read_exact(&mut buf)?;
+    /// reader.fill_buf()?;
+    /// reader.consume(2);
+    ///
+    /// interruptor.interrupt()?;
+    /// let interrupt = reader.read(&mut buf).unwrap_err().into_interrupt().unwrap();
+    /// assert_eq!(interrupt.bytes_delivered(), 6 + 5 + 2);
+    ///
+    /// // It keeps counting after that.
+    /// let mut rest = String::new();
+    /// drop(pipe_writer);
+    /// reader.read_to_string(&mut rest)?;
+    /// assert_eq!(rest, "how are you?");
+    ///
+    /// interruptor.interrupt()?;
+    /// let interrupt = reader.read(&mut buf).unwrap_err().into_interrupt().unwrap();
+    /// assert_eq!(interrupt.bytes_delivered(), 25);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bytes_delivered(&self) -> u64 {
+        self.bytes_delivered
+    }
+}
+
+impl std::fmt::Display for InterruptReceived {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "Interruptor {label:?} has interrupted")?,
+            None => f.write_str("Interruptor has interrupted")?,
+        }
+
+        match &self.reason {
+            Some(reason) => write!(f, ": {reason}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for InterruptReceived {}
+
+/// The payload of the errors returned once an [`InterruptReader`] is
+/// poisoned
+///
+/// Check out [`Builder::poison_after`] for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Poisoned {
+    kind: ErrorKind,
+    errors: u32,
+}
+
+impl Poisoned {
+    /// The kind of the error that poisoned the stream
+    pub fn last_error_kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// How many errors in a row poisoned the stream
+    pub fn consecutive_errors(&self) -> u32 {
+        self.errors
+    }
+}
+
+impl std::fmt::Display for Poisoned {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the stream was poisoned by {} errors in a row, the last one being: {}",
+            self.errors, self.kind
+        )
+    }
+}
+
+impl std::error::Error for Poisoned {}
+
+/// The payload of the error returned when writing into the sink of
+/// [`InterruptReader::divert_to`] fails
+///
+/// The error has the same [`ErrorKind`] as its [`cause`].
+///
+/// [`cause`]: DivertFailed::cause
+#[derive(Debug)]
+pub struct DivertFailed {
+    cause: Error,
+}
+
+impl DivertFailed {
+    /// The error returned by the sink
+    pub fn cause(&self) -> &Error {
+        &self.cause
+    }
+}
+
+impl std::fmt::Display for DivertFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "writing into the sink failed: {}", self.cause)
+    }
+}
+
+impl std::error::Error for DivertFailed {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.cause)
+    }
+}
+
+/// The payload of the error returned when the source claims to have
+/// read more bytes than the buffer it was given could hold
+///
+/// None of the bytes of that read are returned, since there is no
+/// telling which of them are real. The error doesn't end the stream,
+/// so the following reads go on as usual. Every reader of this crate
+/// that reads from a source does this check.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::io::Read;
+///
+/// use interrupt_read::{
+///     Builder, MisbehavingReader,
+///     testing::{ScriptedReader, Step},
+/// };
+///
+/// # fn main() -> std::io::Result<()> {
+/// let (mut reader, _interruptor) = Builder::new().buffer_size(4).pair(ScriptedReader::new(vec![
+///     Step::OverReport(b"no".to_vec()),
+///     Step::Data(b"ok".to_vec()),
+/// ]));
+///
+/// let err = reader.read(&mut [0; 16]).unwrap_err();
+/// let misbehaving = err
+///     .get_ref()
+///     .and_then(|err| err.downcast_ref::<MisbehavingReader>())
+///     .unwrap();
+/// assert_eq!((misbehaving.claimed(), misbehaving.capacity()), (5, 4));
+///
+/// let mut out = Vec::new();
+/// reader.read_to_end(&mut out)?;
+/// assert_eq!(out, b"ok");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Without a worker thread, through [`pair_local`]:
+///
+/// ```rust
+/// use std::io::Read;
+///
+/// use interrupt_read::{
+///     MisbehavingReader, pair_local,
+///     testing::{ScriptedReader, Step},
+/// };
+///
+/// # fn main() -> std::io::Result<()> {
+/// let (mut reader, _interruptor) = pair_local(ScriptedReader::new(vec![
+///     Step::OverReport(b"no".to_vec()),
+///     Step::Data(b"ok".to_vec()),
+/// ]));
+///
+/// let err = reader.read(&mut [0; 16]).unwrap_err();
+/// assert!(err.get_ref().unwrap().is::<MisbehavingReader>());
+///
+/// let mut out = Vec::new();
+/// reader.read_to_end(&mut out)?;
+/// assert_eq!(out, b"ok");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Through [`broadcast::pair`], where every subscriber gets the
+/// error, without its payload:
+///
+/// ```rust
+/// use std::io::{ErrorKind, Read};
+///
+/// use interrupt_read::{
+///     broadcast,
+///     testing::{ScriptedReader, Step},
+/// };
+///
+/// # fn main() -> std::io::Result<()> {
+/// let (subscribers, _interruptor) = broadcast::pair(
+///     ScriptedReader::new(vec![
+///         Step::OverReport(b"no".to_vec()),
+///         Step::Data(b"ok".to_vec()),
+///     ]),
+///     2,
+/// );
+///
+/// for mut subscriber in subscribers {
+///     let err = subscriber.read(&mut [0; 16]).unwrap_err();
+///     assert_eq!(err.kind(), ErrorKind::Other);
+///
+///     let mut out = Vec::new();
+///     subscriber.read_to_end(&mut out)?;
+///     assert_eq!(out, b"ok");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`pair_local`]: crate::pair_local
+/// [`broadcast::pair`]: crate::broadcast::pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MisbehavingReader {
+    claimed: usize,
+    capacity: usize,
+}
+
+impl MisbehavingReader {
+    /// How many bytes the source claimed to have read
+    pub fn claimed(&self) -> usize {
+        self.claimed
+    }
+
+    /// How many bytes the buffer it was given could hold
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl std::fmt::Display for MisbehavingReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the source claimed to read {} bytes into a buffer of {}",
+            self.claimed, self.capacity
+        )
+    }
+}
+
+impl std::error::Error for MisbehavingReader {}
+
+/// A guard that interrupts an [`InterruptReader`] when dropped
+///
+/// This is acquired through [`Interruptor::scope`] or
+/// [`Interruptor::into_scope`]. If the reader was already dropped,
+/// nothing happens.
+#[derive(Debug)]
+#[must_use = "the reader is interrupted as soon as the scope is dropped"]
+pub struct InterruptScope<'a>(Option<Cow<'a, Interruptor>>);
+
+impl InterruptScope<'_> {
+    /// Drops the scope without interrupting the [`InterruptReader`]
+    pub fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for InterruptScope<'_> {
+    fn drop(&mut self) {
+        if let Some(interruptor) = self.0.take() {
+            // The reader being gone is fine at this point.
+            _ = interruptor.interrupt();
+        }
+    }
+}
+
+/// A binding between a [`CancellationToken`] and an
+/// [`Interruptor`]
+///
+/// This is acquired through [`Interruptor::bind_cancellation_token`],
+/// and detaches the token when dropped.
+///
+/// [`CancellationToken`]: tokio_util::sync::CancellationToken
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct CancellationBinding(Option<tokio::task::AbortHandle>);
+
+#[cfg(feature = "tokio")]
+impl Drop for CancellationBinding {
+    fn drop(&mut self) {
+        if let Some(task) = self.0.take() {
+            task.abort();
+        }
+    }
+}
+
+/// A trigger for interrupts that is safe to use in a signal handler
+///
+/// This is acquired through [`Interruptor::raw_trigger`]. Calling
+/// [`RawTrigger::trigger`] will make a relay thread interrupt the
+/// [`InterruptReader`], just like [`Interruptor::interrupt`] would.
+#[derive(Debug, Clone)]
+pub struct RawTrigger {
+    triggered: Arc<AtomicBool>,
+    pipe: Arc<PipeWriter>,
+}
+
+impl RawTrigger {
+    /// Interrupts the [`InterruptReader`], through the relay thread
+    ///
+    /// This function only performs two operations, both of which are
+    /// async-signal-safe:
+    ///
+    /// - An atomic store of `true` into an [`AtomicBool`].
+    /// - On Unix, a single call to [`write(2)`] of one byte, on the
+    ///   non blocking write end of a pipe. On other platforms, a
+    ///   regular write to the pipe is done instead.
+    ///
+    /// The result of the write is ignored, since it can only fail if
+    /// the pipe is full, in which case the relay thread will wake up
+    /// anyway, or if the relay thread has exited, in which case the
+    /// `InterruptReader` has been dropped. Triggering more than once
+    /// before the relay thread wakes up results in only one
+    /// interrupt.
+    ///
+    /// [`write(2)`]: https://man7.org/linux/man-pages/man2/write.2.html
+    pub fn trigger(&self) {
+        self.triggered.store(true, Release);
+
+        #[cfg(unix)]
+        // SAFETY: The pointer points to one valid byte.
+        unsafe {
+            libc::write(self.pipe.as_raw_fd(), [1u8].as_ptr().cast(), 1)
+        };
+        #[cfg(not(unix))]
+        {
+            _ = (&*self.pipe).write(&[1]);
+        }
+    }
+}
+
+/// A handle for observing the [`ReaderState`] of an
+/// [`InterruptReader`]
+///
+/// This handle is cheap to clone, and can be sent to other threads.
+/// It is acquired through [`InterruptReader::state_watch`].
+#[derive(Debug, Clone)]
+pub struct StateWatch(Arc<Shared>);
+
+impl StateWatch {
+    /// The current [`ReaderState`] of the [`InterruptReader`]
+    ///
+    /// Keep in mind that this is just a snapshot, the state may
+    /// have changed by the time you look at it.
+    pub fn get(&self) -> ReaderState {
+        if self.0.waiting.load(Acquire) & 1 == 1 {
+            ReaderState::WaitingForData
+        } else {
+            match self.0.buffered.load(Relaxed) {
+                0 => ReaderState::Idle,
+                buffered => ReaderState::Draining { buffered },
+            }
+        }
+    }
+}
+
+/// The state of an [`InterruptReader`], as observed by a
+/// [`StateWatch`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReaderState {
+    /// The reader isn't being read from, and has nothing in its
+    /// internal buffer.
+    Idle,
+    /// The reader is blocked, waiting for data from the worker
+    /// thread.
+    ///
+    /// This is also the state while a dropped reader waits for the
+    /// worker thread to finish, with [`DropBehavior::Join`] or
+    /// [`DropBehavior::JoinTimeout`].
+    WaitingForData,
+    /// The reader has data in its internal buffer, which is yet to be
+    /// consumed.
+    Draining {
+        /// How many bytes are left in the internal buffer.
+        buffered: usize,
+    },
+}
+
+/// State shared between an [`InterruptReader`] and its
+/// [`Interruptor`]s.
+#[derive(Debug)]
+struct Shared {
+    coalesce_interrupts: bool,
+    /// The number of waits for an event so far, shifted left by one,
+    /// with the lowest bit set while the reader is waiting.
+    waiting: AtomicU64,
+    /// How many bytes are left in the reader's internal buffer.
+    buffered: AtomicUsize,
+    /// How many bytes were sent by the worker, but not yet received.
+    queued: AtomicUsize,
+    /// The sequence number of the persistent interrupt, or 0 if
+    /// there is none.
+    persistent: AtomicU64,
+    /// The sequence number of the last interrupt that discards data,
+    /// only changed while holding the lock on `state`.
+    discard_until: AtomicU64,
+    /// The sequence number of the last interrupt that
+    /// [`InterruptToken`]s report, only changed while holding the
+    /// lock on `state`.
+    token_interrupt: AtomicU64,
+    /// A copy of [`State::interrupts_observed`], for
+    /// [`InterruptToken`]s.
+    observed: AtomicU64,
+    /// The bytes per second that are read from the source, or 0 if
+    /// reading isn't throttled.
+    throttle: AtomicU64,
+    /// Wether the worker's buffer adapts to the reads.
+    adaptive: bool,
+    /// The size of the buffer that the worker reads into.
+    buffer_size: AtomicUsize,
+    /// The size of the biggest read of the reader, which the
+    /// worker's buffer grows to, unless it adapts to the reads.
+    read_size: AtomicUsize,
+    /// The message of the worker's panic, if it panicked.
+    panic_message: std::sync::OnceLock<String>,
+    /// Where the session is recorded, if it is.
+    recorder: Mutex<Option<record::Recorder>>,
+    /// How many chunks are kept, if the oldest ones are dropped
+    /// instead of waiting for the reader.
+    max_latest: Option<usize>,
+    /// How many bytes were dropped because of `max_latest`.
+    dropped: AtomicU64,
+    /// How many bytes can be held by the reader before the worker
+    /// thread stops reading, if there is a cap.
+    max_buffered_bytes: Option<usize>,
+    /// How many bytes are in the chunks that were sent to the reader,
+    /// but not handed back yet, only decreased while holding the lock
+    /// on `pool`.
+    held: AtomicUsize,
+    /// Where the time comes from.
+    clock: Arc<dyn Clock>,
+    /// How many [`Interruptor`]s there are, not counting the
+    /// [`WeakInterruptor`]s.
+    interruptors: AtomicUsize,
+    /// How many [`Interruptor`]s were created so far.
+    interruptors_created: AtomicU64,
+    /// The [`Interruptor`] of the stage before this one, for
+    /// [`InterruptReader::pipe_through`].
+    upstream: std::sync::OnceLock<Interruptor>,
+    /// The sequence number of the interrupt that was sent to
+    /// `upstream` when the reader was dropped, or 0 if there is none.
+    upstream_interrupt: AtomicU64,
+    /// The claims of the reader, and the interrupts held for them.
+    claims: Mutex<Claims>,
+    /// Where the chunks go instead of the reader, if they are
+    /// diverted.
+    diversion: Mutex<Option<Diversion>>,
+    /// Wether there is a `diversion`, only changed while holding the
+    /// lock on it.
+    diverting: AtomicBool,
+    /// What the stall watchdog watches, if there is one.
+    watchdog: Option<Watchdog>,
+    /// Wakes up the worker thread, when it is waiting on the source.
+    cancel: Option<PipeWriter>,
+    /// Signals that something arrived, for
+    /// [`InterruptReader::readiness_fd`].
+    #[cfg(unix)]
+    ready: std::sync::OnceLock<ReadyPipe>,
+    /// A duplicate of the worker thread's handle, used to cancel its
+    /// reads.
+    #[cfg(all(windows, feature = "windows-cancel"))]
+    worker_thread: std::sync::OnceLock<std::os::windows::io::OwnedHandle>,
+    state: Mutex<State>,
+    state_changed: Condvar,
+    pool: Mutex<Pool>,
+    pool_changed: Condvar,
+}
+
+impl Shared {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn claims(&self) -> MutexGuard<'_, Claims> {
+        self.claims.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Records something, if the session is being recorded
+    ///
+    /// If writing to the sink fails, the recording stops.
+    fn record(&self, f: impl FnOnce(&mut record::Recorder) -> std::io::Result<()>) {
+        let mut recorder = self.recorder.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(rec) = recorder.as_mut()
+            && let Err(_err) = f(rec)
+        {
+            debug!(error = %_err, "recording stopped");
+            *recorder = None;
+        }
+    }
+
+    /// Marks the start of a wait, returning its number
+    fn start_waiting(&self) -> u64 {
+        // Only the reader changes this value, so there are no races.
+        let wait = (self.waiting.load(Relaxed) >> 1) + 1;
+        self.waiting.store((wait << 1) | 1, Release);
+        wait
+    }
+
+    fn stop_waiting(&self) {
+        self.waiting.fetch_and(!1, Release);
+    }
+
+    /// Waits for a free buffer, returning an [`Err`] if the worker
+    /// thread should stop, or run a [`Command`] in the meantime
+    fn take_buffer(&self) -> Result<Vec<u8>, Option<Command>> {
+        let mut pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            let at_capacity = self.at_capacity();
+            let (in_use, limit) = self.buffer_usage(&pool);
+            if pool.stopped {
+                break Err(None);
+            } else if !at_capacity
+                && in_use < limit
+                && let Some(buf) = pool.free.pop()
+            {
+                break Ok(buf);
+            } else if let Some(command) = pool.command.take() {
+                break Err(Some(command));
+            } else if !at_capacity && self.max_latest.is_some() {
+                // The reader will drop the extra buffers.
+                break Ok(Vec::new());
+            } else if self.diverting.load(Relaxed) {
+                // The chunks don't go to the reader, so neither do the buffers.
+                break Ok(Vec::new());
+            }
+
+            pool.worker_waiting = true;
+            pool = self
+                .pool_changed
+                .wait(pool)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    fn diversion(&self) -> MutexGuard<'_, Option<Diversion>> {
+        self.diversion
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Writes a chunk into the sink of the [`Diversion`], returning
+    /// [`None`] if there is none
+    ///
+    /// If writing fails, the diversion ends, and the error is
+    /// returned along with how many bytes were written.
+    fn divert(&self, bytes: &[u8]) -> Option<Result<(), (Error, usize)>> {
+        let mut diversion = self.diversion();
+        let sink = diversion.as_mut()?;
+
+        let mut written = 0;
+        while written < bytes.len() {
+            match sink.sink.write(&bytes[written..]) {
+                Ok(0) => {
+                    *diversion = None;
+                    self.diverting.store(false, Relaxed);
+                    return Some(Err((ErrorKind::WriteZero.into(), written)));
+                }
+                Ok(len) => {
+                    written += len;
+                    sink.bytes += len as u64;
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => {
+                    debug!(error = %err, "diversion failed");
+                    *diversion = None;
+                    self.diverting.store(false, Relaxed);
+                    return Some(Err((err, written)));
+                }
+            }
+        }
+
+        Some(Ok(()))
+    }
+
+    /// Wether the reader holds as many bytes as the cap allows, so
+    /// the worker thread should wait
+    fn at_capacity(&self) -> bool {
+        self.max_buffered_bytes
+            .is_some_and(|max| self.held.load(Relaxed) >= max)
+    }
+
+    /// How many buffers are in use, by the worker thread or the
+    /// reader, and how many can be
+    ///
+    /// Small chunks can take up all the buffers, but big ones only
+    /// take [`BIG_BUFFERS`] of them, so they don't take up as much
+    /// memory.
+    fn buffer_usage(&self, pool: &Pool) -> (usize, usize) {
+        let limit = if self.buffer_size.load(Relaxed) > SMALL_BUFFER {
+            BIG_BUFFERS
+        } else {
+            BUFFERS
+        };
+        (BUFFERS.saturating_sub(pool.free.len()), limit)
+    }
+
+    /// Lets the stall watchdog know that `len` bytes were read from
+    /// the source, if there is one
+    fn produced(&self, len: usize) {
+        let Some(watchdog) = &self.watchdog else {
+            return;
+        };
+        if len == 0 {
+            return;
+        }
+
+        let elapsed = (self.clock.now())
+            .saturating_duration_since(watchdog.start)
+            .as_nanos();
+        watchdog
+            .last_data
+            .store(u64::try_from(elapsed).unwrap_or(u64::MAX), Relaxed);
+        watchdog.total.fetch_add(len as u64, SeqCst);
+
+        // If the watchdog didn't see this, it is stalled, and taking
+        // the lock makes sure that it is waiting.
+        if watchdog.stalled.swap(false, SeqCst) {
+            drop(self.state());
+            self.state_changed.notify_all();
+        }
+    }
+
+    /// Hands a buffer back to the worker thread, along with the
+    /// length of the chunk that was in it
+    ///
+    /// In order to avoid waking it up for every buffer, the worker
+    /// thread is only woken up once half of the buffers are free,
+    /// or once the reader starts waiting, through
+    /// [`Shared::flush_buffers`], and never while the reader is at
+    /// capacity.
+    fn give_buffer(&self, buf: Vec<u8>, len: usize) {
+        let mut pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        self.held.fetch_sub(len, Relaxed);
+        // There can be one too many, from InterruptReader::chain_front.
+        if pool.free.len() < BUFFERS {
+            pool.free.push(buf);
+        }
+        trace!(free = pool.free.len(), "buffer handed back");
+        let (in_use, limit) = self.buffer_usage(&pool);
+        if pool.worker_waiting
+            && (in_use <= limit / 2 || self.max_latest.is_some())
+            && !self.at_capacity()
+        {
+            pool.worker_waiting = false;
+            self.pool_changed.notify_one();
+        }
+    }
+
+    /// Puts a chunk with the ones that were kept, if the oldest
+    /// chunks are dropped, returning the [`Event`] to send instead
+    ///
+    /// The oldest chunks are dropped once there are more than
+    /// `max_latest` of them.
+    fn keep_latest(&self, event: Event) -> Event {
+        let (max, buf, len, arrived) = match (self.max_latest, event) {
+            // The end of the stream is never dropped.
+            (Some(max), Event::Buf(buf, len, arrived)) if len > 0 => (max, buf, len, arrived),
+            (_, event) => return event,
+        };
+
+        let mut pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        let id = pool.next_latest;
+        pool.next_latest += 1;
+        pool.latest.push_back((id, buf, len, arrived));
+
+        while pool.latest.len() > max {
+            let (_, buf, len, _) = pool.latest.pop_front().unwrap();
+            self.queued.fetch_sub(len, Relaxed);
+            self.held.fetch_sub(len, Relaxed);
+            self.dropped.fetch_add(len as u64, Relaxed);
+            trace!(len, "dropped a chunk");
+            if pool.free.len() < BUFFERS {
+                pool.free.push(buf);
+            }
+        }
+
+        Event::Latest(id)
+    }
+
+    /// Takes the chunk of an [`Event::Latest`], returning [`None`] if
+    /// it was dropped
+    fn take_latest(&self, id: u64) -> Option<Event> {
+        let mut pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        // Chunks are dropped in order, so if it's not the first one, it's
+        // gone.
+        if pool.latest.front().is_some_and(|(first, ..)| *first == id) {
+            let (_, buf, len, arrived) = pool.latest.pop_front().unwrap();
+            Some(Event::Buf(buf, len, arrived))
+        } else {
+            None
+        }
+    }
+
+    /// Makes the file descriptor of
+    /// [`InterruptReader::readiness_fd`] readable, if there is one
+    ///
+    /// This must be called after sending an [`Event`].
+    fn signal_ready(&self) {
+        #[cfg(unix)]
+        if let Some(ready) = self.ready.get()
+            && !ready
+                .signaled
+                .swap(true, std::sync::atomic::Ordering::AcqRel)
+        {
+            _ = (&ready.writer).write(&[0]);
+        }
+    }
+
+    /// Resets the file descriptor of
+    /// [`InterruptReader::readiness_fd`]
+    ///
+    /// This must be called before checking that there are no more
+    /// [`Event`]s, or one could be missed.
+    #[cfg(unix)]
+    fn reset_ready(&self) {
+        if let Some(ready) = self.ready.get()
+            && ready
+                .signaled
+                .swap(false, std::sync::atomic::Ordering::AcqRel)
+        {
+            // The byte may not have been written yet, in which case
+            // the next reset reads it.
+            _ = (&ready.reader).read(&mut [0; 64]);
+        }
+    }
+
+    /// Wakes up the worker thread, if it is waiting for buffers
+    /// that are already free
+    fn flush_buffers(&self) {
+        let mut pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        if pool.worker_waiting && !pool.free.is_empty() {
+            pool.worker_waiting = false;
+            self.pool_changed.notify_one();
+        }
+    }
+
+    /// Waits until the worker thread can read again, returning
+    /// `false` if it should stop
+    ///
+    /// The wait is done on `pool_changed`, so it is cut short when
+    /// the worker thread is stopped or the rate changes.
+    fn pace(&self, pace: &mut Pace, len: usize) -> bool {
+        pace.sent += len as u64;
+        let mut pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if pool.stopped {
+                break false;
+            } else if pool.command.is_some() {
+                break true;
+            }
+
+            let rate = self.throttle.load(Relaxed);
+            if rate != pace.rate {
+                *pace = Pace::new(self);
+            }
+
+            let Some(next) = pace.next_read(self.clock.now()) else {
+                break true;
+            };
+
+            pool = self
+                .pool_changed
+                .wait_timeout(pool, self.clock.timeout_until(next))
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// Makes the worker thread wait for `delay`, returning `false`
+    /// if it should stop
+    ///
+    /// Commands cut the wait short, so they can run.
+    fn worker_sleep(&self, delay: Duration) -> bool {
+        let deadline = self.clock.now().checked_add(delay);
+        let mut pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if pool.stopped {
+                break false;
+            } else if pool.command.is_some() {
+                break true;
+            }
+
+            pool = match deadline {
+                Some(deadline) => {
+                    if self.clock.now() >= deadline {
+                        break true;
+                    }
+                    let timeout = self.clock.timeout_until(deadline);
+                    (self.pool_changed.wait_timeout(pool, timeout))
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+                None => (self.pool_changed.wait(pool)).unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+
+    /// Changes the rate of the worker thread, waking it up
+    fn set_throttle(&self, rate: Option<Rate>) {
+        let _pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        self.throttle.store(rate.map_or(0, |rate| rate.0), Relaxed);
+        self.pool_changed.notify_all();
+    }
+
+    /// Hands a [`Command`] to the worker thread, returning `false` if
+    /// it has stopped
+    fn send_command(&self, command: Command) -> bool {
+        let mut pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        if pool.stopped {
+            return false;
+        }
+
+        pool.command = Some(command);
+        self.pool_changed.notify_all();
+        drop(pool);
+        // In case it is waiting on the source.
+        self.wake_worker();
+        true
+    }
+
+    /// Wether there is a [`Command`] for the worker thread to run
+    #[cfg(unix)]
+    fn has_command(&self) -> bool {
+        let pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        pool.command.is_some()
+    }
+
+    /// The [`Command`] that the worker thread should run, if any
+    fn take_command(&self) -> Option<Command> {
+        let mut pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        pool.command.take()
+    }
+
+    /// Makes the worker thread stop, once it needs a new buffer
+    fn stop_worker(&self) {
+        let mut pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        pool.stopped = true;
+        self.pool_changed.notify_all();
+    }
+
+    /// Interrupts the stage before this one, if there is one, so the
+    /// worker thread stops waiting on it
+    fn interrupt_upstream(&self) {
+        if let Some(upstream) = self.upstream.get()
+            && let Ok(seq) = upstream.send_interrupt(false, None, ErrorKind::Other)
+        {
+            self.upstream_interrupt.store(seq, Relaxed);
+        }
+    }
+
+    /// Marks the interrupt sent by [`Shared::interrupt_upstream`] as
+    /// observed, once the worker thread is done with the stage before
+    /// this one
+    fn withdraw_upstream_interrupt(&self) {
+        if let Some(upstream) = self.upstream.get() {
+            match self.upstream_interrupt.load(Relaxed) {
+                0 => {}
+                seq => _ = upstream.shared.observe(seq),
+            }
+        }
+    }
+
+    /// Wakes up the worker thread, so it can check if it should stop
+    fn wake_worker(&self) {
+        if let Some(mut cancel) = self.cancel.as_ref() {
+            _ = cancel.write(&[0]);
+        }
+
+        #[cfg(all(windows, feature = "windows-cancel"))]
+        {
+            _ = self.cancel_worker_read();
+        }
+    }
+
+    /// Cancels the read that the worker thread is blocked on, if any
+    #[cfg(all(windows, feature = "windows-cancel"))]
+    fn cancel_worker_read(&self) -> std::io::Result<()> {
+        use std::os::windows::io::AsRawHandle;
+
+        let Some(handle) = self.worker_thread.get() else {
+            return Err(Error::other("the worker thread's handle is not available"));
+        };
+
+        // SAFETY: The handle is owned by this struct, so it is valid.
+        if unsafe { windows_sys::Win32::System::IO::CancelSynchronousIo(handle.as_raw_handle()) }
+            == 0
+        {
+            let err = Error::last_os_error();
+            // No read was in progress, which is fine.
+            if err.raw_os_error() != Some(windows_sys::Win32::Foundation::ERROR_NOT_FOUND as i32) {
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Marks the interrupts up to `seq` as observed, returning
+    /// wether the reader should discard its data
+    fn observe(&self, seq: u64) -> bool {
+        let mut state = self.state();
+        let discard = self.discard_until.load(Relaxed) > state.interrupts_observed;
+        // The interrupt has been observed, so new ones shouldn't be coalesced
+        // into it.
+        state.interrupt_pending = false;
+        state.interrupts_observed = state.interrupts_observed.max(seq);
+        self.observed.store(state.interrupts_observed, Release);
+        drop(state);
+        self.state_changed.notify_all();
+
+        discard
+    }
+
+    /// Wether a discarding interrupt is yet to be observed
+    fn is_discarding(&self) -> bool {
+        let discard_until = self.discard_until.load(Relaxed);
+        discard_until > 0 && self.state().interrupts_observed < discard_until
+    }
+}
+
+/// The claims of an [`InterruptReader`], from
+/// [`InterruptReader::register_claim`]
+#[derive(Debug, Default)]
+struct Claims {
+    registered: Vec<ClaimId>,
+    /// The claim that read operations are made under, if any.
+    current: Option<ClaimId>,
+    /// Interrupts for the registered claims, other than `current`.
+    held: Vec<Interrupt>,
+    next: u64,
+}
+
+impl Claims {
+    /// Wether `interrupt` can be returned right now
+    ///
+    /// Interrupts for claims that aren't registered are returned like
+    /// untargeted ones.
+    fn delivers(&self, interrupt: &Interrupt) -> bool {
+        interrupt
+            .claim
+            .is_none_or(|claim| self.current == Some(claim) || !self.registered.contains(&claim))
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The sequence number of the last interrupt sent.
+    interrupts_sent: u64,
+    /// The sequence number of the last interrupt observed.
+    interrupts_observed: u64,
+    interrupt_pending: bool,
+    reader_dropped: bool,
+    closed: bool,
+    /// Wether the worker thread is done, for [`Worker`].
+    worker_finished: bool,
+    /// Tokens to cancel on the next interrupt.
+    #[cfg(feature = "tokio")]
+    tokens: Vec<tokio_util::sync::CancellationToken>,
+}
+
+/// The [`InterruptReader`]'s handle to the [`Shared`] state, which
+/// lets [`Interruptor`]s know when it has been dropped.
+#[derive(Debug)]
+struct ReaderHandle(Arc<Shared>);
+
+impl std::ops::Deref for ReaderHandle {
+    type Target = Shared;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Drop for ReaderHandle {
+    fn drop(&mut self) {
+        debug!("reader dropped");
+        self.state().reader_dropped = true;
+        self.state_changed.notify_all();
+        self.stop_worker();
+        self.wake_worker();
+        self.interrupt_upstream();
+    }
+}
+
+/// The worker thread of an [`InterruptReader`], which is waited for
+/// when dropped, according to its [`DropBehavior`]
+#[derive(Debug)]
+struct Worker<R> {
+    /// Only [`None`] once it was joined.
+    handle: Option<WorkerHandle<R>>,
+    shared: Arc<Shared>,
+    on_drop: DropBehavior,
+    on_detach: Option<OnDetach>,
+}
+
+impl<R> Worker<R> {
+    /// Waits for the worker thread, returning the source
+    fn join(mut self) -> std::thread::Result<R> {
+        self.handle.take().unwrap().join()
+    }
+
+    fn is_finished(&self) -> bool {
+        match self.handle.as_ref().unwrap() {
+            WorkerHandle::Thread(handle) => handle.is_finished(),
+            WorkerHandle::Spawned(..) => self.shared.state().worker_finished,
+        }
+    }
+
+    /// The thread of the worker, waiting for it to start if it was
+    /// spawned by a spawner
+    fn thread(&self) -> &std::thread::Thread {
+        match self.handle.as_ref().unwrap() {
+            WorkerHandle::Thread(handle) => handle.thread(),
+            WorkerHandle::Spawned(_, thread) => thread.wait(),
+        }
+    }
+}
+
+/// Where the source comes back from, once the worker is done
+#[derive(Debug)]
+enum WorkerHandle<R> {
+    /// A thread spawned by the [`Builder`].
+    Thread(JoinHandle<R>),
+    /// A worker spawned through [`pair_with_spawner`], with the
+    /// thread that it runs on, once it starts.
+    Spawned(
+        mpsc::Receiver<std::thread::Result<R>>,
+        Arc<std::sync::OnceLock<std::thread::Thread>>,
+    ),
+}
+
+impl<R> WorkerHandle<R> {
+    fn join(self) -> std::thread::Result<R> {
+        match self {
+            WorkerHandle::Thread(handle) => handle.join(),
+            WorkerHandle::Spawned(result_rx, _) => result_rx.recv().unwrap_or_else(|_| {
+                Err(Box::new(
+                    "the spawner dropped the worker without running it",
+                ))
+            }),
+        }
+    }
+
+    /// The thread of the worker, if it started
+    fn thread(&self) -> Option<&std::thread::Thread> {
+        match self {
+            WorkerHandle::Thread(handle) => Some(handle.thread()),
+            WorkerHandle::Spawned(_, thread) => thread.get(),
+        }
+    }
+}
+
+impl<R> Drop for Worker<R> {
+    fn drop(&mut self) {
+        let Some(handle) = self.handle.take() else {
+            return;
+        };
+
+        let deadline = match self.on_drop {
+            DropBehavior::Detach => return,
+            DropBehavior::Join => None,
+            DropBehavior::JoinTimeout(timeout) => self.shared.clock.now().checked_add(timeout),
+        };
+
+        // The reader is blocked on the worker thread until it is done.
+        self.shared.start_waiting();
+        let mut state = self.shared.state();
+        while !state.worker_finished {
+            state = match deadline {
+                Some(deadline) => {
+                    if self.shared.clock.now() >= deadline {
+                        debug!("worker thread detached");
+                        drop(state);
+                        self.shared.stop_waiting();
+                        if let Some(OnDetach(on_detach)) = &self.on_detach
+                            && let Some(thread) = handle.thread()
+                        {
+                            on_detach(thread);
+                        }
+                        return;
+                    }
+                    match self
+                        .shared
+                        .state_changed
+                        .wait_timeout(state, self.shared.clock.timeout_until(deadline))
+                    {
+                        Ok((state, _)) => state,
+                        Err(err) => err.into_inner().0,
+                    }
+                }
+                None => self
+                    .shared
+                    .state_changed
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+        drop(state);
+        self.shared.stop_waiting();
+
+        // A panic was already returned as an error.
+        _ = handle.join();
+    }
+}
+
+/// The hook passed to [`Builder::before_each_read`]
+#[derive(Clone)]
+struct BeforeRead {
+    /// The type of the source it takes.
+    reader: std::any::TypeId,
+    f: Arc<Mutex<BeforeReadFn>>,
+}
+
+type BeforeReadFn = dyn FnMut(&mut dyn Any) -> std::io::Result<ReadDirective> + Send;
+
+impl std::fmt::Debug for BeforeRead {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BeforeRead").finish_non_exhaustive()
+    }
+}
+
+/// The function passed to [`Builder::on_detach`]
+#[derive(Clone)]
+struct OnDetach(Arc<dyn Fn(&std::thread::Thread) + Send + Sync>);
+
+impl std::fmt::Debug for OnDetach {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("OnDetach").finish_non_exhaustive()
+    }
+}
+
+/// Lets the [`InterruptReader`] know if the worker thread panicked
+///
+/// Otherwise, since the [`Interruptor`]s also hold a [`Sender`], it
+/// would wait forever for the next [`Event`].
+///
+/// [`Sender`]: mpsc::Sender
+struct PanicForwarder {
+    event_tx: mpsc::Sender<Event>,
+    is_reading: Arc<AtomicBool>,
+    shared: Arc<Shared>,
+}
+
+impl PanicForwarder {
+    /// Runs the worker thread, forwarding its panic, if there is one
+    ///
+    /// The panic is resumed afterwards, so
+    /// [`InterruptReader::into_inner`] still returns its payload.
+    fn run<R>(self, worker: impl FnOnce() -> R) -> R {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(worker));
+        self.shared.state().worker_finished = true;
+        self.shared.state_changed.notify_all();
+
+        let payload = match result {
+            Ok(reader) => return reader,
+            Err(payload) => payload,
+        };
+
+        let msg = match payload.downcast_ref::<&'static str>() {
+            Some(msg) => msg.to_string(),
+            None => match payload.downcast_ref::<String>() {
+                Some(msg) => msg.clone(),
+                None => "Box<dyn Any>".to_string(),
+            },
+        };
+        debug!(msg, "worker thread panicked");
+
+        self.is_reading.store(false, Relaxed);
+        let err = Error::other(format!("the source of the InterruptReader panicked: {msg}"));
+        _ = self.shared.panic_message.set(msg);
+        _ = self.event_tx.send(Event::Err(err));
+        _ = self.event_tx.send(Event::Close);
+        self.shared.signal_ready();
+
+        std::panic::resume_unwind(payload)
+    }
+}
+
+#[derive(Debug)]
+struct Interrupt {
+    seq: u64,
+    /// Only interrupt if this wait is still going on.
+    if_waiting: Option<u64>,
+    reason: Option<Arc<str>>,
+    /// The label of the [`Interruptor`] that sent it.
+    label: Option<Cow<'static, str>>,
+    /// The kind of the [`Error`] that is returned.
+    kind: ErrorKind,
+    /// The claim it is for, if it was sent through
+    /// [`Interruptor::interrupt_for`], in which case `seq` is 0.
+    claim: Option<ClaimId>,
+}
+
+impl Interrupt {
+    /// Wether this interrupt applies during the given wait
+    fn applies(&self, wait: Option<u64>, shared: &Shared) -> bool {
+        self.if_waiting
+            .is_none_or(|if_waiting| Some(if_waiting) == wait)
+            && (self.claim.is_some() || self.seq > shared.state().interrupts_observed)
+    }
+}
+
+/// How many buffers are shared between the reader and the worker.
+const BUFFERS: usize = 16;
+
+/// How many buffers are used once they are bigger than
+/// [`SMALL_BUFFER`].
+const BIG_BUFFERS: usize = 4;
+
+/// The biggest buffer size for which all [`BUFFERS`] are used.
+const SMALL_BUFFER: usize = 16 * 1024;
+
+/// The maximum size that the worker's buffer can grow to.
+const MAX_READ_SIZE: usize = 1024 * 1024;
+
+/// How long to wait for an [`Event`]
+#[derive(Debug, Clone, Copy)]
+enum Wait {
+    /// Until an event arrives.
+    Block,
+    /// Until an event arrives or the deadline is reached, if there
+    /// is one.
+    Until(Option<Instant>),
+    /// Only take events that have already arrived.
+    Never,
+}
+
+/// Why the worker thread stopped waiting for the source
+#[derive(Debug, Clone, Copy)]
+// Only pair_fd actually waits on the source.
+#[cfg_attr(not(unix), allow(dead_code))]
+enum Wake {
+    /// The source can be read from.
+    Readable,
+    /// A [`Command`] has to be run first.
+    Command,
+    /// The worker thread should stop.
+    Stop,
+}
+
+/// The buffers that the worker thread can read into
+#[derive(Debug)]
+struct Pool {
+    free: Vec<Vec<u8>>,
+    worker_waiting: bool,
+    /// Wether the worker thread should stop reading.
+    stopped: bool,
+    /// Something for the worker thread to do before its next read.
+    command: Option<Command>,
+    /// The chunks sent through [`Event::Latest`] that weren't
+    /// received or dropped yet, with their ids.
+    latest: VecDeque<(u64, Vec<u8>, usize, Instant)>,
+    next_latest: u64,
+}
+
+/// Where the chunks go, after [`InterruptReader::divert_to`]
+struct Diversion {
+    sink: Box<dyn Write + Send>,
+    /// How many bytes were written into the sink.
+    bytes: u64,
+}
+
+impl std::fmt::Debug for Diversion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Diversion")
+            .field("bytes", &self.bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The pipe behind [`InterruptReader::readiness_fd`]
+#[cfg(unix)]
+#[derive(Debug)]
+struct ReadyPipe {
+    reader: PipeReader,
+    writer: PipeWriter,
+    /// Wether a byte was written since the last reset.
+    signaled: AtomicBool,
+}
+
+/// The configuration of [`Builder::stall_watchdog`]
+#[derive(Clone)]
+struct StallWatchdog {
+    threshold: Duration,
+    on_stall: Arc<dyn Fn(StallInfo) + Send + Sync>,
+}
+
+impl std::fmt::Debug for StallWatchdog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StallWatchdog")
+            .field("threshold", &self.threshold)
+            .finish_non_exhaustive()
+    }
+}
+
+/// What the worker thread tells the stall watchdog
+#[derive(Debug)]
+struct Watchdog {
+    start: Instant,
+    /// The nanoseconds from `start` until bytes were last read.
+    last_data: AtomicU64,
+    /// How many bytes were read from the source.
+    total: AtomicU64,
+    /// Wether the watchdog is waiting for data to resume.
+    stalled: AtomicBool,
+}
+
+/// Calls `on_stall` whenever the source goes quiet for `threshold`,
+/// until the reader is dropped or closed
+fn watch_stalls(shared: &Shared, threshold: Duration, on_stall: &(dyn Fn(StallInfo) + Sync)) {
+    let watchdog = shared.watchdog.as_ref().unwrap();
+    // The total when on_stall was last called.
+    let mut reported = None;
+
+    let mut state = shared.state();
+    loop {
+        if state.reader_dropped || state.closed {
+            break;
+        }
+
+        let total = watchdog.total.load(SeqCst);
+        let last_data = Duration::from_nanos(watchdog.last_data.load(Relaxed));
+        let now = shared.clock.now();
+        let silence = (now.saturating_duration_since(watchdog.start)).saturating_sub(last_data);
+
+        state = if reported == Some(total) {
+            // Woken up by Shared::produced.
+            shared
+                .state_changed
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner)
+        } else if silence >= threshold {
+            reported = Some(total);
+            watchdog.stalled.store(true, SeqCst);
+            drop(state);
+            on_stall(StallInfo { silence, total_bytes: total });
+            shared.state()
+        } else {
+            let timeout = shared.clock.timeout_until(now + (threshold - silence));
+            match shared.state_changed.wait_timeout(state, timeout) {
+                Ok((state, _)) => state,
+                Err(err) => err.into_inner().0,
+            }
+        };
+    }
+}
+
+/// An operation on the source, run by the worker thread
+///
+/// This is how methods that need more than [`Read`] from the source,
+/// like [`Seek::seek`], get to it, replying with an [`Event::Reply`]
+/// with the same `id`.
+struct Command {
+    id: u64,
+    f: Box<CommandFn>,
+}
+
+type CommandFn = dyn FnOnce(&mut dyn Any) -> Box<dyn Any + Send> + Send;
+
+impl std::fmt::Debug for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Command")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// How much the worker thread has read at the current rate
+#[derive(Debug)]
+struct Pace {
+    /// The bytes per second, or 0 if reading isn't throttled.
+    rate: u64,
+    start: Instant,
+    sent: u64,
+}
+
+impl Pace {
+    fn new(shared: &Shared) -> Self {
+        Self {
+            rate: shared.throttle.load(Relaxed),
+            start: shared.clock.now(),
+            sent: 0,
+        }
+    }
+
+    /// How much to read at once, about 50ms worth of data
+    fn read_size(&self, len: usize) -> usize {
+        match self.rate {
+            0 => len,
+            rate => (rate / 20).clamp(1, len as u64) as usize,
+        }
+    }
+
+    /// When the next read can happen, if it has to wait at all
+    fn next_read(&self, now: Instant) -> Option<Instant> {
+        if self.rate == 0 {
+            return None;
+        }
+
+        let next = self.start + Duration::from_secs_f64(self.sent as f64 / self.rate as f64);
+        (next > now).then_some(next)
+    }
+}
+
+/// How the worker thread's buffer adapts to the reads
+#[derive(Debug)]
+struct Adaptive {
+    min: usize,
+    max: usize,
+    /// How many reads in a row filled the buffer.
+    full: u32,
+    /// How many reads in a row only filled a small part of it.
+    small: u32,
+}
+
+impl Adaptive {
+    /// How many full reads it takes for the buffer to grow.
+    const GROW_AFTER: u32 = 2;
+    /// How many small reads it takes for the buffer to shrink.
+    const SHRINK_AFTER: u32 = 8;
+
+    fn new(min: usize, max: usize) -> Self {
+        Self { min, max, full: 0, small: 0 }
+    }
+
+    /// The new size of the buffer, after reading `read` bytes into
+    /// `len` of its `size` bytes
+    fn update(&mut self, size: usize, len: usize, read: usize) -> usize {
+        if read == len && len == size {
+            self.full += 1;
+            self.small = 0;
+        } else if read < size / 4 {
+            self.small += 1;
+            self.full = 0;
+        } else {
+            (self.full, self.small) = (0, 0);
+        }
+
+        if self.full >= Self::GROW_AFTER {
+            self.full = 0;
+            size.saturating_mul(2).min(self.max)
+        } else if self.small >= Self::SHRINK_AFTER {
+            self.small = 0;
+            (size / 2).max(self.min)
+        } else {
+            size
+        }
+    }
+}
+
+/// A buffer received from the worker thread
+#[derive(Debug)]
+struct Buffer {
+    buf: Vec<u8>,
+    pos: usize,
+    /// How many bytes were actually read into `buf`.
+    len: usize,
+    /// When the worker thread read it from the source.
+    arrived: Instant,
+}
+
+impl Buffer {
+    fn remaining(&self) -> &[u8] {
+        &self.buf[self.pos..self.len]
+    }
+}
+
+#[derive(Debug)]
+enum Event {
+    /// A chunk, how many bytes were read into it, and when they
+    /// arrived from the source.
+    Buf(Vec<u8>, usize, Instant),
+    Err(std::io::Error),
+    Interrupt(Interrupt),
+    Close,
+    Reply(u64, Box<dyn Any + Send>),
+    /// A chunk kept in [`Pool::latest`], with this id.
+    Latest(u64),
+    /// Sent by the relay threads of a [`child::ChildOutputReader`].
+    Relayed(child::Relayed),
+}
+
+/// Copies the contents of an [`InterruptReader`] into a [`Write`]r
+///
+/// This works like [`std::io::copy`], but an interrupt is treated as
+/// a normal stop condition, rather than a failure, and the returned
+/// [`CopyOutcome`] tells you how many bytes made it into the
+/// `writer`, which is useful for resuming the copy later. Any other
+/// error is propagated as usual.
+///
+/// Internally, this uses [`fill_buf`] and [`consume`], so each byte
+/// is only copied once, straight from the internal buffer.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::{io::Write, time::Duration};
+///
+/// use interrupt_read::{CopyOutcome, copy_interruptible, pair};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+/// let (mut reader, interruptor) = pair(pipe_reader);
+///
+/// pipe_writer.write_all(b"hello")?;
+/// let join_handle = std::thread::spawn(move || {
+///     let mut out = Vec::new();
+///     let outcome = copy_interruptible(&mut reader, &mut out);
+///     (outcome, out, reader)
+/// });
+///
+/// std::thread::sleep(Duration::from_millis(50));
+/// interruptor.interrupt()?;
+///
+/// let (outcome, mut out, mut reader) = join_handle.join().unwrap();
+/// assert_eq!(outcome?, CopyOutcome { bytes_copied: 5, interrupted: true });
+/// assert_eq!(out, b"hello");
+///
+/// // The stream resumes cleanly.
+/// pipe_writer.write_all(b" world")?;
+/// drop(pipe_writer);
+///
+/// let outcome = copy_interruptible(&mut reader, &mut out)?;
+/// assert_eq!(outcome, CopyOutcome { bytes_copied: 6, interrupted: false });
+/// assert_eq!(out, b"hello world");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`fill_buf`]: BufRead::fill_buf
+/// [`consume`]: BufRead::consume
+pub fn copy_interruptible<R: Read, W: Write + ?Sized>(
+    reader: &mut InterruptReader<R>,
+    writer: &mut W,
+) -> std::io::Result<CopyOutcome> {
+    let mut bytes_copied = 0;
+
+    loop {
+        let buf = match reader.fill_buf() {
+            Ok([]) => break Ok(CopyOutcome { bytes_copied, interrupted: false }),
+            Ok(buf) => buf,
+            Err(err) if is_interrupt(&err) => {
+                break Ok(CopyOutcome { bytes_copied, interrupted: true });
+            }
+            Err(err) => break Err(err),
+        };
+
+        writer.write_all(buf)?;
+        let len = buf.len();
+        reader.consume(len);
+        bytes_copied += len as u64;
+    }
+}
+
+/// The outcome of a call to [`copy_interruptible`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CopyOutcome {
+    /// How many bytes were written into the [`Write`]r.
+    pub bytes_copied: u64,
+    /// Wether the copy stopped because of an interrupt, rather than
+    /// because the end of the stream was reached.
+    pub interrupted: bool,
+}
+
+/// The outcome of a call to [`InterruptReader::undivert`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DivertStats {
+    /// How many bytes were written into the sink.
+    pub bytes_diverted: u64,
+}
+
+/// How often an [`InterruptReader`] checks for interrupts
+///
+/// When the `InterruptReader` has to fetch a new chunk of data from
+/// the worker thread, it always checks for interrupts first. But
+/// while it is serving data that is already buffered, checking for
+/// interrupts has a small cost on every call, which can add up if
+/// the consumer makes lots of tiny reads (e.g. a tokenizer reading
+/// one byte at a time).
+///
+/// This policy lets you trade off how promptly interrupts are
+/// delivered against that overhead. Under every policy, interrupts
+/// are eventually delivered, at the latest once the current chunk
+/// (at most [`Builder::buffer_size`] bytes) has been consumed.
+///
+/// Persistent interrupts, sent through
+/// [`Interruptor::interrupt_persistent`], are checked for on every
+/// call regardless, since that is just an atomic load.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptCheck {
+    /// Check on every read operation.
+    ///
+    /// Interrupts are delivered as soon as possible, even in the
+    /// middle of a chunk. This is the default.
+    #[default]
+    EveryCall,
+    /// Only check when fetching a new chunk from the worker thread.
+    ///
+    /// This removes the per call overhead entirely, but the rest of
+    /// the current chunk is served before an interrupt is delivered.
+    PerChunk,
+    /// Check once at least this many bytes have been consumed since
+    /// the last check, as well as when fetching a new chunk.
+    EveryNBytes(usize),
+}
+
+/// A rate at which to read from a source, for [`Builder::throttle`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate(u64);
+
+impl Rate {
+    /// A `Rate` of `bytes` per second
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is `0`.
+    pub fn bytes_per_second(bytes: u64) -> Self {
+        assert!(bytes > 0, "rate can't be 0");
+        Self(bytes)
+    }
+
+    /// The bytes per second of this `Rate`
+    pub fn as_bytes_per_second(&self) -> u64 {
+        self.0
+    }
+}
+
+/// What the worker thread does when the reader falls behind, for
+/// [`Builder::overflow`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Stop reading until the reader catches up.
+    ///
+    /// This is the default.
+    #[default]
+    Backpressure,
+    /// Keep reading, dropping the oldest chunks that weren't
+    /// received yet once there are more than `max_buffered_chunks`
+    /// of them.
+    DropOldest {
+        /// How many chunks are kept.
+        max_buffered_chunks: usize,
+    },
+}
+
+/// What the worker thread does next, returned by the hook of
+/// [`Builder::before_each_read`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadDirective {
+    /// Read from the source, as usual.
+    Continue,
+    /// Don't read this time, and wait for this long before calling
+    /// the hook again.
+    ///
+    /// The wait is cut short if the [`InterruptReader`] is dropped,
+    /// and interrupts are delivered to it all the same in the
+    /// meantime.
+    SkipAndWait(Duration),
+    /// Stop the worker thread, after which the [`InterruptReader`]
+    /// reaches the end of the stream, once it runs out of data.
+    Stop,
+}
+
+/// What happens to the worker thread when an [`InterruptReader`] is
+/// dropped, for [`Builder::on_drop`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DropBehavior {
+    /// Let the worker thread finish on its own.
+    ///
+    /// This is the default.
+    #[default]
+    Detach,
+    /// Wait for the worker thread to finish.
+    Join,
+    /// Wait for the worker thread to finish, for at most this long,
+    /// then let it finish on its own.
+    ///
+    /// The time is counted by the [`Builder::clock`].
+    JoinTimeout(Duration),
+}
+
+/// What ends the stream for [`Read::read_to_end`] and
+/// [`Read::read_to_string`], for [`Builder::eof`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Eof {
+    /// The first `Ok(0)` from the source.
+    ///
+    /// This is the default.
+    #[default]
+    Empty,
+    /// Only [`Interruptor::close`], or the worker thread stopping.
+    ///
+    /// Every `Ok(0)` from the source is skipped.
+    Final,
+}
+
+/// A silence of the source, passed to the callback of
+/// [`Builder::stall_watchdog`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StallInfo {
+    silence: Duration,
+    total_bytes: u64,
+}
+
+impl StallInfo {
+    /// How long it has been since bytes were last read from the
+    /// source, or since the pair was created
+    pub fn silence(&self) -> Duration {
+        self.silence
+    }
+
+    /// How many bytes were read from the source so far
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+}
+
+/// The outcome of a call to [`InterruptReader::read_until_pattern`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UntilOutcome {
+    /// The pattern was found, and is at the end of the output.
+    Found,
+    /// The end of the stream was reached before finding the pattern.
+    Eof,
+    /// An interrupt arrived before finding the pattern.
+    Interrupted,
+}
+
+/// The outcome of a call to
+/// [`InterruptReader::read_until_interruptible`]
+///
+/// At most one of `terminated`, `interrupted` and `eof` is `true`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct UntilProgress {
+    /// How many bytes were appended to the output.
+    pub appended: usize,
+    /// Wether the delimiter was found, and is at the end of the
+    /// output.
+    pub terminated: bool,
+    /// Wether an interrupt arrived before finding the delimiter.
+    pub interrupted: bool,
+    /// Wether the end of the stream was reached before finding the
+    /// delimiter.
+    pub eof: bool,
+}
+
+/// The outcome of a call to
+/// [`InterruptReader::skip_until_interruptible`]
+///
+/// At most one of `terminated`, `interrupted` and `eof` is `true`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SkipProgress {
+    /// How many bytes were skipped, including the delimiter.
+    pub skipped: usize,
+    /// Wether the delimiter was found, and was skipped.
+    pub terminated: bool,
+    /// Wether an interrupt arrived before finding the delimiter.
+    pub interrupted: bool,
+    /// Wether the end of the stream was reached before finding the
+    /// delimiter.
+    pub eof: bool,
+}
+
+/// An iterator over the frames of an [`InterruptReader`]
+///
+/// This is acquired through [`InterruptReader::delimited`], check out
+/// its documentation for more information.
+#[derive(Debug)]
+pub struct Delimited<'a, R> {
+    reader: &'a mut InterruptReader<R>,
+    delim: Vec<u8>,
+    /// The frame read so far.
+    frame: Vec<u8>,
+    done: bool,
+}
+
+impl<R: Read> Iterator for Delimited<'_, R> {
+    type Item = FrameEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        // Since a partial frame was already scanned, the search only
+        // looks back far enough to find a split delimiter.
+        match self
+            .reader
+            .scan_until_pattern(&self.delim, &mut self.frame, 0)
+        {
+            Ok(UntilOutcome::Found) => {
+                let mut frame = std::mem::take(&mut self.frame);
+                frame.truncate(frame.len() - self.delim.len());
+                Some(FrameEvent::Frame(frame))
+            }
+            Ok(UntilOutcome::Eof) => {
+                self.done = true;
+                let frame = std::mem::take(&mut self.frame);
+                (!frame.is_empty()).then_some(FrameEvent::Partial(frame))
+            }
+            Ok(UntilOutcome::Interrupted) => Some(FrameEvent::Interrupted),
+            Err(err) => Some(FrameEvent::Err(err)),
+        }
+    }
+}
+
+/// An iterator over the lines of an [`InterruptReader`], with a
+/// timeout
+///
+/// This is acquired through [`InterruptReader::timed_lines`], check
+/// out its documentation for more information.
+#[derive(Debug)]
+pub struct TimedLines<'a, R> {
+    reader: &'a mut InterruptReader<R>,
+    timeout: Duration,
+    done: bool,
+}
+
+impl<R: Read> Iterator for TimedLines<'_, R> {
+    type Item = std::io::Result<LinePoll>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.reader.next_line_timeout(self.timeout) {
+            Ok(LinePoll::Eof) => {
+                self.done = true;
+                None
+            }
+            result => Some(result),
+        }
+    }
+}
+
+/// An iterator over the characters of an [`InterruptReader`]
+///
+/// This is acquired through [`InterruptReader::chars`], check out
+/// its documentation for more information.
+#[derive(Debug)]
+pub struct Chars<'a, R> {
+    reader: &'a mut InterruptReader<R>,
+}
+
+impl<R: Read> Iterator for Chars<'_, R> {
+    type Item = std::io::Result<char>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.read_char().transpose()
+    }
+}
+
+/// A fast iterator over the bytes of an [`InterruptReader`]
+///
+/// This is acquired through [`InterruptReader::bytes_fast`], check
+/// out its documentation for more information.
+#[derive(Debug)]
+pub struct BytesFast<'a, R> {
+    reader: &'a mut InterruptReader<R>,
+}
+
+impl<R: Read> Iterator for BytesFast<'_, R> {
+    type Item = std::io::Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(&byte) = self.reader.buffer().first()
+            && self.reader.shared.persistent.load(Relaxed) == 0
+        {
+            self.reader.consume(1);
+            return Some(Ok(byte));
+        }
+
+        match self.reader.fill_buf() {
+            Ok([]) => None,
+            Ok(buf) => {
+                let byte = buf[0];
+                self.reader.consume(1);
+                Some(Ok(byte))
+            }
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// A chunk read by the worker thread of an [`InterruptReader`]
+///
+/// This is acquired through [`InterruptReader::next_chunk`], and it
+/// dereferences to the bytes that were read. Dropping it hands the
+/// buffer back to the worker thread.
+pub struct Chunk {
+    buf: Vec<u8>,
+    pos: usize,
+    len: usize,
+    arrived: Instant,
+    shared: Arc<Shared>,
+}
+
+impl Chunk {
+    /// Turns this `Chunk` into a [`Vec`], without copying it
+    ///
+    /// Since the buffer is no longer handed back, a new one is
+    /// allocated for the worker thread in its place.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Cursor;
+    ///
+    /// use interrupt_read::pair;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let (mut reader, _interruptor) = pair(Cursor::new(b"hello world".to_vec()));
+    ///
+    /// let chunk = reader.next_chunk()?.unwrap();
+    /// assert_eq!(chunk.into_vec(), b"hello world");
+    /// assert!(reader.next_chunk()?.is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_vec(mut self) -> Vec<u8> {
+        let replacement = vec![0; self.buf.len()];
+        let mut buf = std::mem::replace(&mut self.buf, replacement);
+        buf.truncate(self.len);
+        buf.drain(..self.pos);
+        buf
+    }
+
+    /// When this `Chunk` arrived from the source
+    ///
+    /// Check out [`InterruptReader::last_chunk_arrival`] for more
+    /// information.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{io::Cursor, sync::Arc, time::Duration};
+    ///
+    /// use interrupt_read::{Builder, clock::Clock, testing::MockClock};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let clock = Arc::new(MockClock::new());
+    /// let source = Cursor::new(b"hello".to_vec());
+    /// let (mut reader, _interruptor) = Builder::new().clock(clock.clone()).pair(source);
+    ///
+    /// let chunk = reader.next_chunk()?.unwrap();
+    /// clock.advance(Duration::from_millis(30));
+    /// // How long it took to get around to it.
+    /// assert_eq!(clock.now() - chunk.arrived_at(), Duration::from_millis(30));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn arrived_at(&self) -> Instant {
+        self.arrived
+    }
+}
+
+impl std::ops::Deref for Chunk {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.buf[self.pos..self.len]
+    }
+}
+
+impl AsRef<[u8]> for Chunk {
+    fn as_ref(&self) -> &[u8] {
+        self
     }
 }
 
-impl<R: Read> Read for InterruptReader<R> {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        if let Some(cursor) = self.cursor.as_mut() {
-            deal_with_interrupt(&self.event_rx)?;
+impl std::fmt::Debug for Chunk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Chunk").field(&&**self).finish()
+    }
+}
 
-            match cursor.read(buf) {
-                Ok(0) => {
-                    let buffer = self.cursor.take().unwrap().into_inner().into_inner();
-                    match self.buffer_tx.send(buffer) {
-                        Ok(()) => self.read(buf),
-                        // Now we handle that.
-                        Err(_) => Ok(0),
-                    }
-                }
-                Ok(num_bytes) => Ok(num_bytes),
-                Err(_) => unreachable!("Afaik, this shouldn't happen if T is Vec<u8>"),
-            }
-        } else {
-            match self.event_rx.recv() {
-                Ok(Event::Buf(buffer, len)) => {
-                    self.cursor = Some(Cursor::new(buffer).take(len as u64));
-                    if len == 0 { Ok(0) } else { self.read(buf) }
-                }
-                Ok(Event::Err(err)) => Err(err),
-                Ok(Event::Interrupt) => Err(interrupt_error()),
-                Err(_) => Ok(0),
-            }
-        }
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        self.shared
+            .give_buffer(std::mem::take(&mut self.buf), self.len);
     }
 }
 
-impl<R: Read> BufRead for InterruptReader<R> {
-    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
-        if let Some(cursor) = self.cursor.as_mut() {
-            deal_with_interrupt(&self.event_rx)?;
+/// A reader over a limited amount of bytes of an [`InterruptReader`]
+///
+/// This is acquired through [`InterruptReader::limit`], check out its
+/// documentation for more information.
+#[derive(Debug)]
+pub struct Limited<'a, R> {
+    reader: &'a mut InterruptReader<R>,
+    remaining: u64,
+    /// How many bytes were consumed, to be taken from the parent.
+    consumed: u64,
+    /// The `remaining` of the `Limited` that this one came from.
+    parent: Option<&'a mut u64>,
+}
 
-            let (addr, len) = {
-                let buf = cursor.fill_buf()?;
-                ((buf as *const [u8]).addr(), buf.len())
-            };
+impl<R: Read> Limited<'_, R> {
+    /// How many bytes can still be read
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
 
-            if len == 0 {
-                let buffer = self.cursor.take().unwrap().into_inner().into_inner();
-                match self.buffer_tx.send(buffer) {
-                    Ok(()) => self.fill_buf(),
-                    Err(_) => Ok(&[]),
-                }
-            } else {
-                let buffer = self.cursor.as_ref().unwrap().get_ref().get_ref();
-                let buf_addr = (buffer.as_slice() as *const [u8]).addr();
+    /// Returns a reader over at most the next `n` bytes of this one
+    ///
+    /// Once it is dropped, what it read is taken from this limit.
+    pub fn limit(&mut self, n: u64) -> Limited<'_, R> {
+        Limited {
+            reader: self.reader,
+            remaining: n.min(self.remaining),
+            consumed: 0,
+            parent: Some(&mut self.remaining),
+        }
+    }
 
-                // First time the borrow checker actually forced me to do something
-                // inconvenient, instead of the safe alternative.
-                Ok(&buffer[addr - buf_addr..(addr - buf_addr) + len])
-            }
-        } else {
-            match self.event_rx.recv() {
-                Ok(Event::Buf(buffer, len)) => {
-                    self.cursor = Some(Cursor::new(buffer).take(len as u64));
-                    if len == 0 { Ok(&[]) } else { self.fill_buf() }
-                }
-                Ok(Event::Err(err)) => Err(err),
-                Ok(Event::Interrupt) => Err(interrupt_error()),
-                Err(_) => Ok(&[]),
-            }
+    /// A reference to the [`InterruptReader`]
+    pub fn reader(&self) -> &InterruptReader<R> {
+        self.reader
+    }
+
+    fn limited(&self, len: usize) -> usize {
+        len.min(self.remaining.try_into().unwrap_or(usize::MAX))
+    }
+}
+
+impl<R: Read> Read for Limited<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.remaining == 0 {
+            return Ok(0);
         }
+
+        let len = self.limited(buf.len());
+        let len = self.reader.read(&mut buf[..len])?;
+        self.remaining -= len as u64;
+        self.consumed += len as u64;
+        Ok(len)
     }
+}
 
-    fn consume(&mut self, amount: usize) {
-        if let Some(cursor) = self.cursor.as_mut() {
-            cursor.consume(amount);
+impl<R: Read> BufRead for Limited<'_, R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if self.remaining == 0 {
+            return Ok(&[]);
         }
+
+        let len = self.limited(usize::MAX);
+        let buf = self.reader.fill_buf()?;
+        Ok(&buf[..buf.len().min(len)])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        let amount = self.limited(amount);
+        self.reader.consume(amount);
+        self.remaining -= amount as u64;
+        self.consumed += amount as u64;
     }
 }
 
-/// An interruptor for an [`InterruptReader`].
-///
-/// This struct serves the purpose of interrupting any of the [`Read`]
-/// or [`BufRead`] functions being performend on the `InterruptReader`
-///
-/// If it is dropped, the `InterruptReader` will no longer be able to
-/// be interrupted.
-#[derive(Debug, Clone)]
-pub struct Interruptor(mpsc::Sender<Event>);
+impl<R> Drop for Limited<'_, R> {
+    fn drop(&mut self) {
+        if let Some(parent) = self.parent.take() {
+            *parent -= self.consumed;
+        }
+    }
+}
 
-impl Interruptor {
-    /// Interrupts the [`InterruptReader`]
-    ///
-    /// This will send an interrupt event to the reader, which makes
-    /// the next `read` operation return [`Err`], with an
-    /// [`ErrorKind::Other`] with a payload of [`InterruptReceived`].
+/// An item of a [`Delimited`] iterator
+#[derive(Debug)]
+pub enum FrameEvent {
+    /// A complete frame, without the delimiter.
+    Frame(Vec<u8>),
+    /// What came after the last delimiter, at the end of the stream.
+    Partial(Vec<u8>),
+    /// An interrupt arrived in the middle of a frame.
     ///
-    /// You can check if an [`std::io::Error`] is of this type by
-    /// calling the [`is_interrupt`] function.
+    /// What was read of the frame so far is kept, and will be part of
+    /// the next one.
+    Interrupted,
+    /// The reader returned an error.
     ///
-    /// Subsequent `read` operations proceed as normal.
+    /// Like with interrupts, what was read of the frame is kept.
+    Err(std::io::Error),
+}
+
+/// The outcome of a call to [`InterruptReader::poll_read_ready`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Readiness {
+    /// The next read operation won't block.
+    Data,
+    /// An interrupt arrived, and the next read operation will return
+    /// it.
+    Interrupted,
+    /// Nothing arrived before the timeout.
+    TimedOut,
+}
+
+/// Why [`InterruptReader::for_each_line`] returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopEnd {
+    /// The end of the stream was reached.
+    Eof,
+    /// An interrupt arrived.
+    Interrupted,
+    /// The callback returned [`ControlFlow::Break`].
+    Break,
+}
+
+/// The outcome of a call to [`InterruptReader::next_line_timeout`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinePoll {
+    /// A complete line, without the newline at the end.
+    Line(String),
+    /// The line wasn't complete before the timeout.
     ///
-    /// [`ErrorKind::Other`]: std::io::ErrorKind::Other
-    pub fn interrupt(&self) -> Result<(), InterruptSendError> {
-        self.0
-            .send(Event::Interrupt)
-            .map_err(|_| InterruptSendError)
-    }
+    /// What was read of it is kept for the next call.
+    TimedOut,
+    /// The end of the stream was reached.
+    Eof,
 }
 
-/// An error occurred while calling [`Interruptor::interrupt`].
+/// How the frames read by [`InterruptReader::read_message`] are
+/// prefixed with their length
 ///
-/// This means that the receiving [`InterruptReader`] has been
-/// dropped.
-#[derive(Debug, Clone, Copy)]
-pub struct InterruptSendError;
+/// By default, the length is big-endian, and frames of any length
+/// are accepted. For sources that aren't trusted, a limit should be
+/// set through [`LengthPrefix::max_frame_size`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthPrefix {
+    size: usize,
+    little_endian: bool,
+    max_frame_size: usize,
+}
 
-impl std::fmt::Display for InterruptSendError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str("InterruptReader has been dropped")
+impl LengthPrefix {
+    /// A `LengthPrefix` that is `size` bytes long
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` isn't `1`, `2`, `4` or `8`.
+    pub fn new(size: usize) -> Self {
+        assert!(
+            matches!(size, 1 | 2 | 4 | 8),
+            "length prefix must be 1, 2, 4 or 8 bytes long"
+        );
+        Self {
+            size,
+            little_endian: false,
+            max_frame_size: usize::MAX,
+        }
     }
-}
 
-impl std::error::Error for InterruptSendError {}
+    /// Makes the length little-endian
+    pub fn little_endian(mut self) -> Self {
+        self.little_endian = true;
+        self
+    }
 
-/// Indicates that an [`Interruptor`] has called
-/// [`Interruptor::interrupt`], causing a read operation to be
-/// interrupted.
-#[derive(Debug, Clone, Copy)]
-pub struct InterruptReceived;
+    /// Makes the length big-endian, which is the default
+    pub fn big_endian(mut self) -> Self {
+        self.little_endian = false;
+        self
+    }
 
-impl std::fmt::Display for InterruptReceived {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str("Interruptor has interrupted")
+    /// Sets the maximum length of a frame, not counting the prefix
+    ///
+    /// Frames over this size result in an [`ErrorKind::InvalidData`]
+    /// error, instead of being read.
+    pub fn max_frame_size(mut self, max: usize) -> Self {
+        self.max_frame_size = max;
+        self
     }
 }
 
-impl std::error::Error for InterruptReceived {}
+/// The outcome of a call to [`InterruptReader::read_message`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageEvent {
+    /// A complete frame, without the length prefix.
+    Frame(Vec<u8>),
+    /// An interrupt arrived.
+    ///
+    /// What was read of the current frame is kept for the next call.
+    Interrupted,
+    /// The end of the stream was reached, between frames.
+    Eof,
+}
 
+/// A message sent by the thread of
+/// [`InterruptReader::into_lines_receiver`]
 #[derive(Debug)]
-enum Event {
-    Buf(Vec<u8>, usize),
-    Err(std::io::Error),
-    Interrupt,
+pub enum LineMsg {
+    /// A complete line, without the newline at the end.
+    ///
+    /// The last line is sent even if the stream ended before its
+    /// newline.
+    Line(String),
+    /// The source returned an error.
+    Err(Error),
+    /// An interrupt arrived.
+    ///
+    /// What was read of the current line is kept, and reading goes
+    /// on.
+    Interrupted,
+}
+
+impl LineMsg {
+    /// The line, if this is a [`LineMsg::Line`]
+    pub fn line(&self) -> Option<&str> {
+        match self {
+            LineMsg::Line(line) => Some(line),
+            _ => None,
+        }
+    }
+
+    /// Turns this into the line, if this is a [`LineMsg::Line`]
+    pub fn into_line(self) -> Option<String> {
+        match self {
+            LineMsg::Line(line) => Some(line),
+            _ => None,
+        }
+    }
+}
+
+/// The outcome of a call to [`InterruptReader::replace_inner`]
+#[derive(Debug)]
+pub struct ReplaceOutcome<R> {
+    /// The reader that was replaced.
+    pub old: R,
+    /// How many bytes read from the old reader are yet to be
+    /// delivered, ahead of those of the new one.
+    pub old_bytes_pending: u64,
+}
+
+/// The outcome of a call to [`InterruptReader::read_line_deadline`]
+/// or [`InterruptReader::read_until_deadline`]
+///
+/// Each variant holds the amount of bytes that were appended to the
+/// output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadlineOutcome {
+    /// The delimiter was found, and is at the end of the output.
+    Complete(usize),
+    /// The deadline was reached before finding the delimiter.
+    TimedOut(usize),
+    /// The end of the stream was reached before finding the
+    /// delimiter.
+    Eof(usize),
+}
+
+/// The outcome of a call to [`InterruptReader::read_line_limited`]
+/// or [`InterruptReader::read_until_limited`]
+///
+/// Each variant holds the amount of bytes that were appended to the
+/// output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineOutcome {
+    /// The delimiter was found, and is at the end of the output.
+    Complete(usize),
+    /// The limit was reached before finding the delimiter.
+    LimitReached(usize),
+    /// The end of the stream was reached before finding the
+    /// delimiter.
+    Eof(usize),
 }
 
 /// Wether the error in question originated from an [`Interruptor`]
 /// calling [`Interruptor::interrupt`].
 ///
-/// This just checks if the error is of type [`InterruptReceived`].
+/// This checks if the error is of type [`InterruptReceived`], or
+/// was caused by one. That is, the chain of [`source`]s is followed,
+/// as well as the payloads of any [`std::io::Error`]s in it, so the
+/// interrupt is still found if the error was wrapped by your own
+/// code. In order to avoid getting stuck in a cyclical chain, at
+/// most 16 errors are checked.
 ///
 /// # Examples
 ///
@@ -414,20 +11652,383 @@ enum Event {
 ///     }
 /// }
 /// ```
+///
+/// Wrapped interrupts are also found:
+///
+/// ```
+/// use std::io::{Error, ErrorKind};
+///
+/// use interrupt_read::{InterruptReceived, is_interrupt};
+///
+/// // An error type that hides the interrupt as its `source`.
+/// #[derive(Debug)]
+/// struct Context(Error);
+///
+/// impl std::fmt::Display for Context {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         f.write_str("while talking to the child")
+///     }
+/// }
+///
+/// impl std::error::Error for Context {
+///     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+///         Some(&self.0)
+///     }
+/// }
+///
+/// let err = Error::other(InterruptReceived::new());
+/// assert!(is_interrupt(&err));
+///
+/// // Interrupts with a reason are found as well.
+/// let err = Error::other(InterruptReceived::with_reason("shutting down"));
+/// assert!(is_interrupt(&err));
+///
+/// // Two levels deep.
+/// let err = Error::new(ErrorKind::Interrupted, err);
+/// assert!(is_interrupt(&err));
+///
+/// // Three levels deep, behind a different payload type.
+/// let err = Error::other(Context(err));
+/// assert!(is_interrupt(&err));
+///
+/// assert!(!is_interrupt(&Error::other(Context(Error::other(
+///     "not it"
+/// )))));
+/// ```
+///
+/// [`source`]: std::error::Error::source
 pub fn is_interrupt(err: &Error) -> bool {
-    err.get_ref()
-        .is_some_and(|err| err.is::<InterruptReceived>())
+    find_interrupt(err).is_some()
+}
+
+/// Extension methods for checking [`std::io::Error`]s for interrupts
+///
+/// This trait is sealed, it is only implemented for
+/// [`std::io::Error`]. Like [`is_interrupt`], all of its methods
+/// look through the whole chain of sources of the error.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{BufRead, Read, Result};
+///
+/// use interrupt_read::{InterruptReader, IoErrorExt};
+///
+/// // Read until either `Ok(0)` or an interrupt occurred.
+/// fn interrupt_read_loop(mut reader: InterruptReader<impl Read>) -> Result<String> {
+///     let mut string = String::new();
+///     loop {
+///         match reader.read_line(&mut string) {
+///             Ok(0) => break Ok(string),
+///             Ok(_) => {}
+///             Err(err) if err.is_interrupt() => break Ok(string),
+///             Err(err) => break Err(err),
+///         }
+///     }
+/// }
+/// ```
+pub trait IoErrorExt: sealed::Sealed + Sized {
+    /// Wether the error originated from an [`Interruptor`]
+    ///
+    /// This is the same as calling [`is_interrupt`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{Error, ErrorKind};
+    ///
+    /// use interrupt_read::{InterruptReceived, IoErrorExt};
+    ///
+    /// assert!(Error::other(InterruptReceived::new()).is_interrupt());
+    /// assert!(!Error::from(ErrorKind::Interrupted).is_interrupt());
+    /// ```
+    fn is_interrupt(&self) -> bool;
+
+    /// The payload of the interrupt, if it is of type `T`
+    ///
+    /// Returns [`None`] if the error didn't originate from an
+    /// [`Interruptor`], or if the payload is not a `T`. Currently,
+    /// the only payload of an interrupt is the [`InterruptReceived`]
+    /// itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{Error, ErrorKind};
+    ///
+    /// use interrupt_read::{InterruptReceived, IoErrorExt};
+    ///
+    /// let err = Error::new(ErrorKind::TimedOut, Error::other(InterruptReceived::new()));
+    /// assert!(err.interrupt_payload::<InterruptReceived>().is_some());
+    /// assert!(err.interrupt_payload::<String>().is_none());
+    ///
+    /// let err = Error::other("not an interrupt");
+    /// assert!(err.interrupt_payload::<InterruptReceived>().is_none());
+    /// ```
+    fn interrupt_payload<T: 'static>(&self) -> Option<&T>;
+
+    /// Turns the error into an [`InterruptReceived`], if it
+    /// originated from an [`Interruptor`]
+    ///
+    /// Otherwise, the original error is returned back, so it can
+    /// still be handled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{Error, ErrorKind};
+    ///
+    /// use interrupt_read::{InterruptReceived, IoErrorExt};
+    ///
+    /// let err = Error::other(InterruptReceived::new());
+    /// assert!(err.into_interrupt().is_ok());
+    ///
+    /// let err = Error::from(ErrorKind::NotFound);
+    /// assert_eq!(
+    ///     err.into_interrupt().unwrap_err().kind(),
+    ///     ErrorKind::NotFound
+    /// );
+    /// ```
+    fn into_interrupt(self) -> Result<InterruptReceived, Self>;
+}
+
+impl IoErrorExt for Error {
+    fn is_interrupt(&self) -> bool {
+        is_interrupt(self)
+    }
+
+    fn interrupt_payload<T: 'static>(&self) -> Option<&T> {
+        find_interrupt(self).and_then(|interrupt| (interrupt as &dyn Any).downcast_ref())
+    }
+
+    fn into_interrupt(self) -> Result<InterruptReceived, Self> {
+        match find_interrupt(&self) {
+            Some(interrupt) => Ok(interrupt.clone()),
+            None => Err(self),
+        }
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for std::io::Error {}
+}
+
+/// Looks for an [`InterruptReceived`] in the chain of an [`Error`]
+fn find_interrupt(err: &Error) -> Option<&InterruptReceived> {
+    const MAX_DEPTH: usize = 16;
+
+    let mut next = err
+        .get_ref()
+        .map(|err| err as &(dyn std::error::Error + 'static));
+
+    for _ in 0..MAX_DEPTH {
+        let err = next?;
+        if let Some(interrupt) = err.downcast_ref() {
+            return Some(interrupt);
+        }
+
+        // The source of an io::Error skips its payload.
+        next = match err.downcast_ref::<Error>() {
+            Some(err) => err
+                .get_ref()
+                .map(|err| err as &(dyn std::error::Error + 'static)),
+            None => err.source(),
+        };
+    }
+
+    None
+}
+
+/// A transform applied to every chunk, given to [`pair_with_map`]
+type Map = Box<dyn FnMut(Vec<u8>) -> std::io::Result<Vec<u8>> + Send>;
+
+/// What runs the worker, given to [`pair_with_spawner`]
+type Spawner<'a> = Box<dyn FnOnce(Box<dyn FnOnce() + Send + 'static>) + 'a>;
+
+/// Applies the [`Map`] to the first `len` bytes of a chunk,
+/// returning [`None`] if nothing is left of it
+fn map_chunk(map: &mut Map, mut chunk: Vec<u8>, len: usize, arrived: Instant) -> Option<Event> {
+    chunk.truncate(len);
+    match map(chunk) {
+        // It would look like the end of the stream.
+        Ok(chunk) if chunk.is_empty() => None,
+        Ok(chunk) => {
+            let len = chunk.len();
+            Some(Event::Buf(chunk, len, arrived))
+        }
+        Err(err) => Some(Event::Err(err)),
+    }
+}
+
+/// Wether a read failed because it was cancelled through
+/// [`Interruptor::cancel_blocking_read`]
+#[cfg(all(windows, feature = "windows-cancel"))]
+fn was_cancelled(err: &Error) -> bool {
+    err.raw_os_error() == Some(windows_sys::Win32::Foundation::ERROR_OPERATION_ABORTED as i32)
+}
+
+#[cfg(not(all(windows, feature = "windows-cancel")))]
+fn was_cancelled(_: &Error) -> bool {
+    false
+}
+
+/// Waits for the source to be readable, or for the worker thread to
+/// be woken up
+#[cfg(unix)]
+fn wait_readable(source: BorrowedFd, mut cancel: &PipeReader, shared: &Shared) -> Wake {
+    loop {
+        let mut fds = [source.as_raw_fd(), cancel.as_raw_fd()].map(|fd| libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        });
+
+        // SAFETY: The pointer and length come from a valid array.
+        if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) } < 0 {
+            match Error::last_os_error().kind() {
+                std::io::ErrorKind::Interrupted => continue,
+                // Let the read figure it out.
+                _ => return Wake::Readable,
+            }
+        }
+
+        if fds[1].revents != 0 {
+            _ = cancel.read(&mut [0; 64]);
+            let state = shared.state();
+            if state.reader_dropped || state.closed {
+                return Wake::Stop;
+            } else if shared.has_command() {
+                return Wake::Command;
+            }
+        }
+
+        if fds[0].revents != 0 {
+            return Wake::Readable;
+        }
+    }
+}
+
+/// Makes reads and writes on a file descriptor return
+/// [`ErrorKind::WouldBlock`] instead of blocking
+#[cfg(unix)]
+fn set_nonblocking(fd: BorrowedFd) {
+    let fd = fd.as_raw_fd();
+    // SAFETY: The file descriptor is valid, since it is borrowed.
+    unsafe {
+        libc::fcntl(
+            fd,
+            libc::F_SETFL,
+            libc::fcntl(fd, libc::F_GETFL) | libc::O_NONBLOCK,
+        );
+    }
+}
+
+/// Blocks until an [`Event`] arrives, or the deadline is reached
+fn recv_event(
+    event_rx: &mpsc::Receiver<Event>,
+    shared: &Shared,
+    deadline: Option<Instant>,
+) -> Result<Event, RecvTimeoutError> {
+    // The worker thread could be waiting for them.
+    shared.flush_buffers();
+    let wait = shared.start_waiting();
+    let event = loop {
+        let event = match deadline {
+            Some(deadline) => match event_rx.recv_timeout(shared.clock.timeout_until(deadline)) {
+                // The clock may not follow real time.
+                Err(RecvTimeoutError::Timeout) if shared.clock.now() < deadline => continue,
+                event => event,
+            },
+            None => event_rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match event {
+            // An interrupt that arrived too late, or was already observed.
+            Ok(Event::Interrupt(interrupt)) if !interrupt.applies(Some(wait), shared) => {}
+            Ok(Event::Interrupt(interrupt)) if !shared.claims().delivers(&interrupt) => {
+                shared.claims().held.push(interrupt);
+            }
+            Ok(Event::Latest(id)) => {
+                if let Some(event) = shared.take_latest(id) {
+                    break Ok(event);
+                }
+            }
+            event => break event,
+        }
+    };
+    shared.stop_waiting();
+
+    event.inspect(|event| received(event, shared))
+}
+
+/// Receives an [`Event`], if one is available
+fn try_recv_event(
+    event_rx: &mpsc::Receiver<Event>,
+    shared: &Shared,
+) -> Result<Event, TryRecvError> {
+    loop {
+        match event_rx.try_recv() {
+            // An interrupt that arrived too late, or was already observed.
+            Ok(Event::Interrupt(interrupt)) if !interrupt.applies(None, shared) => {}
+            Ok(Event::Interrupt(interrupt)) if !shared.claims().delivers(&interrupt) => {
+                shared.claims().held.push(interrupt);
+            }
+            Ok(Event::Latest(id)) => {
+                if let Some(event) = shared.take_latest(id) {
+                    break Ok(event).inspect(|event| received(event, shared));
+                }
+            }
+            event => break event.inspect(|event| received(event, shared)),
+        }
+    }
+}
+
+/// Bookkeeping for a received [`Event`]
+fn received(event: &Event, shared: &Shared) {
+    if let Event::Buf(_, len, _) = event {
+        shared.queued.fetch_sub(*len, Relaxed);
+    }
+}
+
+/// Turns a read that claims more than `capacity` bytes into an error
+/// with a [`MisbehavingReader`] payload
+///
+/// What is past the bytes that were actually read is stale, so it
+/// can't be served.
+fn checked_len(result: std::io::Result<usize>, capacity: usize) -> std::io::Result<usize> {
+    match result {
+        Ok(claimed) if claimed > capacity => {
+            Err(Error::other(MisbehavingReader { claimed, capacity }))
+        }
+        result => result,
+    }
 }
 
-fn interrupt_error() -> Error {
-    Error::other(InterruptReceived)
+/// The length of the character at the end of `bytes` that is cut
+/// short, or `0` if there is none
+fn incomplete_utf8_tail(bytes: &[u8]) -> usize {
+    let mut rest = bytes;
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(_) => break 0,
+            Err(err) => match err.error_len() {
+                Some(len) => rest = &rest[err.valid_up_to() + len..],
+                None => break rest.len() - err.valid_up_to(),
+            },
+        }
+    }
 }
 
-fn deal_with_interrupt(event_rx: &mpsc::Receiver<Event>) -> std::io::Result<()> {
-    match event_rx.try_recv() {
-        Ok(Event::Interrupt) => Err(interrupt_error()),
-        Ok(_) => unreachable!("This should not be possible"),
-        // The channel was dropped, but no need to handle that right now.
-        Err(_) => Ok(()),
+/// The length of a UTF-8 sequence starting with `byte`, or `0` if it
+/// can't start one
+fn utf8_width(byte: u8) -> usize {
+    match byte {
+        0x00..=0x7f => 1,
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => 0,
     }
 }
@@ -0,0 +1,286 @@
+//! Interruptable reading from non-blocking sources, without a thread
+//!
+//! For sources that never block, like a socket with
+//! `set_nonblocking(true)`, or an in-memory queue that returns
+//! [`ErrorKind::WouldBlock`] when it is empty, a worker thread is
+//! pure overhead. [`pair_local`] reads from those directly, on the
+//! calling thread, and whenever the source would block, it waits for
+//! a little while on the [`Interruptor`] instead, before trying
+//! again.
+//!
+//! The [`Interruptor`] is the same one returned by [`pair`], and the
+//! errors are the same as well, so [`is_interrupt`] works as usual.
+//! Since both [`LocalInterruptReader`] and [`InterruptReader`]
+//! implement [`BufRead`], code that is generic over it doesn't have
+//! to care about which one it got.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(unix)]
+//! # fn main() -> std::io::Result<()> {
+//! use std::{
+//!     io::{BufRead, Write},
+//!     os::unix::net::UnixStream,
+//!     time::Duration,
+//! };
+//!
+//! use interrupt_read::{is_interrupt, pair_local};
+//!
+//! let (stream, mut peer) = UnixStream::pair()?;
+//! stream.set_nonblocking(true)?;
+//! let (mut reader, interruptor) = pair_local(stream);
+//!
+//! peer.write_all(b"hello\n")?;
+//! let mut line = String::new();
+//! reader.read_line(&mut line)?;
+//! assert_eq!(line, "hello\n");
+//!
+//! // Nothing else was written, so the source would block.
+//! std::thread::spawn(move || {
+//!     std::thread::sleep(Duration::from_millis(50));
+//!     interruptor.interrupt()
+//! });
+//! let err = reader.read_line(&mut line).unwrap_err();
+//! assert!(is_interrupt(&err));
+//!
+//! // The end of the stream works as usual.
+//! peer.write_all(b"bye")?;
+//! drop(peer);
+//! line.clear();
+//! assert_eq!(reader.read_line(&mut line)?, 3);
+//! assert_eq!(reader.read_line(&mut line)?, 0);
+//! assert_eq!(line, "bye");
+//! # Ok(())
+//! # }
+//! # #[cfg(not(unix))]
+//! # fn main() {}
+//! ```
+//!
+//! [`pair_local`]: crate::pair_local
+//! [`pair`]: crate::pair
+//! [`is_interrupt`]: crate::is_interrupt
+//! [`InterruptReader`]: crate::InterruptReader
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, Error, ErrorKind, Read},
+    sync::{
+        Arc,
+        atomic::Ordering::Relaxed,
+        mpsc::{self, RecvTimeoutError},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Builder, Event, Interrupt, InterruptReceived, Interruptor, ReaderHandle, checked_len,
+    recv_event, try_recv_event,
+};
+
+/// How long to wait on the [`Interruptor`] whenever the source would
+/// block.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// An interruptable, buffered [`Read`]er, without a worker thread
+///
+/// This is acquired through [`pair_local`], check out the [module
+/// level documentation] for more information.
+///
+/// [`pair_local`]: crate::pair_local
+/// [module level documentation]: self
+#[derive(Debug)]
+pub struct LocalInterruptReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    pos: usize,
+    len: usize,
+    capacity: usize,
+    event_rx: mpsc::Receiver<Event>,
+    shared: ReaderHandle,
+    /// Events that arrived while looking for interrupts.
+    pending: VecDeque<Event>,
+    closed: bool,
+    /// Bytes delivered to the consumer since creation.
+    delivered: u64,
+}
+
+impl<R: Read> LocalInterruptReader<R> {
+    /// Returns a new `LocalInterruptReader`, and its [`Interruptor`]
+    pub(crate) fn new(reader: R) -> (Self, Interruptor) {
+        let builder = Builder::new();
+        let (event_tx, event_rx) = mpsc::channel();
+        let shared = Arc::new(builder.shared(builder.buffer_size, None));
+
+        let reader = Self {
+            reader,
+            buf: Vec::new(),
+            pos: 0,
+            len: 0,
+            capacity: builder.buffer_size,
+            event_rx,
+            shared: ReaderHandle(shared.clone()),
+            pending: VecDeque::new(),
+            closed: false,
+            delivered: 0,
+        };
+
+        (reader, Interruptor::new(event_tx, shared))
+    }
+
+    /// Unwraps this `LocalInterruptReader`, returning the source
+    ///
+    /// Any data left in the internal buffer is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// A reference to the source
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// A mutable reference to the source
+    ///
+    /// Reading from it directly may lead to data loss, since there
+    /// could be data in the internal buffer.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Takes in the events that arrived, waiting until `deadline`
+    /// for one, if there is one
+    ///
+    /// Interrupts are returned as an [`Err`] right away, while other
+    /// events are put in `pending`.
+    fn take_events(&mut self, deadline: Option<Instant>) -> io::Result<()> {
+        match self.shared.persistent.load(Relaxed) {
+            0 => {}
+            seq => {
+                return Err(self.interrupted(Interrupt {
+                    seq,
+                    if_waiting: None,
+                    reason: None,
+                    label: None,
+                    kind: ErrorKind::Other,
+                    claim: None,
+                }));
+            }
+        }
+
+        if let Some(deadline) = deadline {
+            match recv_event(&self.event_rx, &self.shared, Some(deadline)) {
+                Ok(event) => self.take_event(event)?,
+                Err(RecvTimeoutError::Timeout) => return Ok(()),
+                // Without Interruptors, there is nothing to wait on.
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.shared.clock.wait_until(deadline);
+                }
+            }
+        }
+
+        while let Ok(event) = try_recv_event(&self.event_rx, &self.shared) {
+            self.take_event(event)?;
+        }
+
+        Ok(())
+    }
+
+    fn take_event(&mut self, event: Event) -> io::Result<()> {
+        match event {
+            Event::Interrupt(interrupt) => Err(self.interrupted(interrupt)),
+            event => {
+                self.pending.push_back(event);
+                Ok(())
+            }
+        }
+    }
+
+    /// Observes an interrupt, returning the [`Error`] for it
+    fn interrupted(&mut self, interrupt: Interrupt) -> Error {
+        if self.shared.observe(interrupt.seq) {
+            self.pos = self.len;
+            self.pending
+                .retain(|event| !matches!(event, Event::Buf(..)));
+        }
+
+        Error::new(interrupt.kind, InterruptReceived {
+            reason: interrupt.reason,
+            label: interrupt.label,
+            bytes_delivered: self.delivered,
+        })
+    }
+}
+
+impl<R: Read> Read for LocalInterruptReader<R> {
+    /// Reads some bytes, waiting until at least one is available
+    ///
+    /// Like with an [`InterruptReader`], interrupts take precedence
+    /// over the data that was already read from the source, and an
+    /// empty `buf` returns `Ok(0)` right away.
+    ///
+    /// [`InterruptReader`]: crate::InterruptReader
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+
+        Ok(len)
+    }
+}
+
+impl<R: Read> BufRead for LocalInterruptReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        // Interrupts take precedence over what was already read.
+        self.take_events(None)?;
+
+        while self.pos == self.len {
+            match self.pending.pop_front() {
+                Some(Event::Buf(buf, len, _)) => {
+                    self.buf = buf;
+                    self.pos = 0;
+                    self.len = len;
+                }
+                Some(Event::Err(err)) => return Err(err),
+                Some(Event::Close) => self.closed = true,
+                Some(
+                    Event::Interrupt(_) | Event::Reply(..) | Event::Latest(_) | Event::Relayed(_),
+                ) => {
+                    unreachable!("never pending, or only sent to a worker thread")
+                }
+                None if self.closed => return Ok(&[]),
+                None => {
+                    if self.buf.len() < self.capacity {
+                        self.buf.resize(self.capacity, 0);
+                    }
+
+                    match checked_len(self.reader.read(&mut self.buf), self.buf.len()) {
+                        Ok(0) => return Ok(&[]),
+                        Ok(len) => {
+                            self.pos = 0;
+                            self.len = len;
+                        }
+                        Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                            self.take_events(Some(self.shared.clock.now() + POLL_INTERVAL))?;
+                        }
+                        // Just a signal landing in this thread.
+                        Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                        Err(err) => return Err(err),
+                    }
+                }
+            }
+        }
+
+        Ok(&self.buf[self.pos..self.len])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        let amount = amount.min(self.len - self.pos);
+        self.pos += amount;
+        self.delivered += amount as u64;
+    }
+}
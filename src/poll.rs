@@ -0,0 +1,348 @@
+//! Interruptable reading without a worker thread
+//!
+//! For sources that can be polled, like sockets and pipes, spawning
+//! a whole thread per reader is overkill. The [`PollReader`] from
+//! this module instead waits on the file descriptor of the source
+//! alongside a notification from its [`PollInterruptor`], reading
+//! from the source on the calling thread.
+//!
+//! The semantics are the same as those of the thread based
+//! [`InterruptReader`]: an interrupt makes the next read operation
+//! return an [`Err`] with a payload of [`InterruptReceived`] (which
+//! you can check for with [`is_interrupt`]), the buffered data is
+//! kept, and reading again afterwards proceeds as normal. Both
+//! readers implement [`Read`] and [`BufRead`], so code that is
+//! generic over those traits works with either of them.
+//!
+//! This module is only available on Unix, with the `os-poll`
+//! feature enabled.
+//!
+//! [`InterruptReader`]: crate::InterruptReader
+//! [`is_interrupt`]: crate::is_interrupt
+use std::{
+    io::{self, BufRead, Read},
+    os::fd::{AsFd, AsRawFd},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering::Relaxed},
+    },
+};
+
+use polling::{Event, Events, Poller};
+
+use crate::{InterruptReceived, InterruptSendError, checked_len};
+
+/// The key used for the source in the [`Poller`].
+const SOURCE: usize = 0;
+
+/// Returns a pair of a [`PollReader`] and a [`PollInterruptor`]
+///
+/// This works just like [`interrupt_read::pair`], but no thread is
+/// spawned, reads instead wait for the file descriptor of `reader`
+/// to become readable.
+///
+/// This function returns an [`Err`] if the OS poller couldn't be
+/// created, or if the `reader` couldn't be registered with it.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::{
+///     io::{BufRead, Read, Write},
+///     net::{TcpListener, TcpStream},
+///     time::Duration,
+/// };
+///
+/// use interrupt_read::{is_interrupt, poll};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let listener = TcpListener::bind("127.0.0.1:0")?;
+/// let stream = TcpStream::connect(listener.local_addr()?)?;
+/// let (mut peer, _) = listener.accept()?;
+///
+/// let (mut reader, interruptor) = poll::pair(stream)?;
+///
+/// peer.write_all(b"hello\n")?;
+/// let mut line = String::new();
+/// reader.read_line(&mut line)?;
+/// assert_eq!(line, "hello\n");
+///
+/// // Nothing is being sent, so this would block forever.
+/// let join_handle = std::thread::spawn(move || {
+///     let result = reader.read_line(&mut String::new());
+///     (result, reader)
+/// });
+///
+/// std::thread::sleep(Duration::from_millis(50));
+/// interruptor.interrupt()?;
+///
+/// let (result, mut reader) = join_handle.join().unwrap();
+/// assert!(is_interrupt(&result.unwrap_err()));
+///
+/// // Reading proceeds as normal afterwards.
+/// peer.write_all(b"world\n")?;
+/// drop(peer);
+///
+/// let mut rest = String::new();
+/// reader.read_to_string(&mut rest)?;
+/// assert_eq!(rest, "world\n");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// With a pipe:
+///
+/// ```rust
+/// use std::io::{BufRead, Write};
+///
+/// use interrupt_read::{is_interrupt, poll};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+/// let (mut reader, interruptor) = poll::pair(pipe_reader)?;
+///
+/// pipe_writer.write_all(b"data")?;
+///
+/// // Buffered data is kept through an interrupt.
+/// assert_eq!(reader.fill_buf()?, b"data");
+/// interruptor.interrupt()?;
+/// assert!(is_interrupt(&reader.fill_buf().unwrap_err()));
+/// assert_eq!(reader.fill_buf()?, b"data");
+/// reader.consume(4);
+///
+/// // The end of the stream.
+/// drop(pipe_writer);
+/// assert_eq!(reader.fill_buf()?, b"");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// A source that claims to have read more than it could, which is
+/// returned as an error with a [`MisbehavingReader`] payload:
+///
+/// ```rust
+/// use std::{
+///     io::{PipeReader, Read, Write},
+///     os::fd::{AsFd, BorrowedFd},
+/// };
+///
+/// use interrupt_read::{MisbehavingReader, poll};
+///
+/// /// Claims one byte too many on the first read.
+/// struct OffByOne(PipeReader, bool);
+///
+/// impl Read for OffByOne {
+///     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+///         let len = self.0.read(buf)?;
+///         Ok(if std::mem::replace(&mut self.1, true) {
+///             len
+///         } else {
+///             buf.len() + 1
+///         })
+///     }
+/// }
+///
+/// impl AsFd for OffByOne {
+///     fn as_fd(&self) -> BorrowedFd<'_> {
+///         self.0.as_fd()
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let (pipe_reader, mut pipe_writer) = std::io::pipe()?;
+/// let (mut reader, _interruptor) = poll::pair(OffByOne(pipe_reader, false))?;
+///
+/// pipe_writer.write_all(b"no")?;
+/// let err = reader.read(&mut [0; 16]).unwrap_err();
+/// assert!(err.get_ref().unwrap().is::<MisbehavingReader>());
+///
+/// pipe_writer.write_all(b"ok")?;
+/// drop(pipe_writer);
+/// let mut out = Vec::new();
+/// reader.read_to_end(&mut out)?;
+/// assert_eq!(out, b"ok");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`interrupt_read::pair`]: crate::pair
+/// [`MisbehavingReader`]: crate::MisbehavingReader
+pub fn pair<R: Read + AsFd>(reader: R) -> io::Result<(PollReader<R>, PollInterruptor)> {
+    let shared = Arc::new(Shared {
+        poller: Poller::new()?,
+        interrupts: AtomicUsize::new(0),
+        reader_dropped: AtomicBool::new(false),
+    });
+
+    // SAFETY: The source is deleted from the poller before the file
+    // descriptor is closed, in the Drop implementation of PollReader.
+    unsafe {
+        shared
+            .poller
+            .add(reader.as_fd().as_raw_fd(), Event::readable(SOURCE))?
+    };
+
+    let poll_reader = PollReader {
+        reader: Some(reader),
+        // Same capacity as BufReader.
+        buf: vec![0; 8 * 1024],
+        pos: 0,
+        len: 0,
+        events: Events::new(),
+        shared: shared.clone(),
+    };
+
+    Ok((poll_reader, PollInterruptor { shared }))
+}
+
+/// An interruptable reader that doesn't spawn a thread
+///
+/// This is acquired through [`poll::pair`], check out its
+/// documentation for more information.
+///
+/// [`poll::pair`]: pair
+#[derive(Debug)]
+pub struct PollReader<R: AsFd> {
+    reader: Option<R>,
+    buf: Vec<u8>,
+    pos: usize,
+    len: usize,
+    events: Events,
+    shared: Arc<Shared>,
+}
+
+impl<R: AsFd> PollReader<R> {
+    /// Unwraps this `PollReader`, returning the underlying reader
+    ///
+    /// Note that any leftover data in the internal buffer is lost.
+    pub fn into_inner(mut self) -> R {
+        let reader = self.reader.take().unwrap();
+        _ = self.shared.poller.delete(reader.as_fd());
+        reader
+    }
+
+    /// The bytes that are currently in the internal buffer
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..self.len]
+    }
+}
+
+impl<R: Read + AsFd> Read for PollReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+
+        Ok(len)
+    }
+}
+
+impl<R: Read + AsFd> BufRead for PollReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        loop {
+            if self.shared.take_interrupt() {
+                return Err(io::Error::other(InterruptReceived::new()));
+            } else if self.pos < self.len {
+                break;
+            }
+
+            let reader = self.reader.as_mut().unwrap();
+
+            // Sources are registered in oneshot mode, so they have to be
+            // rearmed before every wait.
+            self.shared
+                .poller
+                .modify(reader.as_fd(), Event::readable(SOURCE))?;
+            self.events.clear();
+            match self.shared.poller.wait(&mut self.events, None) {
+                Ok(_) => {}
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+
+            // Woken up by an interrupt, which is checked for above.
+            if !self.events.iter().any(|event| event.key == SOURCE) {
+                continue;
+            }
+
+            match checked_len(reader.read(&mut self.buf), self.buf.len()) {
+                Ok(len) => {
+                    self.pos = 0;
+                    self.len = len;
+                    if len == 0 {
+                        return Ok(&[]);
+                    }
+                }
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock
+                    ) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(self.buffer())
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.pos = (self.pos + amount).min(self.len);
+    }
+}
+
+impl<R: AsFd> Drop for PollReader<R> {
+    fn drop(&mut self) {
+        self.shared.reader_dropped.store(true, Relaxed);
+        if let Some(reader) = self.reader.as_ref() {
+            _ = self.shared.poller.delete(reader.as_fd());
+        }
+    }
+}
+
+/// An interruptor for a [`PollReader`]
+///
+/// This works just like the regular [`Interruptor`], waking up the
+/// `PollReader` if it is waiting for its source to become readable.
+///
+/// [`Interruptor`]: crate::Interruptor
+#[derive(Debug, Clone)]
+pub struct PollInterruptor {
+    shared: Arc<Shared>,
+}
+
+impl PollInterruptor {
+    /// Interrupts the [`PollReader`]
+    ///
+    /// The next read operation will return an [`Err`] with a payload
+    /// of [`InterruptReceived`], after which reading proceeds as
+    /// normal.
+    ///
+    /// This returns an [`Err`] if the `PollReader` has been dropped,
+    /// or if the poller couldn't be notified.
+    pub fn interrupt(&self) -> Result<(), InterruptSendError> {
+        if self.shared.reader_dropped.load(Relaxed) {
+            return Err(InterruptSendError);
+        }
+
+        self.shared.interrupts.fetch_add(1, Relaxed);
+        self.shared.poller.notify().map_err(|_| InterruptSendError)
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    poller: Poller,
+    /// How many interrupts are yet to be observed.
+    interrupts: AtomicUsize,
+    reader_dropped: AtomicBool,
+}
+
+impl Shared {
+    /// Observes one interrupt, if there are any
+    fn take_interrupt(&self) -> bool {
+        self.interrupts
+            .fetch_update(Relaxed, Relaxed, |interrupts| interrupts.checked_sub(1))
+            .is_ok()
+    }
+}
@@ -0,0 +1,164 @@
+//! The stream of events behind an [`InterruptReader`]
+//!
+//! The [`Read`] and [`BufRead`] implementations of an
+//! `InterruptReader` are a facade over a stream of events sent by
+//! the worker thread: chunks of data, errors, interrupts, and the
+//! end of the stream. For consumers that do their own scheduling,
+//! like an async bridge, that facade gets in the way, so
+//! [`InterruptReader::into_raw`] hands out the events themselves,
+//! through a [`RawInterruptReader`].
+//!
+//! The events come in the same order, and follow the same rules, as
+//! they would through the `InterruptReader`: interrupts take
+//! precedence over chunks that weren't handed out yet, and the
+//! [`Chunk`]s are handed back to the worker thread when dropped, so
+//! holding on to them holds up the reading, but dropping them never
+//! does. [`RawInterruptReader::into_reader`] turns it back into an
+//! `InterruptReader`, without losing anything.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::{
+//!     io::{ErrorKind, Read},
+//!     sync::{Arc, Barrier},
+//! };
+//!
+//! use interrupt_read::{
+//!     pair,
+//!     raw::RawEvent,
+//!     testing::{ScriptedReader, Step},
+//! };
+//!
+//! let barrier = Arc::new(Barrier::new(2));
+//! let mut steps = vec![
+//!     Step::Data(b"first".to_vec()),
+//!     Step::Error(ErrorKind::ConnectionReset),
+//!     Step::WaitForSignal(barrier.clone()),
+//! ];
+//! // Way more chunks than there are buffers, so they must be recycled.
+//! steps.extend((0..100u8).map(|i| Step::Data(vec![i; 10])));
+//! steps.push(Step::Data(b"last".to_vec()));
+//!
+//! let (reader, interruptor) = pair(ScriptedReader::new(steps));
+//! let mut raw = reader.into_raw();
+//!
+//! assert!(matches!(raw.recv(), RawEvent::Data(chunk) if *chunk == *b"first"));
+//! assert!(matches!(raw.recv(), RawEvent::Err(err) if err.kind() == ErrorKind::ConnectionReset));
+//!
+//! // The worker thread is waiting on the barrier, so nothing else came.
+//! assert!(raw.try_recv().is_none());
+//! interruptor.interrupt_with_reason("checking in").unwrap();
+//! let RawEvent::Interrupt(interrupt) = raw.recv() else {
+//!     panic!("expected an interrupt");
+//! };
+//! assert_eq!(interrupt.reason(), Some("checking in"));
+//!
+//! barrier.wait();
+//! for i in 0..100u8 {
+//!     let mut received = Vec::new();
+//!     while received.len() < 10 {
+//!         match raw.recv() {
+//!             RawEvent::Data(chunk) => received.extend_from_slice(&chunk),
+//!             event => panic!("unexpected {event:?}"),
+//!         }
+//!     }
+//!     assert_eq!(received, [i; 10]);
+//! }
+//!
+//! // Back to reading as usual.
+//! let mut reader = raw.into_reader();
+//! let mut rest = String::new();
+//! reader.read_to_string(&mut rest).unwrap();
+//! assert_eq!(rest, "last");
+//! ```
+//!
+//! [`InterruptReader`]: crate::InterruptReader
+//! [`InterruptReader::into_raw`]: crate::InterruptReader::into_raw
+//! [`Read`]: std::io::Read
+//! [`BufRead`]: std::io::BufRead
+use std::{
+    io::{self, Read},
+    time::Duration,
+};
+
+use crate::{Chunk, InterruptReader, InterruptReceived, IoErrorExt, Wait};
+
+/// An [`InterruptReader`], handing out the events behind it
+///
+/// This is acquired through [`InterruptReader::into_raw`], check out
+/// the [module level documentation] for more information.
+///
+/// [module level documentation]: self
+#[derive(Debug)]
+pub struct RawInterruptReader<R> {
+    reader: InterruptReader<R>,
+}
+
+impl<R: Read> RawInterruptReader<R> {
+    pub(crate) fn new(reader: InterruptReader<R>) -> Self {
+        Self { reader }
+    }
+
+    /// Returns the next event, blocking until there is one
+    ///
+    /// Once the end of the stream is reached, this keeps returning
+    /// [`RawEvent::Eof`].
+    pub fn recv(&mut self) -> RawEvent {
+        self.next_event(Wait::Block).unwrap()
+    }
+
+    /// Returns the next event, if there is one already
+    pub fn try_recv(&mut self) -> Option<RawEvent> {
+        self.next_event(Wait::Never)
+    }
+
+    /// Returns the next event, blocking for up to `timeout` until
+    /// there is one
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Option<RawEvent> {
+        let deadline = self.reader.shared.clock.now().checked_add(timeout);
+        self.next_event(Wait::Until(deadline))
+    }
+
+    /// Turns this back into an [`InterruptReader`]
+    ///
+    /// Nothing is lost: whatever wasn't returned yet is read from the
+    /// `InterruptReader` next.
+    pub fn into_reader(self) -> InterruptReader<R> {
+        self.reader
+    }
+
+    /// A reference to the [`InterruptReader`]
+    pub fn get_ref(&self) -> &InterruptReader<R> {
+        &self.reader
+    }
+
+    fn next_event(&mut self, wait: Wait) -> Option<RawEvent> {
+        let event = match self.reader.fill_buf_with(wait, 0) {
+            Ok(None) => return None,
+            Ok(Some([])) => RawEvent::Eof,
+            Ok(Some(_)) => RawEvent::Data(self.reader.take_chunk()),
+            Err(err) => match err.into_interrupt() {
+                Ok(interrupt) => RawEvent::Interrupt(interrupt),
+                Err(err) => RawEvent::Err(err),
+            },
+        };
+
+        Some(event)
+    }
+}
+
+/// An event handed out by a [`RawInterruptReader`]
+#[derive(Debug)]
+pub enum RawEvent {
+    /// A chunk read from the source.
+    Data(Chunk),
+    /// An error returned by the source.
+    Err(io::Error),
+    /// An interrupt sent by an [`Interruptor`].
+    ///
+    /// [`Interruptor`]: crate::Interruptor
+    Interrupt(InterruptReceived),
+    /// The end of the stream.
+    Eof,
+}
@@ -0,0 +1,394 @@
+//! Reading from a source that is recreated whenever it fails
+//!
+//! Sources like network connections drop every now and then, and
+//! what you usually want is to connect again and keep reading, as if
+//! nothing happened. [`pair_with_reconnect`] does that: instead of a
+//! reader, it takes a factory, which the worker thread calls to
+//! create the source, and to create it again whenever it fails, as
+//! configured by a [`ReconnectPolicy`].
+//!
+//! Every time the source is going to be recreated, the
+//! [`InterruptReader`] returns an error with a [`Reconnecting`]
+//! payload, which can be found through [`reconnecting`], so the
+//! consumer knows about it, but can keep reading.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::{
+//!     io::{Cursor, ErrorKind, Read},
+//!     time::Duration,
+//! };
+//!
+//! use interrupt_read::{
+//!     pair_with_reconnect,
+//!     reconnect::{ReconnectPolicy, reconnecting},
+//! };
+//!
+//! # fn main() -> std::io::Result<()> {
+//! let mut calls = 0;
+//! let factory = move |_| {
+//!     calls += 1;
+//!     match calls {
+//!         // Refused twice, then connected, then connected again.
+//!         3 => Ok(Cursor::new(b"hello ".to_vec())),
+//!         4 => Ok(Cursor::new(b"world".to_vec())),
+//!         _ => Err(std::io::Error::from(ErrorKind::ConnectionRefused)),
+//!     }
+//! };
+//!
+//! let policy = ReconnectPolicy::new()
+//!     .max_attempts(3)
+//!     .backoff(Duration::from_millis(1), Duration::from_millis(10));
+//! let (mut reader, _interruptor) = pair_with_reconnect(factory, policy);
+//!
+//! let mut out = String::new();
+//! let mut reconnects = 0;
+//! let err = loop {
+//!     match reader.read_to_string(&mut out) {
+//!         Ok(_) => unreachable!("the stream ends after an error"),
+//!         Err(err) if reconnecting(&err).is_some() => reconnects += 1,
+//!         Err(err) => break err,
+//!     }
+//! };
+//!
+//! // The data came through in one piece.
+//! assert_eq!(out, "hello world");
+//! // Two failed attempts, two ends of the stream, then two more failed
+//! // attempts, before the third one made it give up.
+//! assert_eq!(reconnects, 6);
+//! assert_eq!(err.kind(), ErrorKind::ConnectionRefused);
+//! assert_eq!(reader.read(&mut [0; 16])?, 0);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`pair_with_reconnect`]: crate::pair_with_reconnect
+//! [`InterruptReader`]: crate::InterruptReader
+use std::{
+    io::{self, Error, ErrorKind, Read},
+    sync::{Arc, OnceLock, PoisonError},
+    time::Duration,
+};
+
+use crate::Shared;
+
+/// When and how a [`ReconnectReader`] recreates its source
+///
+/// By default, the source is recreated after any error or end of the
+/// stream, without ever giving up, waiting 100ms before the first
+/// attempt, and twice as long before each of the following ones, up
+/// to 5 seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    max_attempts: Option<u32>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    reconnect_on_eof: bool,
+}
+
+impl ReconnectPolicy {
+    /// Returns the default `ReconnectPolicy`
+    pub fn new() -> Self {
+        Self {
+            max_attempts: None,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            reconnect_on_eof: true,
+        }
+    }
+
+    /// Gives up after `attempts` calls to the factory in a row that
+    /// didn't lead to any data being read
+    ///
+    /// Once it gives up, the error that caused it is returned, and
+    /// then the stream ends.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `attempts` is `0`.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        assert!(attempts > 0, "there must be at least one attempt");
+        self.max_attempts = Some(attempts);
+        self
+    }
+
+    /// Waits `initial` before the first attempt, doubling the wait
+    /// for every attempt that follows, up to `max`
+    ///
+    /// The attempts are counted from the last time data was read.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Sets wether the end of the stream makes the source be
+    /// recreated
+    ///
+    /// If it doesn't, the end of the stream is returned as usual,
+    /// and only errors make the source be recreated.
+    pub fn reconnect_on_eof(mut self, reconnect: bool) -> Self {
+        self.reconnect_on_eof = reconnect;
+        self
+    }
+
+    /// How long to wait before the attempt after `failed` failed
+    /// ones
+    fn delay(&self, failed: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failed.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An attempt at creating the source, passed to the factory of a
+/// [`ReconnectReader`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attempt {
+    number: u32,
+    reconnect: bool,
+}
+
+impl Attempt {
+    /// The number of this attempt, counting from `1`, since the last
+    /// time data was read
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Wether a source was created before, so this is about
+    /// recreating it
+    pub fn is_reconnect(&self) -> bool {
+        self.reconnect
+    }
+}
+
+/// A reader that recreates its source whenever it fails
+///
+/// This is acquired through [`pair_with_reconnect`], check out the
+/// [module level documentation] for more information.
+///
+/// [`pair_with_reconnect`]: crate::pair_with_reconnect
+/// [module level documentation]: self
+pub struct ReconnectReader<F, R> {
+    factory: F,
+    policy: ReconnectPolicy,
+    source: Option<R>,
+    /// Calls to the factory since data was last read.
+    attempts: u32,
+    connected: bool,
+    delay: Option<Duration>,
+    gave_up: bool,
+    shared: Arc<OnceLock<Arc<Shared>>>,
+}
+
+impl<F, R> ReconnectReader<F, R>
+where
+    F: FnMut(Attempt) -> io::Result<R>,
+    R: Read,
+{
+    /// Returns a new `ReconnectReader`, and where to put the
+    /// [`Shared`] state of its pair
+    pub(crate) fn new(factory: F, policy: ReconnectPolicy) -> (Self, Arc<OnceLock<Arc<Shared>>>) {
+        let shared = Arc::new(OnceLock::new());
+        let reader = Self {
+            factory,
+            policy,
+            source: None,
+            attempts: 0,
+            connected: false,
+            delay: None,
+            gave_up: false,
+            shared: shared.clone(),
+        };
+
+        (reader, shared)
+    }
+
+    /// The current source, if there is one
+    pub fn source(&self) -> Option<&R> {
+        self.source.as_ref()
+    }
+
+    /// Wether it gave up on recreating the source
+    pub fn gave_up(&self) -> bool {
+        self.gave_up
+    }
+
+    /// Deals with a failure of the source, or of its creation
+    fn failed(&mut self, cause: Error) -> io::Result<usize> {
+        self.source = None;
+
+        if self
+            .policy
+            .max_attempts
+            .is_some_and(|max| self.attempts >= max)
+        {
+            self.gave_up = true;
+            return Err(cause);
+        }
+
+        let delay = self.policy.delay(self.attempts);
+        self.delay = Some(delay);
+
+        Err(Error::other(Reconnecting {
+            attempt: self.attempts + 1,
+            delay,
+            cause,
+        }))
+    }
+
+    /// Waits for `delay`, returning `false` if the pair was dropped
+    /// or closed in the meantime
+    fn backoff(&self, delay: Duration) -> bool {
+        let shared = self.shared.wait();
+        let deadline = shared.clock.now().checked_add(delay);
+
+        let mut state = shared.state();
+        loop {
+            if state.reader_dropped || state.closed {
+                return false;
+            }
+
+            state = match deadline {
+                Some(deadline) => {
+                    if shared.clock.now() >= deadline {
+                        return true;
+                    }
+                    let timeout = shared.clock.timeout_until(deadline);
+                    match shared.state_changed.wait_timeout(state, timeout) {
+                        Ok((state, _)) => state,
+                        Err(err) => err.into_inner().0,
+                    }
+                }
+                None => shared
+                    .state_changed
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+}
+
+impl<F, R> Read for ReconnectReader<F, R>
+where
+    F: FnMut(Attempt) -> io::Result<R>,
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.gave_up {
+                return Ok(0);
+            }
+
+            let Some(source) = &mut self.source else {
+                if let Some(delay) = self.delay.take()
+                    && !self.backoff(delay)
+                {
+                    return Ok(0);
+                }
+
+                self.attempts += 1;
+                let attempt = Attempt {
+                    number: self.attempts,
+                    reconnect: self.connected,
+                };
+
+                match (self.factory)(attempt) {
+                    Ok(source) => {
+                        self.source = Some(source);
+                        self.connected = true;
+                        continue;
+                    }
+                    Err(err) => return self.failed(err),
+                }
+            };
+
+            return match source.read(buf) {
+                Ok(0) if !buf.is_empty() && self.policy.reconnect_on_eof => self.failed(
+                    Error::new(ErrorKind::UnexpectedEof, "the source reached its end"),
+                ),
+                Ok(len) => {
+                    if len > 0 {
+                        self.attempts = 0;
+                    }
+                    Ok(len)
+                }
+                // Retried by the worker thread, if it is configured to do so.
+                Err(err) if err.kind() == ErrorKind::Interrupted => Err(err),
+                Err(err) => self.failed(err),
+            };
+        }
+    }
+}
+
+impl<F, R: std::fmt::Debug> std::fmt::Debug for ReconnectReader<F, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReconnectReader")
+            .field("policy", &self.policy)
+            .field("source", &self.source)
+            .field("attempts", &self.attempts)
+            .field("gave_up", &self.gave_up)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The payload of the error returned when the source of a
+/// [`ReconnectReader`] is going to be recreated
+///
+/// The error that caused it is its [`source`].
+///
+/// [`source`]: std::error::Error::source
+#[derive(Debug)]
+pub struct Reconnecting {
+    attempt: u32,
+    delay: Duration,
+    cause: Error,
+}
+
+impl Reconnecting {
+    /// The number of the next attempt, as in [`Attempt::number`]
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// How long until the next attempt
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// The error that caused the source to be recreated
+    pub fn cause(&self) -> &Error {
+        &self.cause
+    }
+}
+
+impl std::fmt::Display for Reconnecting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "reconnecting in {:?}, attempt {}: {}",
+            self.delay, self.attempt, self.cause
+        )
+    }
+}
+
+impl std::error::Error for Reconnecting {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.cause)
+    }
+}
+
+/// Returns the [`Reconnecting`] payload of an [`Error`], if it has
+/// one
+pub fn reconnecting(err: &Error) -> Option<&Reconnecting> {
+    err.get_ref()?.downcast_ref()
+}
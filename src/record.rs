@@ -0,0 +1,427 @@
+//! Recording the chunks of a session, and replaying them later
+//!
+//! [`tee`] mirrors the bytes that are read, but when debugging flaky
+//! interactions, the chunk boundaries and the timing between them
+//! matter just as much. [`InterruptReader::record_to`] writes all of
+//! that into a sink, and a [`ReplayReader`] reproduces it, so it can
+//! be fed straight back into [`pair`] in a test.
+//!
+//! # Format
+//!
+//! A recording starts with the magic bytes `b"IRREC"` followed by a
+//! version byte, which is currently [`VERSION`]. After that comes a
+//! sequence of records, each of which starts with a tag byte, and the
+//! time since the previous record (or since the start of the
+//! recording) in microseconds, as a little endian `u64`:
+//!
+//! - `b'D'`: A chunk, followed by its length as a little endian
+//!   `u32`, and its bytes. A length of `0` is an `Ok(0)` from the
+//!   source.
+//! - `b'E'`: An error, followed by its [`ErrorKind`] as a byte (see
+//!   below), the length of its message as a little endian `u32`, and
+//!   the message, in UTF-8.
+//! - `b'I'`: An interrupt that the consumer received, with nothing
+//!   after it.
+//!
+//! The recording ends with the sink. Chunks and errors are recorded
+//! by the worker thread as they come from the source, so their timing
+//! is accurate regardless of how fast the consumer is.
+//!
+//! The [`ErrorKind`]s are stored as the index of the kind in this
+//! list, and any kind not in it is stored as [`ErrorKind::Other`]:
+//! `NotFound`, `PermissionDenied`, `ConnectionRefused`,
+//! `ConnectionReset`, `ConnectionAborted`, `NotConnected`,
+//! `AddrInUse`, `AddrNotAvailable`, `BrokenPipe`, `AlreadyExists`,
+//! `WouldBlock`, `InvalidInput`, `InvalidData`, `TimedOut`,
+//! `WriteZero`, `Interrupted`, `Unsupported`, `UnexpectedEof`,
+//! `OutOfMemory`, `Other`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::{
+//!     fs::File,
+//!     io::{ErrorKind, Read},
+//!     sync::{Arc, Barrier},
+//!     time::Duration,
+//! };
+//!
+//! use interrupt_read::{
+//!     Builder, Readiness, pair,
+//!     record::ReplayReader,
+//!     testing::{MockClock, ScriptedReader, Step},
+//! };
+//!
+//! # fn main() -> std::io::Result<()> {
+//! let path = std::env::temp_dir().join("interrupt_read_record");
+//! let clock = Arc::new(MockClock::new());
+//!
+//! // The script only starts once the recording has.
+//! let start = Arc::new(Barrier::new(2));
+//! let script = ScriptedReader::new(vec![
+//!     Step::WaitForSignal(start.clone()),
+//!     Step::Data(b"hello ".to_vec()),
+//!     Step::Sleep(Duration::from_millis(300)),
+//!     Step::Data(b"world".to_vec()),
+//!     Step::Error(ErrorKind::ConnectionReset),
+//!     Step::Sleep(Duration::from_millis(300)),
+//!     Step::Data(b"!".to_vec()),
+//! ]);
+//! let (mut reader, _interruptor) = Builder::new()
+//!     .clock(clock.clone())
+//!     .pair(script.clock(clock.clone()));
+//!
+//! reader.record_to(File::create(&path)?)?;
+//! start.wait();
+//!
+//! // Each sleep of the script passes while the reader waits for it.
+//! let watch = reader.state_watch();
+//! let sleep = || {
+//!     let (clock, watch) = (clock.clone(), watch.clone());
+//!     std::thread::spawn(move || clock.advance_when_waiting(&watch, Duration::from_millis(300)))
+//! };
+//!
+//! let mut recorded = vec![0; 6];
+//! reader.read_exact(&mut recorded)?;
+//! let sleeping = sleep();
+//! assert!(reader.read_to_end(&mut recorded).is_err());
+//! sleeping.join().unwrap();
+//! let sleeping = sleep();
+//! reader.read_to_end(&mut recorded)?;
+//! sleeping.join().unwrap();
+//! reader.stop_recording()?;
+//! assert_eq!(recorded, b"hello world!");
+//!
+//! // At 10 times the speed, the 300ms between chunks become 30ms.
+//! let replay = ReplayReader::from_reader(File::open(&path)?)?
+//!     .speed(10.0)
+//!     .clock(clock.clone());
+//! let (mut reader, _interruptor) = pair(replay);
+//!
+//! clock.advance(Duration::from_millis(30));
+//! let mut replayed = Vec::new();
+//! let err = reader.read_to_end(&mut replayed).unwrap_err();
+//! assert_eq!(err.kind(), ErrorKind::ConnectionReset);
+//! assert_eq!(replayed, b"hello world");
+//!
+//! let readiness = reader.poll_read_ready(Some(Duration::ZERO))?;
+//! assert_eq!(readiness, Readiness::TimedOut);
+//!
+//! clock.advance(Duration::from_millis(30));
+//! reader.read_to_end(&mut replayed)?;
+//! assert_eq!(replayed, recorded);
+//! # std::fs::remove_file(&path)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`tee`]: crate::tee
+//! [`InterruptReader::record_to`]: crate::InterruptReader::record_to
+//! [`pair`]: crate::pair
+use std::{
+    collections::VecDeque,
+    io::{self, ErrorKind, Read, Write},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::clock::Clock;
+
+/// The version of the format written by
+/// [`InterruptReader::record_to`]
+///
+/// [`InterruptReader::record_to`]: crate::InterruptReader::record_to
+pub const VERSION: u8 = 1;
+
+const MAGIC: &[u8; 5] = b"IRREC";
+
+/// The [`ErrorKind`]s that can be recorded, in the order of their
+/// numbers.
+const ERROR_KINDS: [ErrorKind; 20] = [
+    ErrorKind::NotFound,
+    ErrorKind::PermissionDenied,
+    ErrorKind::ConnectionRefused,
+    ErrorKind::ConnectionReset,
+    ErrorKind::ConnectionAborted,
+    ErrorKind::NotConnected,
+    ErrorKind::AddrInUse,
+    ErrorKind::AddrNotAvailable,
+    ErrorKind::BrokenPipe,
+    ErrorKind::AlreadyExists,
+    ErrorKind::WouldBlock,
+    ErrorKind::InvalidInput,
+    ErrorKind::InvalidData,
+    ErrorKind::TimedOut,
+    ErrorKind::WriteZero,
+    ErrorKind::Interrupted,
+    ErrorKind::Unsupported,
+    ErrorKind::UnexpectedEof,
+    ErrorKind::OutOfMemory,
+    ErrorKind::Other,
+];
+
+/// A reader that replays a recording made by
+/// [`InterruptReader::record_to`]
+///
+/// Every `read` returns the next chunk (or the rest of it, if it
+/// didn't fit in the last one) or error, after sleeping for as long
+/// as the source took to produce it. Interrupts aren't replayed, but
+/// the time before them is. Once the recording is over, every `read`
+/// returns `Ok(0)`.
+///
+/// Check out the [module level documentation] for more information.
+///
+/// [`InterruptReader::record_to`]: crate::InterruptReader::record_to
+/// [module level documentation]: self
+#[derive(Debug)]
+pub struct ReplayReader {
+    records: VecDeque<Record>,
+    speed: f64,
+    /// The clock of the waits, and when the last one ended.
+    clock: Option<(Arc<dyn Clock>, Instant)>,
+    /// Time from interrupts, to be waited before the next record.
+    delay: Duration,
+}
+
+impl ReplayReader {
+    /// Reads a whole recording from `reader`
+    ///
+    /// This returns an [`Err`] if reading fails, or of kind
+    /// [`ErrorKind::InvalidData`] if the recording isn't valid, or
+    /// has a version that isn't supported.
+    pub fn from_reader(mut reader: impl Read) -> io::Result<Self> {
+        let mut header = [0; MAGIC.len() + 1];
+        reader.read_exact(&mut header).map_err(invalid_on_eof)?;
+        if &header[..MAGIC.len()] != MAGIC {
+            return Err(invalid("not a recording"));
+        } else if header[MAGIC.len()] != VERSION {
+            return Err(invalid("unsupported recording version"));
+        }
+
+        let mut records = VecDeque::new();
+        loop {
+            let mut tag = [0; 1];
+            if reader.read(&mut tag)? == 0 {
+                break;
+            }
+
+            let delay = Duration::from_micros(u64::from_le_bytes(read_array(&mut reader)?));
+            let event = match tag[0] {
+                b'D' => ReplayEvent::Data(read_bytes(&mut reader)?),
+                b'E' => {
+                    let [kind] = read_array(&mut reader)?;
+                    let kind = ERROR_KINDS
+                        .get(kind as usize)
+                        .copied()
+                        .unwrap_or(ErrorKind::Other);
+                    let msg = String::from_utf8(read_bytes(&mut reader)?)
+                        .map_err(|_| invalid("error message is not UTF-8"))?;
+                    ReplayEvent::Err(kind, msg)
+                }
+                b'I' => ReplayEvent::Interrupt,
+                _ => return Err(invalid("unknown record")),
+            };
+
+            records.push_back(Record { delay, event });
+        }
+
+        Ok(Self {
+            records,
+            speed: 1.0,
+            clock: None,
+            delay: Duration::ZERO,
+        })
+    }
+
+    /// Replays the recording at `speed` times the original speed
+    ///
+    /// By default, this is `1.0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `speed` isn't a positive, finite number.
+    pub fn speed(mut self, speed: f64) -> Self {
+        assert!(
+            speed.is_finite() && speed > 0.0,
+            "the speed must be positive and finite"
+        );
+        self.speed = speed;
+        self
+    }
+
+    /// Waits between the records on `clock`, instead of in real time
+    ///
+    /// The waits are counted from the call to this function, one
+    /// after the other, so on a [`MockClock`], the records are only
+    /// replayed as the clock is moved past them.
+    ///
+    /// [`MockClock`]: crate::testing::MockClock
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        let now = clock.now();
+        self.clock = Some((clock, now));
+        self
+    }
+}
+
+impl Read for ReplayReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let Some(Record { delay, event }) = self.records.pop_front() else {
+                return Ok(0);
+            };
+
+            let delay = std::mem::take(&mut self.delay) + delay;
+            if let ReplayEvent::Interrupt = event {
+                self.delay = delay;
+                continue;
+            }
+            let delay = delay.div_f64(self.speed);
+            match &mut self.clock {
+                Some((clock, last)) => {
+                    *last = last.checked_add(delay).unwrap_or(*last);
+                    clock.wait_until(*last);
+                }
+                None => std::thread::sleep(delay),
+            }
+
+            match event {
+                ReplayEvent::Data(mut bytes) => {
+                    let len = bytes.len().min(buf.len());
+                    buf[..len].copy_from_slice(&bytes[..len]);
+                    if len < bytes.len() {
+                        let event = ReplayEvent::Data(bytes.split_off(len));
+                        let record = Record { delay: Duration::ZERO, event };
+                        self.records.push_front(record);
+                    }
+
+                    return Ok(len);
+                }
+                ReplayEvent::Err(kind, msg) => return Err(io::Error::new(kind, msg)),
+                ReplayEvent::Interrupt => unreachable!(),
+            }
+        }
+    }
+}
+
+/// Writes a recording into a sink
+///
+/// This is held by the worker thread's shared state, and set through
+/// [`InterruptReader::record_to`].
+///
+/// [`InterruptReader::record_to`]: crate::InterruptReader::record_to
+pub(crate) struct Recorder {
+    sink: Box<dyn Write + Send>,
+    clock: Arc<dyn Clock>,
+    last: Instant,
+}
+
+impl Recorder {
+    /// Returns a new `Recorder`, writing the header into the `sink`
+    pub(crate) fn new(mut sink: Box<dyn Write + Send>, clock: Arc<dyn Clock>) -> io::Result<Self> {
+        sink.write_all(MAGIC)?;
+        sink.write_all(&[VERSION])?;
+        let last = clock.now();
+        Ok(Self { sink, clock, last })
+    }
+
+    /// Records a chunk read from the source
+    pub(crate) fn data(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.start(b'D')?;
+        self.bytes(bytes)
+    }
+
+    /// Records an error returned by the source
+    pub(crate) fn error(&mut self, err: &io::Error) -> io::Result<()> {
+        let kind = ERROR_KINDS
+            .iter()
+            .position(|kind| *kind == err.kind())
+            .unwrap_or(ERROR_KINDS.len() - 1);
+
+        self.start(b'E')?;
+        self.sink.write_all(&[kind as u8])?;
+        self.bytes(err.to_string().as_bytes())
+    }
+
+    /// Records an interrupt received by the consumer
+    pub(crate) fn interrupt(&mut self) -> io::Result<()> {
+        self.start(b'I')
+    }
+
+    /// Flushes the sink
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush()
+    }
+
+    /// Writes the tag and delay of a record
+    fn start(&mut self, tag: u8) -> io::Result<()> {
+        let now = self.clock.now();
+        let delay = now.duration_since(std::mem::replace(&mut self.last, now));
+        let micros = u64::try_from(delay.as_micros()).unwrap_or(u64::MAX);
+
+        self.sink.write_all(&[tag])?;
+        self.sink.write_all(&micros.to_le_bytes())
+    }
+
+    /// Writes bytes, preceded by their length
+    fn bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let len = u32::try_from(bytes.len())
+            .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "chunk too big to record"))?;
+        self.sink.write_all(&len.to_le_bytes())?;
+        self.sink.write_all(bytes)
+    }
+}
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recorder")
+            .field("last", &self.last)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+struct Record {
+    /// The time since the previous record.
+    delay: Duration,
+    event: ReplayEvent,
+}
+
+#[derive(Debug)]
+enum ReplayEvent {
+    Data(Vec<u8>),
+    Err(ErrorKind, String),
+    Interrupt,
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut array = [0; N];
+    reader.read_exact(&mut array).map_err(invalid_on_eof)?;
+    Ok(array)
+}
+
+fn read_bytes(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let len = u32::from_le_bytes(read_array(reader)?);
+    let mut bytes = Vec::new();
+    reader
+        .take(len as u64)
+        .read_to_end(&mut bytes)
+        .map_err(invalid_on_eof)?;
+
+    if bytes.len() == len as usize {
+        Ok(bytes)
+    } else {
+        Err(invalid("recording ends in the middle of a record"))
+    }
+}
+
+/// A recording that ends early is invalid
+fn invalid_on_eof(err: io::Error) -> io::Error {
+    match err.kind() {
+        ErrorKind::UnexpectedEof => invalid("recording ends in the middle of a record"),
+        _ => err,
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg.to_string())
+}
@@ -0,0 +1,277 @@
+//! Reading from multiple sources at once
+//!
+//! When supervising a number of sources, like the outputs of several
+//! child processes, what you usually want is a single loop that gets
+//! the chunks of all of them as they arrive. A [`SelectReader`] does
+//! that: every source added to it through
+//! [`SelectReader::add_source`] is read from its own
+//! [`InterruptReader`], and the chunks are handed out by
+//! [`SelectReader::recv`], tagged with the [`SourceId`] of where
+//! they came from.
+//!
+//! A single [`SelectInterruptor`] interrupts the whole thing, and
+//! each source ends on its own, without holding up the others.
+//!
+//! # Backpressure
+//!
+//! The chunks are [`Chunk`]s, taken straight out of the buffers of
+//! each `InterruptReader`. Since those only have a few buffers each,
+//! a source stops being read from while the chunks it sent are still
+//! queued, or held by the consumer, so memory usage stays bounded.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::{collections::HashMap, io::ErrorKind, time::Duration};
+//!
+//! use interrupt_read::{
+//!     select::{SelectReader, Tagged},
+//!     testing::{ScriptedReader, Step},
+//! };
+//!
+//! let (mut select, interruptor) = SelectReader::new();
+//! let sources = [
+//!     vec![
+//!         Step::Data(b"a1 ".to_vec()),
+//!         Step::Sleep(Duration::from_millis(20)),
+//!         Step::Data(b"a2 ".to_vec()),
+//!         Step::Data(b"a3".to_vec()),
+//!     ],
+//!     // This one fails right away, and then goes on.
+//!     vec![
+//!         Step::Error(ErrorKind::ConnectionReset),
+//!         Step::Data(b"b1".to_vec()),
+//!     ],
+//!     vec![
+//!         Step::Sleep(Duration::from_millis(10)),
+//!         Step::Data(b"c1 ".to_vec()),
+//!         Step::Data(b"c2".to_vec()),
+//!     ],
+//! ];
+//! let ids: Vec<_> = sources
+//!     .into_iter()
+//!     .map(|steps| select.add_source(ScriptedReader::new(steps)))
+//!     .collect();
+//!
+//! let first = select.recv().unwrap();
+//!
+//! // Interrupts take precedence over the chunks that already arrived.
+//! interruptor.interrupt().unwrap();
+//! assert!(matches!(select.recv(), Some(Tagged::Interrupted)));
+//!
+//! let mut data: HashMap<_, Vec<u8>> = HashMap::new();
+//! let mut errors = Vec::new();
+//! let mut ended = Vec::new();
+//! for tagged in std::iter::once(first).chain(std::iter::from_fn(|| select.recv())) {
+//!     match tagged {
+//!         Tagged::Data { source, data: chunk } => {
+//!             data.entry(source).or_default().extend_from_slice(&chunk)
+//!         }
+//!         Tagged::Err(source, err) => errors.push((source, err.kind())),
+//!         Tagged::Eof(source) => ended.push(source),
+//!         Tagged::Interrupted => unreachable!(),
+//!     }
+//! }
+//!
+//! assert_eq!(data[&ids[0]], b"a1 a2 a3");
+//! assert_eq!(data[&ids[1]], b"b1");
+//! assert_eq!(data[&ids[2]], b"c1 c2");
+//! assert_eq!(errors, [(ids[1], ErrorKind::ConnectionReset)]);
+//!
+//! ended.sort();
+//! assert_eq!(ended, ids);
+//! ```
+use std::{
+    io::{self, Read},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering::Relaxed},
+        mpsc,
+    },
+};
+
+use crate::{Chunk, InterruptReader, InterruptSendError};
+
+/// Reads from multiple sources, handing out their chunks as they
+/// arrive
+///
+/// Check out the [module level documentation] for more information.
+///
+/// [module level documentation]: self
+#[derive(Debug)]
+pub struct SelectReader {
+    message_tx: mpsc::Sender<Message>,
+    message_rx: mpsc::Receiver<Message>,
+    interrupts: Arc<AtomicUsize>,
+    /// How many sources were added.
+    sources: usize,
+    /// How many sources haven't ended yet.
+    live: usize,
+}
+
+impl SelectReader {
+    /// Returns a new `SelectReader`, without any sources, and the
+    /// [`SelectInterruptor`] for it
+    pub fn new() -> (Self, SelectInterruptor) {
+        let (message_tx, message_rx) = mpsc::channel();
+        let interrupts = Arc::new(AtomicUsize::new(0));
+        let interruptor = SelectInterruptor {
+            message_tx: message_tx.clone(),
+            interrupts: interrupts.clone(),
+        };
+
+        let select = Self {
+            message_tx,
+            message_rx,
+            interrupts,
+            sources: 0,
+            live: 0,
+        };
+
+        (select, interruptor)
+    }
+
+    /// Adds a source, returning the [`SourceId`] that its chunks are
+    /// tagged with
+    ///
+    /// The source is read from an [`InterruptReader`] of its own, and
+    /// another thread hands its chunks over to the `SelectReader`.
+    /// It ends at the first `Ok(0)`, after which [`Tagged::Eof`] is
+    /// returned for it. Errors don't end it.
+    pub fn add_source<R: Read + Send + 'static>(&mut self, reader: R) -> SourceId {
+        let source = SourceId(self.sources);
+        self.sources += 1;
+        self.live += 1;
+
+        let (reader, _) = crate::pair(reader);
+        let message_tx = self.message_tx.clone();
+        std::thread::spawn(move || relay(source, reader, message_tx));
+
+        source
+    }
+
+    /// Returns the next chunk, error or end of a source, or an
+    /// interrupt
+    ///
+    /// This blocks until one of those arrives. Like with an
+    /// [`InterruptReader`], interrupts take precedence over what
+    /// already arrived, which is returned afterwards.
+    ///
+    /// Once every source has ended, this returns [`None`], unless an
+    /// interrupt is still pending. If there are no sources, it only
+    /// waits for interrupts.
+    pub fn recv(&mut self) -> Option<Tagged> {
+        loop {
+            if self.take_interrupt() {
+                return Some(Tagged::Interrupted);
+            } else if self.live == 0 && self.sources > 0 {
+                return None;
+            }
+
+            // The SelectReader holds a Sender, so this never fails.
+            match self.message_rx.recv().ok()? {
+                Message::Tagged(tagged) => {
+                    if let Tagged::Eof(_) = tagged {
+                        self.live -= 1;
+                    }
+                    break Some(tagged);
+                }
+                // The interrupt is taken at the start of the loop.
+                Message::Wake => {}
+            }
+        }
+    }
+
+    /// How many sources haven't ended yet
+    pub fn live_sources(&self) -> usize {
+        self.live
+    }
+
+    /// Takes a pending interrupt, returning `false` if there is none
+    fn take_interrupt(&self) -> bool {
+        self.interrupts
+            .fetch_update(Relaxed, Relaxed, |interrupts| interrupts.checked_sub(1))
+            .is_ok()
+    }
+}
+
+/// The interruptor of a [`SelectReader`]
+///
+/// This is acquired through [`SelectReader::new`].
+#[derive(Debug, Clone)]
+pub struct SelectInterruptor {
+    message_tx: mpsc::Sender<Message>,
+    interrupts: Arc<AtomicUsize>,
+}
+
+impl SelectInterruptor {
+    /// Interrupts the [`SelectReader`]
+    ///
+    /// This makes the next call to [`SelectReader::recv`] return
+    /// [`Tagged::Interrupted`]. Nothing that was read from the
+    /// sources is lost.
+    pub fn interrupt(&self) -> Result<(), InterruptSendError> {
+        self.interrupts.fetch_add(1, Relaxed);
+        self.message_tx
+            .send(Message::Wake)
+            .map_err(|_| InterruptSendError)
+    }
+}
+
+/// Identifies a source of a [`SelectReader`]
+///
+/// This is acquired through [`SelectReader::add_source`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SourceId(usize);
+
+impl SourceId {
+    /// The order in which the source was added, starting at `0`
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// What was received by [`SelectReader::recv`]
+#[derive(Debug)]
+pub enum Tagged {
+    /// A chunk read from a source.
+    Data {
+        /// Where the chunk came from.
+        source: SourceId,
+        /// What was read.
+        data: Chunk,
+    },
+    /// A source reached the end, so nothing else comes from it.
+    Eof(SourceId),
+    /// A source returned an error.
+    Err(SourceId, io::Error),
+    /// The [`SelectInterruptor`] interrupted the `SelectReader`.
+    Interrupted,
+}
+
+/// Hands the chunks of a source over to the [`SelectReader`], until
+/// it ends or the `SelectReader` is dropped
+fn relay<R: Read>(
+    source: SourceId,
+    mut reader: InterruptReader<R>,
+    message_tx: mpsc::Sender<Message>,
+) {
+    loop {
+        let (tagged, end) = match reader.next_chunk() {
+            Ok(Some(data)) => (Tagged::Data { source, data }, false),
+            Ok(None) => (Tagged::Eof(source), true),
+            Err(err) => (Tagged::Err(source, err), false),
+        };
+
+        if message_tx.send(Message::Tagged(tagged)).is_err() || end {
+            break;
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Message {
+    Tagged(Tagged),
+    /// Wakes up the [`SelectReader`], for an interrupt.
+    Wake,
+}
@@ -0,0 +1,175 @@
+//! Interruptable reading from a [`TcpStream`]
+//!
+//! Unlike the generic [`pair`] function, which can only make the
+//! _consumer_ stop waiting, this module can also make the worker
+//! thread stop waiting, since sockets have a clean way of cancelling
+//! a blocked `read`: calling [`TcpStream::shutdown`] with
+//! [`Shutdown::Read`] on a clone of the stream.
+//!
+//! [`pair`]: crate::pair
+use std::{
+    io,
+    net::{Shutdown, TcpStream},
+};
+
+use crate::{Interruptor, SourceReader};
+
+/// The reader returned by [`pair`], which keeps a duplicate of the
+/// socket
+#[cfg(unix)]
+pub type TcpReader = SourceReader<TcpStream, std::os::fd::OwnedFd>;
+
+/// The reader returned by [`pair`], which keeps a duplicate of the
+/// socket
+#[cfg(windows)]
+pub type TcpReader = SourceReader<TcpStream, std::os::windows::io::OwnedSocket>;
+
+/// Returns a pair of an [`InterruptReader`] and a
+/// [`TcpInterruptor`].
+///
+/// This works just like [`interrupt_read::pair`], but the returned
+/// interruptor holds a clone of the `stream` (acquired through
+/// [`TcpStream::try_clone`]), which it uses in order to shut down
+/// the read half of the connection.
+///
+/// The reader is a [`TcpReader`], which also keeps a duplicate of
+/// the socket, for things like calling `getsockopt` on it (see
+/// [`SourceReader`]).
+///
+/// This function returns an [`Err`] if the stream couldn't be
+/// cloned.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::{
+///     io::{BufRead, Read},
+///     net::{TcpListener, TcpStream},
+/// };
+///
+/// use interrupt_read::{ReaderState, is_interrupt, tcp};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let listener = TcpListener::bind("127.0.0.1:0")?;
+/// let stream = TcpStream::connect(listener.local_addr()?)?;
+/// // The other side never sends anything.
+/// let (_peer, _) = listener.accept()?;
+///
+/// let (mut reader, interruptor) = tcp::pair(stream)?;
+/// let watch = reader.state_watch();
+///
+/// let join_handle = std::thread::spawn(move || {
+///     let mut line = String::new();
+///     let result = reader.read_line(&mut line);
+///     (result, reader)
+/// });
+///
+/// while watch.get() != ReaderState::WaitingForData {
+///     std::thread::yield_now();
+/// }
+/// interruptor.interrupt()?;
+///
+/// let (result, mut reader) = join_handle.join().unwrap();
+/// assert!(is_interrupt(&result.unwrap_err()));
+///
+/// // The read half has been shut down, so this is EOF now.
+/// assert_eq!(reader.read(&mut [0; 16])?, 0);
+///
+/// // And the worker thread can be joined right away.
+/// let _stream = reader.into_inner().unwrap();
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`interrupt_read::pair`]: crate::pair
+/// [`InterruptReader`]: crate::InterruptReader
+pub fn pair(stream: TcpStream) -> io::Result<(TcpReader, TcpInterruptor)> {
+    #[cfg(unix)]
+    let duplicate = |stream: &TcpStream| std::os::fd::AsFd::as_fd(stream).try_clone_to_owned();
+    #[cfg(windows)]
+    let duplicate =
+        |stream: &TcpStream| std::os::windows::io::AsSocket::as_socket(stream).try_clone_to_owned();
+
+    let clone = stream.try_clone()?;
+    let socket = duplicate(&stream)?;
+    let (reader, interruptor) = crate::pair(stream);
+
+    Ok((
+        SourceReader::new(reader, socket, duplicate),
+        TcpInterruptor { interruptor, stream: clone },
+    ))
+}
+
+/// An interruptor for a [`TcpReader`].
+///
+/// In contrast with the regular [`Interruptor`], this one is capable
+/// of waking up the worker thread as well, by shutting down the read
+/// half of the connection.
+#[derive(Debug)]
+pub struct TcpInterruptor {
+    interruptor: Interruptor,
+    stream: TcpStream,
+}
+
+impl TcpInterruptor {
+    /// Interrupts the [`InterruptReader`] and shuts down the read
+    /// half of the connection
+    ///
+    /// This first queues an interrupt, just like
+    /// [`Interruptor::interrupt`], and then calls
+    /// [`TcpStream::shutdown`] with [`Shutdown::Read`], which wakes
+    /// the worker thread up if it was blocked, without waiting for
+    /// the next packet to arrive.
+    ///
+    /// The exact sequence of events observed by the `InterruptReader`
+    /// is:
+    ///
+    /// - Any data that the worker thread had already sent is served
+    ///   as normal.
+    /// - The next read operation that would otherwise block returns
+    ///   an [`Err`] with a payload of [`InterruptReceived`].
+    /// - Every read operation after that returns `Ok(0)`, like with
+    ///   any other shut down socket (although, depending on the
+    ///   platform, some bytes that were already in flight may still
+    ///   be served first).
+    ///
+    /// Unlike with the regular `Interruptor`, this means that this is
+    /// _not_ part of a read/interrupt cycle, the connection can no
+    /// longer be read from, so you should probably only use this
+    /// when you mean to stop reading for good.
+    ///
+    /// This returns an [`Err`] if the `InterruptReader` has been
+    /// dropped, or if the socket couldn't be shut down.
+    ///
+    /// [`InterruptReceived`]: crate::InterruptReceived
+    /// [`InterruptReader`]: crate::InterruptReader
+    pub fn interrupt(&self) -> io::Result<()> {
+        self.interruptor.interrupt().map_err(io::Error::other)?;
+        self.hard_cancel()
+    }
+
+    /// Shuts down the read half of the connection, without
+    /// interrupting
+    ///
+    /// This wakes up the worker thread, which will then hand out
+    /// `Ok(0)` to every read operation, so this is meant for the
+    /// permanent teardown of the connection. Unlike
+    /// [`TcpInterruptor::interrupt`], no [`InterruptReceived`] error
+    /// is produced.
+    ///
+    /// [`InterruptReceived`]: crate::InterruptReceived
+    pub fn hard_cancel(&self) -> io::Result<()> {
+        self.stream.shutdown(Shutdown::Read)
+    }
+
+    /// The regular [`Interruptor`] of the [`InterruptReader`]
+    ///
+    /// Interrupting through it will start a regular read/interrupt
+    /// cycle, without shutting down the connection, but the worker
+    /// thread will only wake up when the next packet arrives.
+    ///
+    /// [`InterruptReader`]: crate::InterruptReader
+    pub fn interruptor(&self) -> &Interruptor {
+        &self.interruptor
+    }
+}
@@ -0,0 +1,174 @@
+//! Mirroring everything that is read into a [`Write`] sink
+//!
+//! This is mostly useful for debugging, e.g. for keeping a
+//! transcript of exactly what came off the wire during a protocol
+//! session, without having to change the code that does the parsing.
+//!
+//! A [`TeeReader`] is acquired through [`InterruptReader::tee`], and
+//! it writes every byte that is delivered to the consumer into the
+//! sink. For [`Read`] methods, those are the bytes put in the
+//! caller's buffer, and for [`BufRead`], those are the bytes passed
+//! to [`consume`], not the ones returned by [`fill_buf`], so bytes
+//! that are looked at but not used are not mirrored.
+//!
+//! [`consume`]: BufRead::consume
+//! [`fill_buf`]: BufRead::fill_buf
+use std::io::{self, BufRead, IoSliceMut, Read, Write};
+
+use crate::InterruptReader;
+
+/// What a [`TeeReader`] does when writing to its sink fails
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SinkErrorPolicy {
+    /// Return the error from the next read operation.
+    ///
+    /// Since the bytes were already delivered when the error came
+    /// up, it is only returned by the read operation after that one,
+    /// so no data is lost. This is the default.
+    #[default]
+    Propagate,
+    /// Ignore the error, and keep reading as normal.
+    Ignore,
+}
+
+/// An [`InterruptReader`] that mirrors what is read into a sink
+///
+/// This is acquired through [`InterruptReader::tee`], check out its
+/// documentation for more information.
+#[derive(Debug)]
+pub struct TeeReader<R, W> {
+    reader: InterruptReader<R>,
+    sink: W,
+    policy: SinkErrorPolicy,
+    error: Option<io::Error>,
+}
+
+impl<R: Read, W: Write> TeeReader<R, W> {
+    pub(crate) fn new(reader: InterruptReader<R>, sink: W) -> Self {
+        Self {
+            reader,
+            sink,
+            policy: SinkErrorPolicy::default(),
+            error: None,
+        }
+    }
+
+    /// What to do when writing to the sink fails
+    ///
+    /// By default, this is [`SinkErrorPolicy::Propagate`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    ///
+    /// use interrupt_read::{pair, tee::SinkErrorPolicy};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (reader, _interruptor) = pair(b"hello world".as_slice());
+    /// // This sink only has room for 4 bytes.
+    /// let mut transcript = [0; 4];
+    /// let mut reader = reader.tee(transcript.as_mut_slice());
+    ///
+    /// // The bytes are delivered, and the error comes after.
+    /// let mut buf = [0; 5];
+    /// assert_eq!(reader.read(&mut buf)?, 5);
+    /// assert!(reader.read(&mut buf).is_err());
+    ///
+    /// // Or you could just ignore it.
+    /// let mut reader = reader.sink_errors(SinkErrorPolicy::Ignore);
+    /// let mut rest = String::new();
+    /// reader.read_to_string(&mut rest)?;
+    /// assert_eq!(rest, " world");
+    ///
+    /// drop(reader);
+    /// assert_eq!(&transcript, b"hell");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sink_errors(mut self, policy: SinkErrorPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// A reference to the inner [`InterruptReader`]
+    pub fn reader(&self) -> &InterruptReader<R> {
+        &self.reader
+    }
+
+    /// A mutable reference to the inner [`InterruptReader`]
+    ///
+    /// Note that bytes read directly through it are not mirrored
+    /// into the sink.
+    pub fn reader_mut(&mut self) -> &mut InterruptReader<R> {
+        &mut self.reader
+    }
+
+    /// A reference to the sink
+    pub fn sink(&self) -> &W {
+        &self.sink
+    }
+
+    /// A mutable reference to the sink
+    pub fn sink_mut(&mut self) -> &mut W {
+        &mut self.sink
+    }
+
+    /// Unwraps this `TeeReader`, returning the [`InterruptReader`]
+    /// and the sink
+    ///
+    /// If writing to the sink failed and the error wasn't returned
+    /// yet, it is lost.
+    pub fn into_inner(self) -> (InterruptReader<R>, W) {
+        (self.reader, self.sink)
+    }
+
+    /// Deals with the result of writing to the sink
+    fn mirrored(&mut self, result: io::Result<()>) {
+        if let Err(err) = result
+            && self.policy == SinkErrorPolicy::Propagate
+        {
+            self.error.get_or_insert(err);
+        }
+    }
+
+    /// Returns the error from the sink, if there is one
+    fn take_error(&mut self) -> io::Result<()> {
+        self.error.take().map_or(Ok(()), Err)
+    }
+}
+
+impl<R: Read, W: Write> Read for TeeReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_vectored(&mut [IoSliceMut::new(buf)])
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        self.take_error()?;
+
+        let len = self.reader.read_vectored(bufs)?;
+        let mut left = len;
+        for buf in bufs.iter() {
+            let len = buf.len().min(left);
+            let result = self.sink.write_all(&buf[..len]);
+            self.mirrored(result);
+            left -= len;
+        }
+
+        Ok(len)
+    }
+}
+
+impl<R: Read, W: Write> BufRead for TeeReader<R, W> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.take_error()?;
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        let amount = amount.min(self.reader.buffer().len());
+        let result = self.sink.write_all(&self.reader.buffer()[..amount]);
+        self.mirrored(result);
+        self.reader.consume(amount);
+    }
+}
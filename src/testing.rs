@@ -0,0 +1,469 @@
+//! Readers for testing code that uses [`InterruptReader`]s
+//!
+//! Testing how an application deals with interrupts means having a
+//! source that behaves in a predictable way: one that sends some
+//! chunks and then blocks, one that fails at a specific point, or one
+//! that only produces data when the test says so. This module has
+//! two readers for that, both of which go straight into [`pair`]:
+//!
+//! - [`ScriptedReader`], which follows a list of [`Step`]s.
+//! - [`BlockingGate`], which blocks until bytes are handed to it
+//!   through its [`GateHandle`].
+//!
+//! And a [`MockClock`], for testing timeouts without waiting for
+//! them.
+//!
+//! # Examples
+//!
+//! Testing the interrupt handling of an application, without relying
+//! on any sleeps:
+//!
+//! ```rust
+//! use std::io::{BufRead, Read};
+//!
+//! use interrupt_read::{InterruptReader, is_interrupt, pair, testing::BlockingGate};
+//!
+//! /// Reads a command, returning [`None`] if it was interrupted.
+//! fn read_command(reader: &mut InterruptReader<impl Read>) -> std::io::Result<Option<String>> {
+//!     let mut line = String::new();
+//!     match reader.read_line(&mut line) {
+//!         Ok(_) => Ok(Some(line.trim().to_string())),
+//!         Err(err) if is_interrupt(&err) => Ok(None),
+//!         Err(err) => Err(err),
+//!     }
+//! }
+//!
+//! # fn main() -> std::io::Result<()> {
+//! let (gate, handle) = BlockingGate::new();
+//! let (mut reader, interruptor) = pair(gate);
+//!
+//! handle.release(b"ls\n");
+//! assert_eq!(read_command(&mut reader)?, Some("ls".to_string()));
+//!
+//! // The gate is still closed, so an interrupt is the only way out.
+//! interruptor.interrupt().unwrap();
+//! assert_eq!(read_command(&mut reader)?, None);
+//!
+//! // And reading goes on as normal after that.
+//! handle.release(b"cd\n");
+//! assert_eq!(read_command(&mut reader)?, Some("cd".to_string()));
+//!
+//! handle.close();
+//! assert_eq!(read_command(&mut reader)?, Some(String::new()));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`InterruptReader`]: crate::InterruptReader
+//! [`pair`]: crate::pair
+use std::{
+    collections::VecDeque,
+    io::{self, ErrorKind, Read},
+    sync::{Arc, Barrier, Condvar, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{ReaderState, StateWatch, clock::Clock};
+
+/// How often waits on a [`MockClock`] check if it has moved.
+const MOCK_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A step taken by a [`ScriptedReader`]
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// Return these bytes.
+    ///
+    /// If they don't fit in the buffer of a `read`, the rest is
+    /// returned by the next one.
+    Data(Vec<u8>),
+    /// Sleep for this long, on the [`ScriptedReader::clock`], then
+    /// go on to the next step.
+    Sleep(Duration),
+    /// Wait on the [`Barrier`], then go on to the next step.
+    ///
+    /// This lets a test control exactly when the reader goes on,
+    /// by waiting on the same barrier.
+    WaitForSignal(Arc<Barrier>),
+    /// Return an error of this kind.
+    Error(ErrorKind),
+    /// Return `Ok(0)`, the end of the stream.
+    ///
+    /// If there are more steps after this one, they are taken by the
+    /// next `read`.
+    Eof,
+    /// Return as much of these bytes as fits in the buffer of a
+    /// `read`, but claim to have read one byte more than the buffer
+    /// can hold, like a buggy source would.
+    ///
+    /// The rest of the bytes are dropped.
+    OverReport(Vec<u8>),
+    /// Panic with this message.
+    Panic(&'static str),
+}
+
+/// A reader that follows a script of [`Step`]s
+///
+/// Every `read` takes steps until one of them returns something.
+/// Once the script is over, every `read` returns `Ok(0)`.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::io::{ErrorKind, Read};
+///
+/// use interrupt_read::{
+///     pair,
+///     testing::{ScriptedReader, Step},
+/// };
+///
+/// let (mut reader, _interruptor) = pair(ScriptedReader::new(vec![
+///     Step::Data(b"hello".to_vec()),
+///     Step::Error(ErrorKind::ConnectionReset),
+///     Step::Data(b"world".to_vec()),
+/// ]));
+///
+/// let mut buf = [0; 16];
+/// assert_eq!(reader.read(&mut buf).unwrap(), 5);
+/// let err = reader.read(&mut buf).unwrap_err();
+/// assert_eq!(err.kind(), ErrorKind::ConnectionReset);
+/// assert_eq!(reader.read(&mut buf).unwrap(), 5);
+/// assert_eq!(&buf[..5], b"world");
+/// assert_eq!(reader.read(&mut buf).unwrap(), 0);
+/// ```
+///
+/// A panic in the source is returned as an error, followed by the
+/// end of the stream:
+///
+/// ```rust
+/// use std::io::Read;
+///
+/// use interrupt_read::{
+///     pair,
+///     testing::{ScriptedReader, Step},
+/// };
+///
+/// let (mut reader, _interruptor) = pair(ScriptedReader::new(vec![Step::Panic("oh no")]));
+///
+/// assert!(reader.read(&mut [0; 16]).is_err());
+/// assert_eq!(reader.read(&mut [0; 16]).unwrap(), 0);
+/// assert!(reader.into_inner().is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ScriptedReader {
+    steps: VecDeque<Step>,
+    /// The clock of the sleeps, and when the last one ended.
+    clock: Option<(Arc<dyn Clock>, Instant)>,
+}
+
+impl ScriptedReader {
+    /// Returns a new `ScriptedReader`, which will take these `steps`
+    pub fn new(steps: Vec<Step>) -> Self {
+        Self { steps: steps.into(), clock: None }
+    }
+
+    /// Takes the [`Step::Sleep`]s on `clock`, instead of in real time
+    ///
+    /// The sleeps are counted from the call to this function, one
+    /// after the other, rather than from when the worker thread gets
+    /// to them. So on a [`MockClock`], a sleep lasts until the clock
+    /// is moved past the end of it, and moving the clock before the
+    /// worker thread started reading has the same effect.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{io::Read, sync::Arc, time::Duration};
+    ///
+    /// use interrupt_read::{
+    ///     Readiness, pair,
+    ///     testing::{MockClock, ScriptedReader, Step},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let clock = Arc::new(MockClock::new());
+    /// let script = ScriptedReader::new(vec![
+    ///     Step::Sleep(Duration::from_secs(60)),
+    ///     Step::Data(b"late".to_vec()),
+    /// ]);
+    /// let (mut reader, _interruptor) = pair(script.clock(clock.clone()));
+    ///
+    /// let readiness = reader.poll_read_ready(Some(Duration::ZERO))?;
+    /// assert_eq!(readiness, Readiness::TimedOut);
+    ///
+    /// clock.advance(Duration::from_secs(60));
+    /// let mut buf = [0; 16];
+    /// assert_eq!(reader.read(&mut buf)?, 4);
+    /// assert_eq!(&buf[..4], b"late");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        let now = clock.now();
+        self.clock = Some((clock, now));
+        self
+    }
+
+    /// The steps that haven't been taken yet
+    pub fn remaining(&self) -> impl ExactSizeIterator<Item = &Step> {
+        self.steps.iter()
+    }
+}
+
+impl Read for ScriptedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let Some(step) = self.steps.pop_front() else {
+                return Ok(0);
+            };
+
+            match step {
+                // An empty read would look like the end of the stream.
+                Step::Data(bytes) if bytes.is_empty() => {}
+                Step::Data(mut bytes) => {
+                    let len = bytes.len().min(buf.len());
+                    buf[..len].copy_from_slice(&bytes[..len]);
+                    if len < bytes.len() {
+                        self.steps.push_front(Step::Data(bytes.split_off(len)));
+                    }
+
+                    return Ok(len);
+                }
+                Step::Sleep(duration) => match &mut self.clock {
+                    Some((clock, last)) => {
+                        *last = last.checked_add(duration).unwrap_or(*last);
+                        clock.wait_until(*last);
+                    }
+                    None => std::thread::sleep(duration),
+                },
+                Step::WaitForSignal(barrier) => _ = barrier.wait(),
+                Step::Error(kind) => return Err(kind.into()),
+                Step::Eof => return Ok(0),
+                Step::OverReport(bytes) => {
+                    let len = bytes.len().min(buf.len());
+                    buf[..len].copy_from_slice(&bytes[..len]);
+                    return Ok(buf.len() + 1);
+                }
+                Step::Panic(msg) => panic!("{msg}"),
+            }
+        }
+    }
+}
+
+/// A reader that blocks until bytes are released through its
+/// [`GateHandle`]
+///
+/// Once the handle is dropped, or [`GateHandle::close`] is called,
+/// the bytes that were released are still returned, and then the
+/// stream ends.
+#[derive(Debug)]
+pub struct BlockingGate(Arc<Gate>);
+
+impl BlockingGate {
+    /// Returns a new `BlockingGate`, and the [`GateHandle`] that
+    /// controls it
+    pub fn new() -> (Self, GateHandle) {
+        let gate = Arc::new(Gate::default());
+        (Self(gate.clone()), GateHandle(gate))
+    }
+}
+
+impl Read for BlockingGate {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.0.state();
+        while state.bytes.is_empty() && !state.closed {
+            state = self
+                .0
+                .released
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+
+        let len = state.bytes.len().min(buf.len());
+        for (byte, released) in buf.iter_mut().zip(state.bytes.drain(..len)) {
+            *byte = released;
+        }
+
+        Ok(len)
+    }
+}
+
+/// The handle that controls a [`BlockingGate`]
+///
+/// This is acquired through [`BlockingGate::new`]. Dropping it closes
+/// the gate.
+#[derive(Debug)]
+pub struct GateHandle(Arc<Gate>);
+
+impl GateHandle {
+    /// Lets these bytes through the [`BlockingGate`]
+    pub fn release(&self, bytes: impl AsRef<[u8]>) {
+        self.0.state().bytes.extend(bytes.as_ref());
+        self.0.released.notify_all();
+    }
+
+    /// Ends the stream, once the bytes that were released are read
+    pub fn close(&self) {
+        self.0.state().closed = true;
+        self.0.released.notify_all();
+    }
+
+    /// How many released bytes haven't been read yet
+    pub fn unread(&self) -> usize {
+        self.0.state().bytes.len()
+    }
+}
+
+impl Drop for GateHandle {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+#[derive(Debug, Default)]
+struct Gate {
+    state: Mutex<GateState>,
+    released: Condvar,
+}
+
+impl Gate {
+    fn state(&self) -> MutexGuard<'_, GateState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[derive(Debug, Default)]
+struct GateState {
+    bytes: VecDeque<u8>,
+    closed: bool,
+}
+
+/// A [`Clock`] that only moves when told to
+///
+/// It starts out at the moment it was created, and stays there until
+/// [`MockClock::advance`] is called, which wakes up everything that
+/// waits for a deadline that was reached. Pass it to
+/// [`Builder::clock`] in order to test timeouts without waiting for
+/// them. Check out the [`clock`] module for an example.
+///
+/// [`Builder::clock`]: crate::Builder::clock
+/// [`clock`]: crate::clock
+#[derive(Debug)]
+pub struct MockClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+    advanced: Condvar,
+}
+
+impl MockClock {
+    /// Returns a new `MockClock`, at the current time
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+            advanced: Condvar::new(),
+        }
+    }
+
+    /// Moves the clock forward by `duration`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use interrupt_read::{clock::Clock, testing::MockClock};
+    ///
+    /// let clock = MockClock::new();
+    /// let start = clock.now();
+    /// assert_eq!(clock.now(), start);
+    ///
+    /// clock.advance(Duration::from_secs(60));
+    /// assert_eq!(clock.now() - start, Duration::from_secs(60));
+    /// assert_eq!(clock.elapsed(), Duration::from_secs(60));
+    /// ```
+    pub fn advance(&self, duration: Duration) {
+        *self.lock() += duration;
+        self.advanced.notify_all();
+    }
+
+    /// Waits until the [`InterruptReader`] behind `watch` is waiting
+    /// for data, then moves the clock forward by `duration`
+    ///
+    /// This is how a test makes a timeout run out on the thread that
+    /// is reading, which is stuck in that wait. It is usually called
+    /// from another thread, right before the read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{sync::Arc, time::Duration};
+    ///
+    /// use interrupt_read::{
+    ///     Builder, Readiness,
+    ///     testing::{BlockingGate, MockClock},
+    /// };
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let clock = Arc::new(MockClock::new());
+    /// let (gate, _handle) = BlockingGate::new();
+    /// let (mut reader, _interruptor) = Builder::new().clock(clock.clone()).pair(gate);
+    ///
+    /// let timeout = Duration::from_secs(30);
+    /// std::thread::spawn({
+    ///     let (clock, watch) = (clock.clone(), reader.state_watch());
+    ///     move || clock.advance_when_waiting(&watch, timeout)
+    /// });
+    ///
+    /// assert_eq!(reader.poll_read_ready(Some(timeout))?, Readiness::TimedOut);
+    /// assert_eq!(clock.elapsed(), timeout);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`InterruptReader`]: crate::InterruptReader
+    pub fn advance_when_waiting(&self, watch: &StateWatch, duration: Duration) {
+        while watch.get() != ReaderState::WaitingForData {
+            std::thread::yield_now();
+        }
+        self.advance(duration);
+    }
+
+    /// How far the clock was moved since it was created
+    pub fn elapsed(&self) -> Duration {
+        *self.lock()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Duration> {
+        self.elapsed.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + *self.lock()
+    }
+
+    fn wait_until(&self, deadline: Instant) {
+        let mut elapsed = self.lock();
+        while self.start + *elapsed < deadline {
+            elapsed = self
+                .advanced
+                .wait(elapsed)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    fn timeout_until(&self, deadline: Instant) -> Duration {
+        if self.now() >= deadline {
+            Duration::ZERO
+        } else {
+            MOCK_POLL_INTERVAL
+        }
+    }
+}
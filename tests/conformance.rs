@@ -0,0 +1,165 @@
+//! Checks that [`InterruptReader`] follows the contracts of [`Read`]
+//! and [`BufRead`] like [`BufReader`] does
+//!
+//! Every routine here is generic over the reader, and is run on both
+//! a `BufReader` and an `InterruptReader` over the same source, which
+//! have to see the same results.
+//!
+//! [`InterruptReader`]: interrupt_read::InterruptReader
+use std::{
+    fmt::Debug,
+    io::{self, BufRead, BufReader, Cursor, ErrorKind, Read},
+};
+
+use interrupt_read::{
+    pair,
+    testing::{ScriptedReader, Step},
+};
+
+/// Runs `routine` on a [`BufReader`] and an `InterruptReader` over
+/// the source returned by `source`, checking that they agree, and
+/// returns what they saw
+fn conform<S, T>(source: impl Fn() -> S, routine: impl Fn(&mut dyn BufRead) -> io::Result<T>) -> T
+where
+    S: Read + Send + 'static,
+    T: PartialEq + Debug,
+{
+    let expected = routine(&mut BufReader::new(source())).map_err(|err| err.kind());
+    let (mut reader, _interruptor) = pair(source());
+    let seen = routine(&mut reader).map_err(|err| err.kind());
+
+    assert_eq!(seen, expected);
+    seen.expect("the routine failed on both readers")
+}
+
+fn cursor(data: &'static [u8]) -> impl Fn() -> Cursor<Vec<u8>> {
+    move || Cursor::new(data.to_vec())
+}
+
+/// A source that ends, and then returns more data.
+fn resumed() -> ScriptedReader {
+    ScriptedReader::new(vec![
+        Step::Data(b"before".to_vec()),
+        Step::Eof,
+        Step::Data(b"after".to_vec()),
+    ])
+}
+
+#[test]
+fn empty_buffer_reads_nothing() {
+    let seen = conform(cursor(b"hello"), |reader| {
+        let empty = reader.read(&mut [])?;
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest)?;
+        Ok((empty, rest))
+    });
+
+    assert_eq!(seen, (0, String::from("hello")));
+}
+
+#[test]
+fn lines_and_exact_reads() {
+    let seen = conform(cursor(b"first line\nabcdthe rest"), |reader| {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let mut buf = [0; 4];
+        reader.read_exact(&mut buf)?;
+        let mut until = Vec::new();
+        reader.read_until(b' ', &mut until)?;
+        Ok((line, buf, until))
+    });
+
+    assert_eq!(
+        seen,
+        (String::from("first line\n"), *b"abcd", b"the ".to_vec())
+    );
+}
+
+#[test]
+fn consuming_too_much_consumes_what_was_available() {
+    let seen = conform(cursor(b"hello world"), |reader| {
+        reader.consume(5);
+        let available = reader.fill_buf()?.to_vec();
+        reader.consume(available.len() + 100);
+        let after = reader.fill_buf()?.to_vec();
+        Ok((available, after))
+    });
+
+    assert_eq!(seen, (b"hello world".to_vec(), Vec::new()));
+}
+
+#[test]
+fn end_of_stream_consumes_nothing() {
+    let seen = conform(cursor(b"hello"), |reader| {
+        reader.read_to_end(&mut Vec::new())?;
+
+        let mut seen = Vec::new();
+        for _ in 0..2 {
+            seen.push(reader.fill_buf()?.len());
+            reader.consume(1);
+            seen.push(reader.read(&mut [0; 4])?);
+            seen.push(reader.read_line(&mut String::new())?);
+            seen.push(reader.read_until(b'\n', &mut Vec::new())?);
+        }
+        Ok(seen)
+    });
+
+    assert_eq!(seen, [0; 8]);
+}
+
+#[test]
+fn end_of_stream_followed_by_data() {
+    let seen = conform(resumed, |reader| {
+        let mut reads = Vec::new();
+        while reads.len() < 2 || reads[reads.len() - 2..] != [0, 0] {
+            reads.push(reader.read(&mut [0; 64])?);
+        }
+        Ok(reads)
+    });
+
+    assert_eq!(seen, [6, 0, 5, 0, 0]);
+}
+
+#[test]
+fn empty_chunk_through_fill_buf() {
+    // The `Ok(0)` of the source reaches the `InterruptReader` as a
+    // chunk of its own, without any bytes in it.
+    let seen = conform(resumed, |reader| {
+        let mut seen = Vec::new();
+        seen.push(reader.fill_buf()?.to_vec());
+        reader.consume(6);
+
+        // Consuming from the empty chunk doesn't reach into the next.
+        seen.push(reader.fill_buf()?.to_vec());
+        reader.consume(3);
+
+        seen.push(reader.fill_buf()?.to_vec());
+        reader.consume(2);
+        seen.push(reader.fill_buf()?.to_vec());
+        reader.consume(3);
+        seen.push(reader.fill_buf()?.to_vec());
+        Ok(seen)
+    });
+
+    let expected: [&[u8]; 5] = [b"before", b"", b"after", b"ter", b""];
+    assert_eq!(seen, expected);
+}
+
+#[test]
+fn errors_consume_nothing() {
+    let script = || {
+        ScriptedReader::new(vec![
+            Step::Data(b"hello".to_vec()),
+            Step::Error(ErrorKind::ConnectionReset),
+            Step::Data(b" world".to_vec()),
+        ])
+    };
+    let seen = conform(script, |reader| {
+        let mut out = Vec::new();
+        let kind = reader.read_to_end(&mut out).unwrap_err().kind();
+        reader.read_to_end(&mut out)?;
+        Ok((kind, out))
+    });
+
+    assert_eq!(seen, (ErrorKind::ConnectionReset, b"hello world".to_vec()));
+}